@@ -0,0 +1,57 @@
+//! Compares writing many small pieces of text directly to the console with writing them through a
+//! `ConsoleWriter`, which combines them into fewer `WriteConsoleW` calls.
+
+#[cfg(windows)]
+use std::io::Write;
+
+#[cfg(windows)]
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(windows)]
+use crossterm_winapi::{Console, ConsoleWriter};
+
+#[cfg(windows)]
+const LINES: usize = 200;
+
+#[cfg(windows)]
+fn write_lines(c: &mut Criterion) {
+    let console = Console::output().unwrap();
+    let mut group = c.benchmark_group("write_lines");
+
+    group.bench_function("unbuffered", |b| {
+        b.iter(|| {
+            for i in 0..LINES {
+                console
+                    .write_char_buffer(format!("line {}\n", i).as_bytes())
+                    .unwrap();
+            }
+        })
+    });
+
+    for capacity in [1024, 8 * 1024, 64 * 1024] {
+        group.bench_with_input(
+            BenchmarkId::new("console_writer", capacity),
+            &capacity,
+            |b, &capacity| {
+                b.iter(|| {
+                    let mut writer = ConsoleWriter::with_capacity(console.clone(), capacity);
+                    for i in 0..LINES {
+                        writeln!(writer, "line {}", i).unwrap();
+                    }
+                    writer.flush().unwrap();
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+#[cfg(windows)]
+criterion_group!(benches, write_lines);
+#[cfg(windows)]
+criterion_main!(benches);
+
+#[cfg(not(windows))]
+fn main() {
+    println!("This benchmark is for the Windows platform only.");
+}
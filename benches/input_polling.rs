@@ -0,0 +1,65 @@
+//! Measures how long it takes to notice input written by another thread with
+//! `Console::spin_wait_for_input`, for several spin durations. A spin of zero is a plain blocking
+//! wait.
+
+#[cfg(windows)]
+use std::slice;
+#[cfg(windows)]
+use std::sync::mpsc;
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::Duration;
+
+#[cfg(windows)]
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(windows)]
+use crossterm_winapi::{Console, HandleType, InputRecord, KeyEventRecord};
+
+#[cfg(windows)]
+fn input_latency(c: &mut Criterion) {
+    let console = Console::from_handle_type(HandleType::CurrentInputHandle).unwrap();
+    let key = InputRecord::KeyEvent(KeyEventRecord::builder().char('x').build());
+
+    // A helper thread writes a record whenever it is asked to, so the wait starts before the
+    // input arrives.
+    let (request, requests) = mpsc::channel::<()>();
+    let writer = {
+        let console = console.clone();
+        thread::spawn(move || {
+            for () in requests {
+                console.write_input(slice::from_ref(&key)).unwrap();
+            }
+        })
+    };
+
+    let mut group = c.benchmark_group("input_latency");
+    for spin_us in [0, 20, 100, 500] {
+        group.bench_with_input(
+            BenchmarkId::new("spin_us", spin_us),
+            &spin_us,
+            |b, &spin_us| {
+                b.iter(|| {
+                    request.send(()).unwrap();
+                    let spin = Duration::from_micros(spin_us);
+                    assert!(console.spin_wait_for_input(spin, true).unwrap());
+                    console.read_console_input().unwrap();
+                })
+            },
+        );
+    }
+    group.finish();
+
+    drop(request);
+    writer.join().unwrap();
+}
+
+#[cfg(windows)]
+criterion_group!(benches, input_latency);
+#[cfg(windows)]
+criterion_main!(benches);
+
+#[cfg(not(windows))]
+fn main() {
+    println!("This benchmark is for the Windows platform only.");
+}
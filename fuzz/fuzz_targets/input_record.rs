@@ -0,0 +1,23 @@
+//! Round-trips arbitrary records through the `INPUT_RECORD` union.
+//!
+//! Run with `cargo +nightly fuzz run input_record` on Windows.
+
+#![no_main]
+
+use crossterm_winapi::InputRecord;
+use libfuzzer_sys::fuzz_target;
+use winapi::um::wincon::{INPUT_RECORD, WINDOW_BUFFER_SIZE_EVENT};
+
+fuzz_target!(|record: InputRecord| {
+    let raw = INPUT_RECORD::from(record.clone());
+    match record {
+        // Reading a resize record queries the window size of the console, so only the raw
+        // record is checked.
+        InputRecord::WindowBufferSizeEvent(resize) => {
+            assert_eq!(raw.EventType, WINDOW_BUFFER_SIZE_EVENT);
+            let size = unsafe { raw.Event.WindowBufferSizeEvent() }.dwSize;
+            assert_eq!((size.X, size.Y), (resize.buffer_size.x, resize.buffer_size.y));
+        }
+        record => assert_eq!(InputRecord::try_from_winapi(raw).unwrap(), record),
+    }
+});
@@ -0,0 +1,196 @@
+//! This module contains functions to read the console aliases, the macros defined with
+//! `doskey` for each executable.
+//!
+//! The names are returned as [`OsString`]s, as the console doesn't require them to be valid
+//! UTF-16.
+
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Result};
+use std::mem::size_of;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::ptr;
+
+use winapi::um::wincon::{
+    GetConsoleAliasExesLengthW, GetConsoleAliasExesW, GetConsoleAliasesLengthW, GetConsoleAliasesW,
+};
+
+use super::trace;
+
+/// An alias defined for an executable, see [`aliases_for`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// The text that is replaced, the name of the macro.
+    pub source: OsString,
+    /// The text it is replaced with.
+    pub target: OsString,
+}
+
+/// The executables that have aliases defined, see [`alias_exes`].
+#[derive(Debug, Clone)]
+pub struct AliasExes {
+    entries: Entries,
+}
+
+impl Iterator for AliasExes {
+    type Item = OsString;
+
+    fn next(&mut self) -> Option<OsString> {
+        self.entries.next().map(OsString::from_wide)
+    }
+}
+
+/// The aliases of an executable, see [`aliases_for`].
+#[derive(Debug, Clone)]
+pub struct Aliases {
+    entries: Entries,
+}
+
+impl Iterator for Aliases {
+    type Item = Alias;
+
+    fn next(&mut self) -> Option<Alias> {
+        let entry = self.entries.next()?;
+        // The source can't contain `=`, the target can.
+        let split = entry
+            .iter()
+            .position(|&unit| unit == u16::from(b'='))
+            .unwrap_or(entry.len());
+        Some(Alias {
+            source: OsString::from_wide(&entry[..split]),
+            target: OsString::from_wide(entry.get(split + 1..).unwrap_or_default()),
+        })
+    }
+}
+
+/// Get the executables that have aliases defined in the console.
+///
+/// This wraps
+/// [`GetConsoleAliasExesW`](https://docs.microsoft.com/en-us/windows/console/getconsolealiasexes).
+pub fn alias_exes() -> Result<AliasExes> {
+    let bytes = trace::call("GetConsoleAliasExesLengthW", ptr::null_mut(), || unsafe {
+        GetConsoleAliasExesLengthW()
+    });
+    let units = read_entries(bytes, |buffer, bytes| {
+        trace::call("GetConsoleAliasExesW", ptr::null_mut(), || unsafe {
+            GetConsoleAliasExesW(buffer, bytes)
+        })
+    })?;
+    Ok(AliasExes {
+        entries: Entries::new(units),
+    })
+}
+
+/// Get the aliases defined in the console for the executable `exe`, like `cmd.exe`.
+///
+/// This wraps
+/// [`GetConsoleAliasesW`](https://docs.microsoft.com/en-us/windows/console/getconsolealiases).
+pub fn aliases_for(exe: impl AsRef<OsStr>) -> Result<Aliases> {
+    let mut exe: Vec<u16> = exe.as_ref().encode_wide().chain(Some(0)).collect();
+    let exe = exe.as_mut_ptr();
+    let bytes = trace::call("GetConsoleAliasesLengthW", ptr::null_mut(), || unsafe {
+        GetConsoleAliasesLengthW(exe)
+    });
+    let units = read_entries(bytes, |buffer, bytes| {
+        trace::call("GetConsoleAliasesW", ptr::null_mut(), || unsafe {
+            GetConsoleAliasesW(buffer, bytes, exe)
+        })
+    })?;
+    Ok(Aliases {
+        entries: Entries::new(units),
+    })
+}
+
+/// Read a buffer of NUL separated entries that is `bytes` long with `read`, which gets the
+/// buffer and its length in bytes.
+fn read_entries(bytes: u32, read: impl FnOnce(*mut u16, u32) -> u32) -> Result<Vec<u16>> {
+    if bytes == 0 {
+        return Ok(Vec::new());
+    }
+    // The lengths of the alias functions are in bytes, not in characters.
+    let mut units = vec![0u16; bytes as usize / size_of::<u16>()];
+    if read(units.as_mut_ptr(), bytes) == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(units)
+}
+
+/// Iterates over the NUL separated entries of a buffer.
+#[derive(Debug, Clone)]
+struct Entries {
+    units: Vec<u16>,
+    position: usize,
+}
+
+impl Entries {
+    fn new(units: Vec<u16>) -> Entries {
+        Entries { units, position: 0 }
+    }
+
+    fn next(&mut self) -> Option<&[u16]> {
+        let rest = &self.units[self.position.min(self.units.len())..];
+        let len = rest
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(rest.len());
+        if len == 0 {
+            // An empty entry ends the list, like the double NUL at the end of the buffer.
+            self.position = self.units.len();
+            return None;
+        }
+        let start = self.position;
+        self.position += len + 1;
+        Some(&self.units[start..start + len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStrExt;
+
+    use super::{Alias, AliasExes, Aliases, Entries};
+
+    fn units(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn test_alias_exes() {
+        let exes = AliasExes {
+            entries: Entries::new(units("cmd.exe\0python.exe\0\0\0")),
+        };
+        assert_eq!(exes.collect::<Vec<_>>(), ["cmd.exe", "python.exe"]);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let aliases = Aliases {
+            entries: Entries::new(units("ls=dir $*\0eq=a=b\0")),
+        };
+        assert_eq!(
+            aliases.collect::<Vec<_>>(),
+            [
+                Alias {
+                    source: OsString::from("ls"),
+                    target: OsString::from("dir $*"),
+                },
+                Alias {
+                    source: OsString::from("eq"),
+                    target: OsString::from("a=b"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unpaired_surrogate_is_kept() {
+        let mut exes = AliasExes {
+            entries: Entries::new(vec![0xD800, u16::from(b'x')]),
+        };
+        assert_eq!(
+            exes.next().map(|exe| exe.encode_wide().collect::<Vec<_>>()),
+            Some(vec![0xD800, u16::from(b'x')])
+        );
+        assert_eq!(exes.next(), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! This module combines Alt+numpad character entry into one key event for
+//! [`Events`](crate::Events).
+
+use winapi::um::wincon::{
+    ENHANCED_KEY, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED,
+};
+use winapi::um::winuser::{
+    VK_CLEAR, VK_DOWN, VK_END, VK_HOME, VK_INSERT, VK_LEFT, VK_MENU, VK_NEXT, VK_NUMPAD0,
+    VK_NUMPAD9, VK_PRIOR, VK_RIGHT, VK_UP,
+};
+
+use super::{InputRecord, KeyEventRecord};
+
+/// What became of a record fed to the [`AltNumpadDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Decoded {
+    /// The record is reported as is.
+    Record(InputRecord),
+    /// The record may be a digit of an Alt+numpad entry and is held back.
+    Digit,
+    /// The held back digits weren't an entry after all: they are reported, followed by the
+    /// record that ended them.
+    Replay(Vec<InputRecord>),
+    /// The Alt key was released at the end of an entry: the character it produced, as a key down
+    /// record, and the release of the Alt key without the character.
+    Char(KeyEventRecord, InputRecord),
+}
+
+/// Recognizes Alt+numpad entry, where the user holds Alt, types the code of a character on the
+/// numeric keypad and releases Alt.
+///
+/// The console reports the digits as separate key events with no character and only puts the
+/// character in the release of the Alt key, which readers that drop key up records never see.
+///
+/// With Num Lock off the digits are the navigation keys of the keypad, so Alt+Home could be the
+/// start of an entry or a shortcut. The digits are held back until Alt is released, and
+/// reported after all if the release carries no character or another key ends the entry.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AltNumpadDecoder {
+    digits: Vec<InputRecord>,
+}
+
+impl AltNumpadDecoder {
+    pub(crate) fn process(&mut self, record: InputRecord) -> Decoded {
+        let key = match &record {
+            InputRecord::KeyEvent(key) => key,
+            _ => return Decoded::Record(record),
+        };
+
+        if key.virtual_key_code == VK_MENU as u16 {
+            if key.key_down {
+                return Decoded::Record(record);
+            }
+            if self.digits.is_empty() {
+                return Decoded::Record(record);
+            }
+            if key.u_char == 0 {
+                return self.replay(record);
+            }
+            self.digits.clear();
+            let character = KeyEventRecord::builder().u_char(key.u_char).build();
+            let release = KeyEventRecord {
+                u_char: 0,
+                ..key.clone()
+            };
+            return Decoded::Char(character, InputRecord::KeyEvent(release));
+        }
+
+        if is_numpad_digit(key) && only_alt_pressed(key) {
+            self.digits.push(record);
+            return Decoded::Digit;
+        }
+        if self.digits.is_empty() {
+            return Decoded::Record(record);
+        }
+        self.replay(record)
+    }
+
+    /// Take the held back digits, to report them when decoding is turned off.
+    pub(crate) fn take_digits(&mut self) -> Vec<InputRecord> {
+        std::mem::take(&mut self.digits)
+    }
+
+    /// Report the held back digits followed by `record`.
+    fn replay(&mut self, record: InputRecord) -> Decoded {
+        let mut records = std::mem::take(&mut self.digits);
+        records.push(record);
+        Decoded::Replay(records)
+    }
+}
+
+/// Returns whether `key` is a digit on the numeric keypad, with Num Lock on or off.
+fn is_numpad_digit(key: &KeyEventRecord) -> bool {
+    let code = i32::from(key.virtual_key_code);
+    if (VK_NUMPAD0..=VK_NUMPAD9).contains(&code) {
+        return true;
+    }
+    // With Num Lock off the keypad reports the navigation keys, which are only enhanced when
+    // they come from the dedicated keys.
+    !key.control_key_state.has_state(ENHANCED_KEY)
+        && [
+            VK_INSERT, VK_END, VK_DOWN, VK_NEXT, VK_LEFT, VK_CLEAR, VK_RIGHT, VK_HOME, VK_UP,
+            VK_PRIOR,
+        ]
+        .contains(&code)
+}
+
+/// Returns whether Alt is held without Ctrl, which would make it AltGr.
+fn only_alt_pressed(key: &KeyEventRecord) -> bool {
+    key.control_key_state
+        .has_state(LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED)
+        && !key
+            .control_key_state
+            .has_state(LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AltNumpadDecoder, Decoded};
+    use crate::{InputRecord, KeyEventRecord};
+
+    const VK_MENU: u16 = 0x12;
+    const VK_NUMPAD1: u16 = 0x61;
+    const VK_END: u16 = 0x23;
+
+    fn key(virtual_key_code: u16, key_down: bool, u_char: u16) -> InputRecord {
+        InputRecord::KeyEvent(
+            KeyEventRecord::builder()
+                .virtual_key_code(virtual_key_code)
+                .key_down(key_down)
+                .u_char(u_char)
+                .alt()
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_decode_entry() {
+        let mut decoder = AltNumpadDecoder::default();
+        let alt_down = key(VK_MENU, true, 0);
+        assert_eq!(decoder.process(alt_down.clone()), Decoded::Record(alt_down));
+        // Alt+0233 is é, with Num Lock off the 1 is reported as End.
+        for code in [VK_NUMPAD1, VK_END] {
+            assert_eq!(decoder.process(key(code, true, 0)), Decoded::Digit);
+            assert_eq!(decoder.process(key(code, false, 0)), Decoded::Digit);
+        }
+        assert_eq!(
+            decoder.process(key(VK_MENU, false, 0xE9)),
+            Decoded::Char(
+                KeyEventRecord::builder().char('é').build(),
+                key(VK_MENU, false, 0)
+            )
+        );
+    }
+
+    #[test]
+    fn test_plain_alt_release() {
+        let mut decoder = AltNumpadDecoder::default();
+        let alt_up = key(VK_MENU, false, 0x61);
+        assert_eq!(decoder.process(alt_up.clone()), Decoded::Record(alt_up));
+
+        // Another key aborts the entry, the digit is reported after all.
+        let digit = key(VK_NUMPAD1, true, 0);
+        assert_eq!(decoder.process(digit.clone()), Decoded::Digit);
+        let a = key(0x41, true, 0x61);
+        assert_eq!(decoder.process(a.clone()), Decoded::Replay(vec![digit, a]));
+        let alt_up = key(VK_MENU, false, 0x61);
+        assert_eq!(decoder.process(alt_up.clone()), Decoded::Record(alt_up));
+    }
+
+    #[test]
+    fn test_navigation_is_replayed() {
+        // Alt+End with Num Lock off, released without producing a character.
+        let mut decoder = AltNumpadDecoder::default();
+        let end_down = key(VK_END, true, 0);
+        let end_up = key(VK_END, false, 0);
+        assert_eq!(decoder.process(end_down.clone()), Decoded::Digit);
+        assert_eq!(decoder.process(end_up.clone()), Decoded::Digit);
+        let alt_up = key(VK_MENU, false, 0);
+        assert_eq!(
+            decoder.process(alt_up.clone()),
+            Decoded::Replay(vec![end_down, end_up, alt_up])
+        );
+    }
+}
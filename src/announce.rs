@@ -0,0 +1,150 @@
+//! This module contains the UI Automation notifications used by
+//! [`Console::announce`](crate::Console::announce).
+
+use std::io::{self, Result};
+use std::mem;
+use std::ptr;
+
+use winapi::shared::windef::HWND;
+use winapi::um::{
+    libloaderapi::LoadLibraryW,
+    oleauto::{SysAllocString, SysFreeString},
+    unknwnbase::IUnknown,
+    winnt::HANDLE,
+};
+
+use super::{capability::proc_address, trace, ErrorContext};
+
+/// How urgently a screen reader should announce a text, see
+/// [`Console::announce`](crate::Console::announce).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnouncePriority {
+    /// Announce the text after everything that is queued, like a status update.
+    Normal,
+    /// Announce the text after everything that is queued, but even if the user silenced
+    /// regular announcements.
+    Important,
+    /// Interrupt the current announcement and drop the queued ones, for example for errors.
+    Interrupt,
+}
+
+impl AnnouncePriority {
+    /// The `NotificationProcessing` value of the priority.
+    fn processing(self) -> i32 {
+        match self {
+            // NotificationProcessing_All
+            AnnouncePriority::Normal => 2,
+            // NotificationProcessing_ImportantAll
+            AnnouncePriority::Important => 0,
+            // NotificationProcessing_ImportantMostRecent
+            AnnouncePriority::Interrupt => 1,
+        }
+    }
+}
+
+/// `NotificationKind_Other`.
+const NOTIFICATION_KIND_OTHER: i32 = 4;
+
+type UiaHostProviderFromHwnd = unsafe extern "system" fn(HWND, *mut *mut IUnknown) -> i32;
+type UiaRaiseNotificationEvent =
+    unsafe extern "system" fn(*mut IUnknown, i32, i32, *mut u16, *mut u16) -> i32;
+
+/// Raise a UI Automation notification with `text` for the window `hwnd`.
+///
+/// This wraps
+/// [`UiaRaiseNotificationEvent`](https://docs.microsoft.com/en-us/windows/win32/api/uiautomationcoreapi/nf-uiautomationcoreapi-uiaraisenotificationevent),
+/// which is loaded from `UIAutomationCore.dll` at runtime because it is only available since
+/// Windows 10 1709.
+pub(crate) fn announce(hwnd: HWND, text: &str, priority: AnnouncePriority) -> Result<()> {
+    // The library is never freed, loading it again is cheap. Once loaded `proc_address` finds it.
+    let module: Vec<u16> = "UIAutomationCore.dll\0".encode_utf16().collect();
+    let module: HANDLE = trace::call("LoadLibraryW", ptr::null_mut(), || unsafe {
+        LoadLibraryW(module.as_ptr()).cast()
+    });
+    if module.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    let functions = (
+        proc_address("UIAutomationCore.dll\0", "UiaHostProviderFromHwnd\0"),
+        proc_address("UIAutomationCore.dll\0", "UiaRaiseNotificationEvent\0"),
+    );
+    let (host_provider, raise) = match functions {
+        (Some(host_provider), Some(raise)) => (host_provider, raise),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "UI Automation notifications are not available",
+            ))
+        }
+    };
+    let host_provider: UiaHostProviderFromHwnd = unsafe { mem::transmute(host_provider) };
+    let raise: UiaRaiseNotificationEvent = unsafe { mem::transmute(raise) };
+
+    let mut provider: *mut IUnknown = ptr::null_mut();
+    hresult(trace::call(
+        "UiaHostProviderFromHwnd",
+        ptr::null_mut(),
+        || unsafe { host_provider(hwnd, &mut provider) },
+    ))
+    .with_context("UiaHostProviderFromHwnd")?;
+
+    let text = Bstr::new(text);
+    let activity = Bstr::new("crossterm_winapi.announce");
+    let raised = hresult(trace::call(
+        "UiaRaiseNotificationEvent",
+        ptr::null_mut(),
+        || unsafe {
+            raise(
+                provider,
+                NOTIFICATION_KIND_OTHER,
+                priority.processing(),
+                text.0,
+                activity.0,
+            )
+        },
+    ))
+    .with_context("UiaRaiseNotificationEvent");
+    unsafe { (*provider).Release() };
+    raised
+}
+
+fn hresult(hr: i32) -> Result<()> {
+    if hr >= 0 {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(hr))
+    }
+}
+
+/// A `BSTR` that is freed on drop.
+struct Bstr(*mut u16);
+
+impl Bstr {
+    fn new(s: &str) -> Bstr {
+        let wide: Vec<u16> = s.encode_utf16().chain(Some(0)).collect();
+        let bstr: HANDLE = trace::call("SysAllocString", ptr::null_mut(), || unsafe {
+            SysAllocString(wide.as_ptr()).cast()
+        });
+        Bstr(bstr.cast())
+    }
+}
+
+impl Drop for Bstr {
+    fn drop(&mut self) {
+        trace::call("SysFreeString", ptr::null_mut(), || unsafe {
+            SysFreeString(self.0)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnnouncePriority;
+
+    #[test]
+    fn test_processing() {
+        assert_eq!(AnnouncePriority::Normal.processing(), 2);
+        assert_eq!(AnnouncePriority::Important.processing(), 0);
+        assert_eq!(AnnouncePriority::Interrupt.processing(), 1);
+    }
+}
@@ -0,0 +1,231 @@
+//! This module contains tokio adapters that read from and write to a console on the blocking
+//! thread pool.
+
+use std::char;
+use std::future::Future;
+use std::io::{self, Result, Write};
+use std::pin::Pin;
+use std::ptr;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::task::{spawn_blocking, JoinHandle};
+use winapi::um::consoleapi::ReadConsoleW;
+
+use super::{result, trace, Console, ConsoleWriter, ErrorContext};
+
+/// The number of UTF-16 code units read from the console at once.
+const READ_UNITS: usize = 4096;
+
+/// Reads text from a console input buffer as UTF-8, implementing [`AsyncRead`].
+///
+/// Every read runs `ReadConsoleW` on tokio's blocking thread pool, so this has to be used from
+/// within a tokio runtime. Like a synchronous read, it returns what the console host hands out,
+/// which is a line at a time unless line input is disabled with the console mode.
+///
+/// A read that is in progress can't be cancelled, dropping the reader leaves it running until
+/// the console has input.
+#[derive(Debug)]
+pub struct AsyncConsoleReader {
+    console: Console,
+    pending: Vec<u8>,
+    offset: usize,
+    state: ReadState,
+}
+
+#[derive(Debug)]
+enum ReadState {
+    /// No read is running, holding the high surrogate that ended the previous read, if any.
+    Idle(Option<u16>),
+    Busy(JoinHandle<Result<(String, Option<u16>)>>),
+}
+
+impl AsyncConsoleReader {
+    /// Create a reader for the given console input buffer.
+    pub fn new(console: Console) -> AsyncConsoleReader {
+        AsyncConsoleReader {
+            console,
+            pending: Vec::new(),
+            offset: 0,
+            state: ReadState::Idle(None),
+        }
+    }
+
+    /// Get the console this reader reads from.
+    pub fn get_ref(&self) -> &Console {
+        &self.console
+    }
+}
+
+impl AsyncRead for AsyncConsoleReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.offset < this.pending.len() {
+                let n = buf.remaining().min(this.pending.len() - this.offset);
+                buf.put_slice(&this.pending[this.offset..this.offset + n]);
+                this.offset += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            match &mut this.state {
+                ReadState::Idle(high) => {
+                    let high = high.take();
+                    let console = this.console.clone();
+                    this.state = ReadState::Busy(spawn_blocking(move || read_text(&console, high)));
+                }
+                ReadState::Busy(task) => {
+                    let (text, high) = ready!(Pin::new(task).poll(cx)).map_err(join_error)??;
+                    let eof = text.is_empty() && high.is_none();
+                    this.state = ReadState::Idle(high);
+                    this.pending = text.into_bytes();
+                    this.offset = 0;
+                    if eof {
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Writes UTF-8 text to a console screen buffer, implementing [`AsyncWrite`].
+///
+/// Writes are copied and handed to a [`ConsoleWriter`] on tokio's blocking thread pool, so this
+/// has to be used from within a tokio runtime. A write returns as soon as the bytes are copied,
+/// an error writing them is returned by the next write or flush.
+#[derive(Debug)]
+pub struct AsyncConsoleWriter {
+    state: WriteState,
+}
+
+#[derive(Debug)]
+enum WriteState {
+    Idle(Option<ConsoleWriter>),
+    Busy(JoinHandle<(Result<()>, ConsoleWriter)>),
+}
+
+impl AsyncConsoleWriter {
+    /// Create a writer for the given console screen buffer.
+    pub fn new(console: Console) -> AsyncConsoleWriter {
+        AsyncConsoleWriter {
+            // Without buffering, the writer only holds back characters split across writes.
+            state: WriteState::Idle(Some(ConsoleWriter::with_capacity(console, 0))),
+        }
+    }
+
+    /// Wait for the write in progress to finish, returning its result.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<Result<&mut ConsoleWriter>> {
+        if let WriteState::Busy(task) = &mut self.state {
+            let (result, writer) = ready!(Pin::new(task).poll(cx)).map_err(join_error)?;
+            self.state = WriteState::Idle(Some(writer));
+            result?;
+        }
+        match &mut self.state {
+            WriteState::Idle(Some(writer)) => Poll::Ready(Ok(writer)),
+            _ => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "the console writer was lost by a failed write",
+            ))),
+        }
+    }
+}
+
+impl AsyncWrite for AsyncConsoleWriter {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_idle(cx))?;
+        let mut writer = match &mut this.state {
+            WriteState::Idle(writer) => writer.take().expect("poll_idle returned a writer"),
+            WriteState::Busy(_) => unreachable!("poll_idle waits for the write in progress"),
+        };
+
+        let data = buf.to_vec();
+        this.state = WriteState::Busy(spawn_blocking(move || {
+            let result = writer.write_all(&data);
+            (result, writer)
+        }));
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_idle(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+/// Read up to [`READ_UNITS`] code units from the console, continuing a surrogate pair if the
+/// previous read ended with a high surrogate.
+///
+/// This wraps [`ReadConsoleW`](https://docs.microsoft.com/en-us/windows/console/readconsole).
+fn read_text(console: &Console, high: Option<u16>) -> Result<(String, Option<u16>)> {
+    let mut units = vec![0u16; READ_UNITS];
+    let start = match high {
+        Some(high) => {
+            units[0] = high;
+            1
+        }
+        None => 0,
+    };
+
+    let mut read = 0;
+    let handle = **console.handle();
+    result(trace::call("ReadConsoleW", handle, || unsafe {
+        ReadConsoleW(
+            handle,
+            units[start..].as_mut_ptr().cast(),
+            (units.len() - start) as u32,
+            &mut read,
+            ptr::null_mut(),
+        )
+    }))
+    .with_handle_context("ReadConsoleW", console.handle())?;
+
+    let end = start + read as usize;
+    Ok(decode_units(&units[..end], read > 0))
+}
+
+/// Decode `units`, holding back a trailing high surrogate if `more` units may follow.
+fn decode_units(units: &[u16], more: bool) -> (String, Option<u16>) {
+    let (units, high) = match units.split_last() {
+        Some((&last, rest)) if more && (0xD800..0xDC00).contains(&last) => (rest, Some(last)),
+        _ => (units, None),
+    };
+    let text = char::decode_utf16(units.iter().copied())
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    (text, high)
+}
+
+fn join_error(e: tokio::task::JoinError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_units;
+
+    #[test]
+    fn test_decode_units() {
+        let units: Vec<u16> = "a😀".encode_utf16().collect();
+        assert_eq!(decode_units(&units, true), ("a😀".to_owned(), None));
+        assert_eq!(
+            decode_units(&units[..2], true),
+            ("a".to_owned(), Some(units[1]))
+        );
+        assert_eq!(
+            decode_units(&units[..2], false),
+            ("a\u{FFFD}".to_owned(), None)
+        );
+        assert_eq!(decode_units(&[], false), (String::new(), None));
+    }
+}
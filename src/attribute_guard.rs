@@ -0,0 +1,50 @@
+use std::io::Result;
+
+use winapi::um::wincon::SetConsoleTextAttribute;
+
+use super::{
+    result, screen_buffer::capture_default_attributes, trace, ErrorContext, Handle, ScreenBuffer,
+};
+
+/// Restores the text attributes of a screen buffer when dropped.
+///
+/// The guard is created with the attributes to apply, and remembers the attributes that were
+/// active before. Because the restore happens on drop, the previous attributes come back on an
+/// early return or a panic as well.
+///
+/// This wraps
+/// [`SetConsoleTextAttribute`](https://docs.microsoft.com/en-us/windows/console/setconsoletextattribute).
+#[derive(Debug)]
+pub struct AttributeGuard {
+    handle: Handle,
+    previous: u16,
+}
+
+impl AttributeGuard {
+    /// Apply `attributes` to the screen buffer, restoring the current attributes on drop.
+    pub fn new(screen_buffer: &ScreenBuffer, attributes: u16) -> Result<AttributeGuard> {
+        let previous = screen_buffer.info()?.attributes();
+        capture_default_attributes(screen_buffer.handle());
+        let handle = screen_buffer.handle().clone();
+        result(trace::call("SetConsoleTextAttribute", *handle, || unsafe {
+            SetConsoleTextAttribute(*handle, attributes)
+        }))
+        .with_handle_context("SetConsoleTextAttribute", &handle)?;
+
+        Ok(AttributeGuard { handle, previous })
+    }
+
+    /// Get the attributes that will be restored on drop.
+    pub fn previous(&self) -> u16 {
+        self.previous
+    }
+}
+
+impl Drop for AttributeGuard {
+    fn drop(&mut self) {
+        // There is nothing sensible to do with an error while dropping.
+        let _ = trace::call("SetConsoleTextAttribute", *self.handle, || unsafe {
+            SetConsoleTextAttribute(*self.handle, self.previous)
+        });
+    }
+}
@@ -0,0 +1,138 @@
+use std::char;
+use std::iter;
+
+use winapi::um::wincon::COMMON_LVB_TRAILING_BYTE;
+
+use super::{Coord, Size, WindowPositions};
+
+/// A copy of the characters and attributes of a region of a screen buffer.
+///
+/// This can be obtained with [`ScreenBuffer::snapshot`](crate::ScreenBuffer::snapshot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferSnapshot {
+    region: WindowPositions,
+    chars: Vec<u16>,
+    attributes: Vec<u16>,
+    text: String,
+    // The attributes of every run and the byte offset in `text` where it ends.
+    runs: Vec<(u16, usize)>,
+}
+
+impl BufferSnapshot {
+    /// Create a snapshot from the characters and attributes of the cells in `region`, row by row.
+    pub(crate) fn new(region: WindowPositions, chars: Vec<u16>, attributes: Vec<u16>) -> Self {
+        let width = (region.right - region.left + 1).max(0) as usize;
+        let mut text = String::with_capacity(chars.len());
+        let mut runs: Vec<(u16, usize)> = Vec::new();
+
+        for (row, (row_chars, row_attributes)) in chars
+            .chunks(width.max(1))
+            .zip(attributes.chunks(width.max(1)))
+            .enumerate()
+        {
+            if row > 0 {
+                text.push('\n');
+                if let Some(last) = runs.last_mut() {
+                    last.1 = text.len();
+                }
+            }
+
+            // The trailing half of a wide character repeats the character of the leading half.
+            let cells = row_chars
+                .iter()
+                .zip(row_attributes)
+                .filter(|(_, &attributes)| attributes & COMMON_LVB_TRAILING_BYTE == 0);
+            let mut units = cells.clone().map(|(&c, _)| c);
+            let mut attributes = cells.map(|(_, &a)| a);
+
+            for c in char::decode_utf16(iter::from_fn(|| units.next())) {
+                let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+                // A surrogate pair takes two cells, the attributes of the first one are used.
+                let cell_attributes = attributes.next().unwrap_or(0);
+                if c.len_utf16() == 2 {
+                    attributes.next();
+                }
+                text.push(c);
+                match runs.last_mut() {
+                    Some(last) if last.0 == cell_attributes => last.1 = text.len(),
+                    _ => runs.push((cell_attributes, text.len())),
+                }
+            }
+        }
+
+        BufferSnapshot {
+            region,
+            chars,
+            attributes,
+            text,
+            runs,
+        }
+    }
+
+    /// Get the region of the screen buffer this snapshot was taken of.
+    pub fn region(&self) -> WindowPositions {
+        self.region
+    }
+
+    /// Get the size of the snapshot in cells.
+    pub fn size(&self) -> Size {
+        Size::new(
+            self.region.right - self.region.left + 1,
+            self.region.bottom - self.region.top + 1,
+        )
+    }
+
+    /// Get the UTF-16 code unit and attributes of the cell at `coord`, relative to the top left of
+    /// the snapshot.
+    pub fn cell(&self, coord: Coord) -> Option<(u16, u16)> {
+        let size = self.size();
+        if coord.x < 0 || coord.y < 0 || coord.x >= size.width || coord.y >= size.height {
+            return None;
+        }
+        let index = coord.y as usize * size.width as usize + coord.x as usize;
+        Some((*self.chars.get(index)?, *self.attributes.get(index)?))
+    }
+
+    /// Get the text of the snapshot, with the rows separated by `\n`.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Iterate over the runs of text that share the same attributes.
+    ///
+    /// Runs can span multiple rows, the rows are separated by `\n` in the text. This makes it
+    /// cheap to convert the snapshot to text with escape sequences, as the attributes only have
+    /// to be translated once per run.
+    pub fn runs(&self) -> impl Iterator<Item = (u16, &str)> + '_ {
+        let starts = iter::once(0).chain(self.runs.iter().map(|(_, end)| *end));
+        self.runs
+            .iter()
+            .zip(starts)
+            .map(move |(&(attributes, end), start)| (attributes, &self.text[start..end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufferSnapshot;
+    use crate::WindowPositions;
+
+    #[test]
+    fn test_runs() {
+        let region = WindowPositions {
+            left: 0,
+            right: 2,
+            top: 0,
+            bottom: 1,
+        };
+        let chars = "abcdef".encode_utf16().collect();
+        let attributes = vec![7, 7, 1, 1, 7, 7];
+        let snapshot = BufferSnapshot::new(region, chars, attributes);
+
+        assert_eq!(snapshot.text(), "abc\ndef");
+        assert_eq!(
+            snapshot.runs().collect::<Vec<_>>(),
+            vec![(7, "ab"), (1, "c\nd"), (7, "ef")]
+        );
+    }
+}
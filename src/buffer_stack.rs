@@ -0,0 +1,94 @@
+use std::io::Result;
+
+use super::ScreenBuffer;
+
+/// A stack of screen buffers for nested full screen modes.
+///
+/// The bottom of the stack is the screen buffer that was active when the stack was created.
+/// [`push_new`](BufferStack::push_new) creates a screen buffer and makes it active,
+/// [`pop`](BufferStack::pop) closes the top buffer and makes the one below it active again.
+/// The stack owns its buffers, so they are always closed from the top down. When the stack is
+/// dropped, all pushed buffers are popped and the original screen buffer is active again.
+///
+/// ```no_run
+/// # use crossterm_winapi::BufferStack;
+/// # fn main() -> std::io::Result<()> {
+/// let mut stack = BufferStack::new()?;
+/// let editor = stack.push_new()?;
+/// // draw the editor...
+/// let help = stack.push_new()?;
+/// // draw the help screen...
+/// stack.pop()?; // back to the editor
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BufferStack {
+    base: ScreenBuffer,
+    buffers: Vec<ScreenBuffer>,
+}
+
+impl BufferStack {
+    /// Create a stack on top of the currently active screen buffer.
+    pub fn new() -> Result<BufferStack> {
+        Ok(BufferStack::with_base(ScreenBuffer::current()?))
+    }
+
+    /// Create a stack on top of the given screen buffer.
+    pub fn with_base(base: ScreenBuffer) -> BufferStack {
+        BufferStack {
+            base,
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Create a new screen buffer, make it active and push it on the stack.
+    pub fn push_new(&mut self) -> Result<&ScreenBuffer> {
+        let buffer = ScreenBuffer::create()?;
+        buffer.show()?;
+        self.buffers.push(buffer);
+        Ok(self.top())
+    }
+
+    /// Close the top screen buffer and make the one below it active.
+    ///
+    /// Returns `false` if only the base buffer is left, which is never popped. If the buffer
+    /// below can't be made active, the top buffer stays on the stack.
+    pub fn pop(&mut self) -> Result<bool> {
+        if self.buffers.is_empty() {
+            return Ok(false);
+        }
+        let below = match self.buffers.len() {
+            1 => &self.base,
+            len => &self.buffers[len - 2],
+        };
+        below.show()?;
+        self.buffers.pop();
+        Ok(true)
+    }
+
+    /// Get the active screen buffer, the top of the stack.
+    pub fn top(&self) -> &ScreenBuffer {
+        self.buffers.last().unwrap_or(&self.base)
+    }
+
+    /// Get the screen buffer at the bottom of the stack.
+    pub fn base(&self) -> &ScreenBuffer {
+        &self.base
+    }
+
+    /// Get the number of pushed screen buffers, not counting the base.
+    pub fn depth(&self) -> usize {
+        self.buffers.len()
+    }
+}
+
+impl Drop for BufferStack {
+    fn drop(&mut self) {
+        if !self.buffers.is_empty() {
+            let _ = self.base.show();
+        }
+        // Close the buffers from the top down.
+        while self.buffers.pop().is_some() {}
+    }
+}
@@ -0,0 +1,246 @@
+use std::io::Result;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{
+    BufferSnapshot, ClearType, Coord, FontInfo, Handle, InputRecord, ScreenBuffer,
+    ScreenBufferInfo, ScreenBufferInfoEx, SizeReport, SizeWatcher, TextAttributes, WindowPositions,
+};
+
+/// A [`ScreenBuffer`] that caches the result of [`info`](CachedScreenBuffer::info).
+///
+/// Render loops query the size of the window and the buffer every frame, which makes
+/// `GetConsoleScreenBufferInfo` a hot call. This wrapper queries it once and returns the cached
+/// information until it is invalidated:
+///
+/// - by the methods that change the buffer or its window through this wrapper, like
+///   [`set_size`](CachedScreenBuffer::set_size),
+/// - by a resize passed to [`observe`](CachedScreenBuffer::observe),
+/// - or explicitly with [`invalidate`](CachedScreenBuffer::invalidate).
+///
+/// The cache doesn't notice other changes. Writing text moves the cursor and changing the
+/// attributes through a [`Console`](crate::Console) changes them in the information, so
+/// invalidate the cache after those when the cursor position or attributes are needed.
+///
+/// The legacy console host only reports a resize when the size of the buffer changes. When the
+/// user resizes just the window, for example a window smaller than a buffer with scrollback, no
+/// record arrives, so invalidate the cache periodically there if the window size matters.
+///
+/// The other methods of [`ScreenBuffer`] are wrapped as well, those that change the information
+/// invalidate the cache. Use [`screen_buffer`](CachedScreenBuffer::screen_buffer) for calls that
+/// should bypass it.
+///
+/// ```no_run
+/// # use crossterm_winapi::{CachedScreenBuffer, Event, Events, Handle, ScreenBuffer};
+/// # fn main() -> std::io::Result<()> {
+/// let screen_buffer = CachedScreenBuffer::new(ScreenBuffer::current()?);
+/// let mut events = Events::new(Handle::current_in_handle()?)?;
+/// loop {
+///     if let Event::Input(record) = events.read()? {
+///         screen_buffer.observe(&record);
+///     }
+///     let size = screen_buffer.info()?.terminal_size();
+///     // Render with `size`.
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CachedScreenBuffer {
+    screen_buffer: ScreenBuffer,
+    info: Mutex<Option<ScreenBufferInfo>>,
+}
+
+impl CachedScreenBuffer {
+    /// Wrap `screen_buffer` with an empty cache.
+    pub fn new(screen_buffer: ScreenBuffer) -> CachedScreenBuffer {
+        CachedScreenBuffer {
+            screen_buffer,
+            info: Mutex::new(None),
+        }
+    }
+
+    /// Get the screen buffer information, querying it only if it isn't cached.
+    ///
+    /// See [`ScreenBuffer::info`].
+    pub fn info(&self) -> Result<ScreenBufferInfo> {
+        let mut cached = self.info.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(info) = cached.as_ref() {
+            return Ok(info.clone());
+        }
+        let info = self.screen_buffer.info()?;
+        *cached = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Forget the cached information, so the next [`info`](CachedScreenBuffer::info) queries it.
+    pub fn invalidate(&self) {
+        *self.info.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Invalidate the cache if `record` reports a resize.
+    pub fn observe(&self, record: &InputRecord) {
+        if invalidates(record) {
+            self.invalidate();
+        }
+    }
+
+    /// Set the size of the buffer, see [`ScreenBuffer::set_size`].
+    pub fn set_size(&self, x: i16, y: i16) -> Result<()> {
+        self.invalidating(|screen_buffer| screen_buffer.set_size(x, y))
+    }
+
+    /// Move the window over the buffer, see [`ScreenBuffer::set_viewport_origin`].
+    pub fn set_viewport_origin(&self, origin: Coord) -> Result<()> {
+        self.invalidating(|screen_buffer| screen_buffer.set_viewport_origin(origin))
+    }
+
+    /// Discard the scrollback above the window except for its last `keep_lines` lines, see
+    /// [`ScreenBuffer::trim_scrollback`].
+    pub fn trim_scrollback(&self, keep_lines: u16) -> Result<()> {
+        self.invalidating(|screen_buffer| screen_buffer.trim_scrollback(keep_lines))
+    }
+
+    /// Discard all of the scrollback above the window, see [`ScreenBuffer::clear_scrollback`].
+    pub fn clear_scrollback(&self) -> Result<()> {
+        self.invalidating(ScreenBuffer::clear_scrollback)
+    }
+
+    /// Clear a part of the buffer, see [`ScreenBuffer::clear`].
+    pub fn clear(&self, clear_type: ClearType) -> Result<()> {
+        self.invalidating(|screen_buffer| screen_buffer.clear(clear_type))
+    }
+
+    /// Restore the default attributes of the process, see
+    /// [`ScreenBuffer::reset_to_default_attributes`].
+    pub fn reset_to_default_attributes(&self) -> Result<()> {
+        self.invalidating(ScreenBuffer::reset_to_default_attributes)
+    }
+
+    /// Set this screen buffer to the current one, see [`ScreenBuffer::show`].
+    pub fn show(&self) -> Result<()> {
+        self.screen_buffer.show()
+    }
+
+    /// Get the extended screen buffer information, see [`ScreenBuffer::info_ex`].
+    ///
+    /// This always queries the information, it isn't cached.
+    pub fn info_ex(&self) -> Result<ScreenBufferInfoEx> {
+        self.screen_buffer.info_ex()
+    }
+
+    /// Get the size of the window in cells and pixels, see [`ScreenBuffer::size_report`].
+    pub fn size_report(&self) -> Result<SizeReport> {
+        self.screen_buffer.size_report()
+    }
+
+    /// Get the font of the screen buffer, see [`ScreenBuffer::font_info`].
+    pub fn font_info(&self) -> Result<FontInfo> {
+        self.screen_buffer.font_info()
+    }
+
+    /// Read the cells of `region`, see [`ScreenBuffer::snapshot`].
+    pub fn snapshot(&self, region: WindowPositions) -> Result<BufferSnapshot> {
+        self.screen_buffer.snapshot(region)
+    }
+
+    /// Get the attributes of the cell at `coord`, see [`ScreenBuffer::attribute_at`].
+    pub fn attribute_at(&self, coord: Coord) -> Result<TextAttributes> {
+        self.screen_buffer.attribute_at(coord)
+    }
+
+    /// Get the character of the cell at `coord`, see [`ScreenBuffer::char_at`].
+    pub fn char_at(&self, coord: Coord) -> Result<char> {
+        self.screen_buffer.char_at(coord)
+    }
+
+    /// Write `text` starting at `pos`, see [`ScreenBuffer::write_chars_at_advance`].
+    ///
+    /// The cursor and the attributes don't change, so the cache stays valid.
+    pub fn write_chars_at_advance(&self, text: &str, pos: Coord) -> Result<Coord> {
+        self.screen_buffer.write_chars_at_advance(text, pos)
+    }
+
+    /// Copy the cells in `src` to `dst`, see [`ScreenBuffer::copy_region`].
+    pub fn copy_region(&self, src: WindowPositions, dst: Coord) -> Result<()> {
+        self.screen_buffer.copy_region(src, dst)
+    }
+
+    /// Watch the size of the screen buffer, see [`ScreenBuffer::watch_size`].
+    pub fn watch_size(&self, interval: Duration) -> Result<SizeWatcher> {
+        self.screen_buffer.watch_size(interval)
+    }
+
+    /// Get the text attributes the process started with, see
+    /// [`ScreenBuffer::default_attributes`].
+    pub fn default_attributes(&self) -> Result<u16> {
+        self.screen_buffer.default_attributes()
+    }
+
+    /// Run `f` with the given text attributes, see [`ScreenBuffer::with_attributes`].
+    ///
+    /// The cache is invalidated afterwards, since `f` may write and move the cursor.
+    pub fn with_attributes<T>(&self, attributes: u16, f: impl FnOnce() -> T) -> Result<T> {
+        self.invalidating(|screen_buffer| screen_buffer.with_attributes(attributes, f))
+    }
+
+    /// Get the handle of the screen buffer.
+    pub fn handle(&self) -> &Handle {
+        self.screen_buffer.handle()
+    }
+
+    /// Get the wrapped screen buffer.
+    pub fn screen_buffer(&self) -> &ScreenBuffer {
+        &self.screen_buffer
+    }
+
+    /// Unwrap the screen buffer, discarding the cache.
+    pub fn into_inner(self) -> ScreenBuffer {
+        self.screen_buffer
+    }
+
+    /// Run `f`, invalidating the cache even if it fails part way.
+    fn invalidating<T>(&self, f: impl FnOnce(&ScreenBuffer) -> Result<T>) -> Result<T> {
+        let result = f(&self.screen_buffer);
+        self.invalidate();
+        result
+    }
+}
+
+impl Clone for CachedScreenBuffer {
+    /// Clone the screen buffer and the cached information, the caches are independent.
+    fn clone(&self) -> Self {
+        CachedScreenBuffer {
+            screen_buffer: self.screen_buffer.clone(),
+            info: Mutex::new(self.info.lock().unwrap_or_else(|e| e.into_inner()).clone()),
+        }
+    }
+}
+
+impl From<ScreenBuffer> for CachedScreenBuffer {
+    fn from(screen_buffer: ScreenBuffer) -> Self {
+        CachedScreenBuffer::new(screen_buffer)
+    }
+}
+
+/// Returns whether `record` changes the screen buffer information.
+fn invalidates(record: &InputRecord) -> bool {
+    matches!(record, InputRecord::WindowBufferSizeEvent(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::invalidates;
+    use crate::{Coord, FocusEventRecord, InputRecord, WindowBufferSizeRecord};
+
+    #[test]
+    fn test_invalidates() {
+        let resize = InputRecord::WindowBufferSizeEvent(WindowBufferSizeRecord {
+            size: Coord::new(80, 25),
+            buffer_size: Coord::new(80, 9001),
+        });
+        assert!(invalidates(&resize));
+        assert!(!invalidates(&InputRecord::FocusEvent(FocusEventRecord {
+            set_focus: true
+        })));
+    }
+}
@@ -0,0 +1,498 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Result};
+use std::mem;
+use std::ptr;
+
+use winapi::shared::minwindef::FARPROC;
+use winapi::shared::winerror::{
+    ERROR_CALL_NOT_IMPLEMENTED, ERROR_INVALID_FUNCTION, ERROR_NOT_SUPPORTED,
+};
+use winapi::um::{
+    consoleapi::{GetConsoleMode, SetConsoleMode},
+    libloaderapi::{GetModuleHandleW, GetProcAddress},
+    wincon::{
+        GetConsoleDisplayMode, ENABLE_VIRTUAL_TERMINAL_INPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    },
+    winnt::{HANDLE, OSVERSIONINFOW},
+};
+
+use super::{
+    result, trace, ApiError, Console, ConsoleHost, ConsoleMode, ConsoleWindow, Coord, ErrorContext,
+    Handle, HostKind, InfoExSource, Overrides, ScreenBuffer,
+};
+
+/// Whether the console supports a capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// The capability is supported.
+    Supported,
+    /// The capability is not supported.
+    Unsupported,
+    /// Whether the capability is supported could not be determined, for example because the
+    /// process is not attached to a console.
+    Unknown,
+}
+
+impl Support {
+    /// Returns whether the capability is known to be supported.
+    pub fn is_supported(self) -> bool {
+        self == Support::Supported
+    }
+}
+
+impl From<bool> for Support {
+    fn from(supported: bool) -> Self {
+        if supported {
+            Support::Supported
+        } else {
+            Support::Unsupported
+        }
+    }
+}
+
+impl fmt::Display for Support {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Support::Supported => "supported",
+            Support::Unsupported => "unsupported",
+            Support::Unknown => "unknown",
+        })
+    }
+}
+
+/// Probe whether the active screen buffer supports virtual terminal sequences.
+///
+/// If `ENABLE_VIRTUAL_TERMINAL_PROCESSING` is not set already, this sets it, checks whether it
+/// stuck and restores the original mode. Only the mode calls themselves are made, so there is no
+/// error to pick up from `GetLastError` and nothing is raised under a debugger. The environment
+/// [`Overrides`] take precedence over the probe.
+///
+/// This wraps [`GetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/getconsolemode)
+/// and [`SetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/setconsolemode).
+pub fn supports_vt() -> Support {
+    let overrides = Overrides::current();
+    if overrides.force_legacy {
+        return Support::Unsupported;
+    }
+    if overrides.force_vt {
+        return Support::Supported;
+    }
+
+    probe_mode_flag(
+        Handle::current_out_handle(),
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    )
+}
+
+/// Probe whether the console input buffer can report input as virtual terminal sequences.
+///
+/// This probes `ENABLE_VIRTUAL_TERMINAL_INPUT` the same way [`supports_vt`] probes the screen
+/// buffer. `CROSSTERM_WINAPI_FORCE_LEGACY` reports it as unsupported.
+pub fn supports_vt_input() -> Support {
+    if Overrides::current().force_legacy {
+        return Support::Unsupported;
+    }
+    probe_mode_flag(Handle::current_in_handle(), ENABLE_VIRTUAL_TERMINAL_INPUT)
+}
+
+/// Set `flag` on the mode of `handle` if it isn't set, check whether it stuck and restore the
+/// original mode.
+fn probe_mode_flag(handle: Result<Handle>, flag: u32) -> Support {
+    let handle = match handle {
+        Ok(handle) => handle,
+        Err(_) => return Support::Unknown,
+    };
+    let mut original = 0;
+    if trace::call("GetConsoleMode", *handle, || unsafe {
+        GetConsoleMode(*handle, &mut original)
+    }) == 0
+    {
+        return Support::Unknown;
+    }
+    if original & flag != 0 {
+        return Support::Supported;
+    }
+
+    let probe = original | flag;
+    if trace::call("SetConsoleMode", *handle, || unsafe {
+        SetConsoleMode(*handle, probe)
+    }) == 0
+    {
+        return Support::Unsupported;
+    }
+
+    let mut mode = 0;
+    let read = trace::call("GetConsoleMode", *handle, || unsafe {
+        GetConsoleMode(*handle, &mut mode)
+    });
+    trace::call("SetConsoleMode", *handle, || unsafe {
+        SetConsoleMode(*handle, original)
+    });
+
+    if read == 0 {
+        Support::Unknown
+    } else {
+        Support::from(mode & flag != 0)
+    }
+}
+
+/// The version of Windows the process runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowsVersion {
+    /// The major version, `10` for Windows 10 and 11.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+    /// The build number, Windows 11 starts at `22000`.
+    pub build: u32,
+}
+
+impl WindowsVersion {
+    /// The first version with the pseudo console API, Windows 10 1809.
+    pub const CONPTY: WindowsVersion = WindowsVersion::new(10, 0, 17763);
+
+    /// Create a version from its parts.
+    pub const fn new(major: u32, minor: u32, build: u32) -> WindowsVersion {
+        WindowsVersion {
+            major,
+            minor,
+            build,
+        }
+    }
+}
+
+impl fmt::Display for WindowsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.build)
+    }
+}
+
+/// Get the version of Windows the process runs on.
+///
+/// `GetVersionEx` reports the version the application manifest declares compatibility with,
+/// which is Windows 8 for most Rust binaries. This uses `RtlGetVersion` instead, which always
+/// reports the real version.
+pub fn windows_version() -> Result<WindowsVersion> {
+    type RtlGetVersion = unsafe extern "system" fn(*mut OSVERSIONINFOW) -> i32;
+
+    let rtl_get_version = proc_address("ntdll.dll\0", "RtlGetVersion\0").ok_or_else(|| {
+        io::Error::new(io::ErrorKind::Unsupported, "RtlGetVersion is not available")
+    })?;
+    let rtl_get_version: RtlGetVersion = unsafe { mem::transmute(rtl_get_version) };
+
+    let mut info: OSVERSIONINFOW = unsafe { mem::zeroed() };
+    info.dwOSVersionInfoSize = mem::size_of::<OSVERSIONINFOW>() as u32;
+    let status = unsafe { rtl_get_version(&mut info) };
+    if status != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("RtlGetVersion failed with status {:#x}", status),
+        ));
+    }
+
+    Ok(WindowsVersion::new(
+        info.dwMajorVersion,
+        info.dwMinorVersion,
+        info.dwBuildNumber,
+    ))
+}
+
+/// Returns whether the pseudo console (ConPTY) API is available.
+///
+/// This checks whether `kernel32.dll` exports `CreatePseudoConsole`, so it is correct regardless
+/// of the application manifest. Without the export the API can't be called, whatever the version
+/// of Windows claims.
+pub fn supports_conpty() -> Support {
+    Support::from(proc_address("kernel32.dll\0", "CreatePseudoConsole\0").is_some())
+}
+
+/// Look up a function exported by a module loaded in the process. Both names must end with a
+/// NUL.
+pub(crate) fn proc_address(module: &str, name: &str) -> Option<FARPROC> {
+    let module: Vec<u16> = module.encode_utf16().collect();
+    let module: HANDLE = trace::call("GetModuleHandleW", ptr::null_mut(), || unsafe {
+        GetModuleHandleW(module.as_ptr()).cast()
+    });
+    if module.is_null() {
+        return None;
+    }
+    let address: HANDLE = trace::call("GetProcAddress", module, || unsafe {
+        GetProcAddress(module.cast(), name.as_ptr().cast()).cast()
+    });
+    if address.is_null() {
+        None
+    } else {
+        Some(address.cast())
+    }
+}
+
+/// Returns whether the console host reports real focus changes as
+/// [`FocusEvent`](crate::InputRecord::FocusEvent)s.
+///
+/// Windows Terminal reports focus changes, the legacy console host only emits focus records for
+/// its own use, which should be ignored.
+pub fn supports_focus_events() -> Support {
+    match ConsoleHost::detect() {
+        Ok(host) => focus_events_support(host.kind()),
+        Err(_) => Support::Unknown,
+    }
+}
+
+fn focus_events_support(kind: HostKind) -> Support {
+    match kind {
+        HostKind::WindowsTerminal => Support::Supported,
+        HostKind::Conhost | HostKind::OpenConsole => Support::Unsupported,
+        HostKind::Unknown => Support::Unknown,
+    }
+}
+
+/// The outcome of calling one API against the current console host, see [`Capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiSupport {
+    /// The call succeeded.
+    Supported,
+    /// The host doesn't implement the API, for example a stub in Wine.
+    Unsupported,
+    /// The call failed for another reason, like the process not being attached to a console.
+    Errored(String),
+}
+
+impl ApiSupport {
+    /// Classify the result of a call, treating errors that mean "not implemented" as
+    /// [`ApiSupport::Unsupported`].
+    fn of<T>(result: Result<T>) -> ApiSupport {
+        match result {
+            Ok(_) => ApiSupport::Supported,
+            Err(e) if is_unsupported(&e) => ApiSupport::Unsupported,
+            Err(e) => ApiSupport::Errored(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ApiSupport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiSupport::Supported => f.write_str("supported"),
+            ApiSupport::Unsupported => f.write_str("unsupported"),
+            ApiSupport::Errored(message) => write!(f, "errored: {}", message),
+        }
+    }
+}
+
+/// Returns whether `error` means that the host doesn't implement the API.
+fn is_unsupported(error: &io::Error) -> bool {
+    match ApiError::raw_os_error(error) {
+        Some(code) => [
+            ERROR_CALL_NOT_IMPLEMENTED,
+            ERROR_INVALID_FUNCTION,
+            ERROR_NOT_SUPPORTED,
+        ]
+        .contains(&(code as u32)),
+        None => error.kind() == io::ErrorKind::Unsupported,
+    }
+}
+
+/// Which of the wrapped APIs work on the current console host, by name.
+///
+/// Unlike the `supports_*` functions, this doesn't rely on the Windows version or the host kind,
+/// but calls every API once, which makes it reliable in exotic environments like Wine, SSH
+/// sessions and CI runners. Only calls that read state are made, apart from the virtual terminal
+/// probes, which restore the original mode. Use the `Display` implementation to attach the report
+/// to a bug report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    apis: BTreeMap<&'static str, ApiSupport>,
+}
+
+impl Capabilities {
+    /// Call every wrapped API once against the current console host.
+    pub fn probe_all() -> Capabilities {
+        let input = Handle::current_in_handle();
+        let screen_buffer = ScreenBuffer::current();
+        let mut apis = BTreeMap::new();
+
+        apis.insert(
+            "GetConsoleMode(input)",
+            ApiSupport::of(
+                input
+                    .as_ref()
+                    .map_err(copy_error)
+                    .and_then(|handle| ConsoleMode::from(handle.clone()).mode()),
+            ),
+        );
+        apis.insert(
+            "GetConsoleMode(output)",
+            ApiSupport::of(
+                Handle::current_out_handle().and_then(|handle| ConsoleMode::from(handle).mode()),
+            ),
+        );
+        apis.insert(
+            "GetNumberOfConsoleInputEvents",
+            ApiSupport::of(
+                input.and_then(|handle| Console::from(handle).number_of_console_input_events()),
+            ),
+        );
+        apis.insert(
+            "GetConsoleScreenBufferInfo",
+            ApiSupport::of(
+                screen_buffer
+                    .as_ref()
+                    .map_err(copy_error)
+                    .and_then(|buffer| buffer.info()),
+            ),
+        );
+        apis.insert(
+            "GetConsoleScreenBufferInfoEx",
+            match screen_buffer
+                .as_ref()
+                .map_err(copy_error)
+                .and_then(|buffer| buffer.info_ex())
+            {
+                Ok(info) if info.source() == InfoExSource::Fallback => ApiSupport::Unsupported,
+                info => ApiSupport::of(info),
+            },
+        );
+        apis.insert(
+            "GetCurrentConsoleFont",
+            ApiSupport::of(
+                screen_buffer
+                    .as_ref()
+                    .map_err(copy_error)
+                    .and_then(|buffer| buffer.font_info()),
+            ),
+        );
+        apis.insert(
+            "ReadConsoleOutputCharacterW",
+            ApiSupport::of(
+                screen_buffer
+                    .as_ref()
+                    .map_err(copy_error)
+                    .and_then(|buffer| buffer.char_at(Coord::new(0, 0))),
+            ),
+        );
+        apis.insert("GetConsoleWindow", ApiSupport::of(ConsoleWindow::current()));
+        let mut display_mode = 0;
+        apis.insert(
+            "GetConsoleDisplayMode",
+            ApiSupport::of(
+                result(trace::call(
+                    "GetConsoleDisplayMode",
+                    ptr::null_mut(),
+                    || unsafe { GetConsoleDisplayMode(&mut display_mode) },
+                ))
+                .with_context("GetConsoleDisplayMode"),
+            ),
+        );
+        apis.insert(
+            "ENABLE_VIRTUAL_TERMINAL_PROCESSING",
+            mode_flag_support(probe_mode_flag(
+                Handle::current_out_handle(),
+                ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+            )),
+        );
+        apis.insert(
+            "ENABLE_VIRTUAL_TERMINAL_INPUT",
+            mode_flag_support(probe_mode_flag(
+                Handle::current_in_handle(),
+                ENABLE_VIRTUAL_TERMINAL_INPUT,
+            )),
+        );
+        apis.insert(
+            "CreatePseudoConsole",
+            export_support(proc_address("kernel32.dll\0", "CreatePseudoConsole\0")),
+        );
+        apis.insert(
+            "GetDpiForWindow",
+            export_support(proc_address("user32.dll\0", "GetDpiForWindow\0")),
+        );
+
+        Capabilities { apis }
+    }
+
+    /// Get the outcome of the API named `api`, or `None` if it wasn't probed.
+    pub fn get(&self, api: &str) -> Option<&ApiSupport> {
+        self.apis.get(api)
+    }
+
+    /// Iterate over the probed APIs and their outcome, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &ApiSupport)> {
+        self.apis.iter().map(|(api, support)| (*api, support))
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (api, support) in self.iter() {
+            writeln!(f, "{}: {}", api, support)?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy an error that is shared between several probes.
+fn copy_error(error: &io::Error) -> io::Error {
+    match ApiError::raw_os_error(error) {
+        Some(code) => io::Error::from_raw_os_error(code),
+        None => io::Error::new(error.kind(), error.to_string()),
+    }
+}
+
+fn mode_flag_support(support: Support) -> ApiSupport {
+    match support {
+        Support::Supported => ApiSupport::Supported,
+        Support::Unsupported => ApiSupport::Unsupported,
+        Support::Unknown => ApiSupport::Errored("the console mode could not be read".to_owned()),
+    }
+}
+
+fn export_support(address: Option<FARPROC>) -> ApiSupport {
+    match address {
+        Some(_) => ApiSupport::Supported,
+        None => ApiSupport::Unsupported,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{focus_events_support, ApiSupport, Support, WindowsVersion};
+    use crate::HostKind;
+
+    #[test]
+    fn test_version_order() {
+        assert!(WindowsVersion::new(10, 0, 22000) >= WindowsVersion::CONPTY);
+        assert!(WindowsVersion::new(6, 3, 9600) < WindowsVersion::CONPTY);
+    }
+
+    #[test]
+    fn test_focus_events_support() {
+        assert_eq!(
+            focus_events_support(HostKind::WindowsTerminal),
+            Support::Supported
+        );
+        assert_eq!(
+            focus_events_support(HostKind::Conhost),
+            Support::Unsupported
+        );
+        assert_eq!(focus_events_support(HostKind::Unknown), Support::Unknown);
+    }
+
+    #[test]
+    fn test_api_support_of() {
+        assert_eq!(ApiSupport::of(Ok(())), ApiSupport::Supported);
+        assert_eq!(
+            ApiSupport::of::<()>(Err(io::Error::from_raw_os_error(120))),
+            ApiSupport::Unsupported
+        );
+        assert_eq!(
+            ApiSupport::of::<()>(Err(io::Error::new(io::ErrorKind::Unsupported, "stub"))),
+            ApiSupport::Unsupported
+        );
+        assert!(matches!(
+            ApiSupport::of::<()>(Err(io::Error::from_raw_os_error(6))),
+            ApiSupport::Errored(_)
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+//! This module contains the modes for clearing a screen buffer.
+
+use super::{Coord, Size};
+
+/// Which part of a screen buffer to clear with [`ScreenBuffer::clear`](crate::ScreenBuffer::clear).
+///
+/// These match the semantics of the `ClearType` of crossterm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClearType {
+    /// All cells of the buffer. The cursor is moved to the top left cell.
+    All,
+    /// The cell under the cursor and all cells after it.
+    FromCursorDown,
+    /// The cell under the cursor and all cells before it.
+    FromCursorUp,
+    /// The line of the cursor. The cursor is moved to the start of the line.
+    CurrentLine,
+    /// The cell under the cursor and the rest of its line.
+    UntilNewLine,
+}
+
+/// The cells to clear, as a start position and a number of cells, and where to move the cursor
+/// to afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClearRange {
+    pub(crate) start: Coord,
+    pub(crate) cells: u32,
+    pub(crate) cursor: Option<Coord>,
+}
+
+impl ClearType {
+    pub(crate) fn range(self, cursor: Coord, buffer_size: Size) -> ClearRange {
+        let width = buffer_size.width.max(0) as u32;
+        let height = buffer_size.height.max(0) as u32;
+        let x = (cursor.x.max(0) as u32).min(width);
+        let y = (cursor.y.max(0) as u32).min(height);
+        let index = y * width + x;
+
+        let (start, cells, cursor) = match self {
+            ClearType::All => (Coord::new(0, 0), width * height, Some(Coord::new(0, 0))),
+            ClearType::FromCursorDown => (cursor, (width * height).saturating_sub(index), None),
+            ClearType::FromCursorUp => (Coord::new(0, 0), (index + 1).min(width * height), None),
+            ClearType::CurrentLine => (
+                Coord::new(0, cursor.y),
+                width,
+                Some(Coord::new(0, cursor.y)),
+            ),
+            ClearType::UntilNewLine => (cursor, width - x, None),
+        };
+        ClearRange {
+            start,
+            cells,
+            cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClearRange, ClearType};
+    use crate::{Coord, Size};
+
+    fn range(clear_type: ClearType) -> ClearRange {
+        clear_type.range(Coord::new(3, 2), Size::new(10, 5))
+    }
+
+    #[test]
+    fn test_clear_ranges() {
+        assert_eq!(
+            range(ClearType::All),
+            ClearRange {
+                start: Coord::new(0, 0),
+                cells: 50,
+                cursor: Some(Coord::new(0, 0)),
+            }
+        );
+        assert_eq!(
+            range(ClearType::FromCursorDown),
+            ClearRange {
+                start: Coord::new(3, 2),
+                cells: 27,
+                cursor: None,
+            }
+        );
+        assert_eq!(
+            range(ClearType::FromCursorUp),
+            ClearRange {
+                start: Coord::new(0, 0),
+                cells: 24,
+                cursor: None,
+            }
+        );
+        assert_eq!(
+            range(ClearType::CurrentLine),
+            ClearRange {
+                start: Coord::new(0, 2),
+                cells: 10,
+                cursor: Some(Coord::new(0, 2)),
+            }
+        );
+        assert_eq!(
+            range(ClearType::UntilNewLine),
+            ClearRange {
+                start: Coord::new(3, 2),
+                cells: 7,
+                cursor: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! This module contains functions to read and write text on the Windows clipboard.
+//!
+//! Terminal applications that implement their own selection can use these to copy the selected
+//! text, as the console only copies its own selection (quick edit mode).
+//!
+//! The clipboard is a global resource that can be held open by one window at a time. The
+//! functions fail with the error of
+//! [`OpenClipboard`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-openclipboard)
+//! if another application holds it open.
+
+use std::io::Result;
+use std::ptr::{copy_nonoverlapping, null_mut};
+
+use winapi::um::{
+    winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+    winuser::{
+        CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
+        OpenClipboard, SetClipboardData, CF_UNICODETEXT,
+    },
+};
+
+use super::{nonnull_handle_result, result, trace, ErrorContext};
+
+/// Closes the clipboard on drop.
+struct OpenedClipboard;
+
+impl OpenedClipboard {
+    fn open() -> Result<OpenedClipboard> {
+        result(trace::call("OpenClipboard", null_mut(), || unsafe {
+            OpenClipboard(null_mut())
+        }))
+        .with_context("OpenClipboard")?;
+        Ok(OpenedClipboard)
+    }
+}
+
+impl Drop for OpenedClipboard {
+    fn drop(&mut self) {
+        trace::call("CloseClipboard", null_mut(), || unsafe { CloseClipboard() });
+    }
+}
+
+/// Replace the contents of the clipboard with `text`.
+///
+/// This wraps
+/// [`SetClipboardData`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setclipboarddata)
+/// with the `CF_UNICODETEXT` format.
+pub fn set_text(text: &str) -> Result<()> {
+    let utf16: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    let size = utf16.len() * 2;
+
+    let memory = nonnull_handle_result(trace::call("GlobalAlloc", null_mut(), || unsafe {
+        GlobalAlloc(GMEM_MOVEABLE, size)
+    }))
+    .with_context("GlobalAlloc")?;
+
+    let outcome = (|| {
+        let ptr = nonnull_handle_result(trace::call("GlobalLock", memory, || unsafe {
+            GlobalLock(memory)
+        }))
+        .with_context("GlobalLock")?;
+        unsafe {
+            copy_nonoverlapping(utf16.as_ptr(), ptr as *mut u16, utf16.len());
+        }
+        trace::call("GlobalUnlock", memory, || unsafe { GlobalUnlock(memory) });
+
+        let _clipboard = OpenedClipboard::open()?;
+        result(trace::call("EmptyClipboard", null_mut(), || unsafe {
+            EmptyClipboard()
+        }))
+        .with_context("EmptyClipboard")?;
+        nonnull_handle_result(trace::call("SetClipboardData", memory, || unsafe {
+            SetClipboardData(CF_UNICODETEXT, memory)
+        }))
+        .with_context("SetClipboardData")?;
+        Ok(())
+    })();
+
+    // The system owns the memory once `SetClipboardData` succeeded.
+    if outcome.is_err() {
+        trace::call("GlobalFree", memory, || unsafe { GlobalFree(memory) });
+    }
+    outcome
+}
+
+/// Get the text on the clipboard, or `None` if the clipboard holds no text.
+///
+/// This wraps
+/// [`GetClipboardData`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getclipboarddata)
+/// with the `CF_UNICODETEXT` format. Invalid UTF-16 is replaced with `U+FFFD`.
+pub fn get_text() -> Result<Option<String>> {
+    if trace::call("IsClipboardFormatAvailable", null_mut(), || unsafe {
+        IsClipboardFormatAvailable(CF_UNICODETEXT)
+    }) == 0
+    {
+        return Ok(None);
+    }
+
+    let _clipboard = OpenedClipboard::open()?;
+    let memory = nonnull_handle_result(trace::call("GetClipboardData", null_mut(), || unsafe {
+        GetClipboardData(CF_UNICODETEXT)
+    }))
+    .with_context("GetClipboardData")?;
+    let ptr = nonnull_handle_result(trace::call("GlobalLock", memory, || unsafe {
+        GlobalLock(memory)
+    }))
+    .with_context("GlobalLock")? as *const u16;
+
+    let text = unsafe {
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+    };
+    trace::call("GlobalUnlock", memory, || unsafe { GlobalUnlock(memory) });
+
+    Ok(Some(text))
+}
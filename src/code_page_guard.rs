@@ -0,0 +1,87 @@
+use std::io::Result;
+use std::ptr;
+
+use winapi::um::{
+    consoleapi::{GetConsoleCP, GetConsoleOutputCP},
+    wincon::{SetConsoleCP, SetConsoleOutputCP},
+    winnls::CP_UTF8,
+};
+
+use super::{console_lock, result, trace, ErrorContext};
+
+/// Restores the input and output code pages of the console when dropped.
+///
+/// Child processes inherit the code pages of the console and encode their output with them, so
+/// wrapping a child process invocation in [`CodePageGuard::utf8`] makes its output decode as
+/// UTF-8. Because the restore happens on drop, the previous code pages come back on an early
+/// return or a panic as well.
+///
+/// This wraps [`SetConsoleCP`](https://docs.microsoft.com/en-us/windows/console/setconsolecp)
+/// and
+/// [`SetConsoleOutputCP`](https://docs.microsoft.com/en-us/windows/console/setconsoleoutputcp).
+#[derive(Debug)]
+pub struct CodePageGuard {
+    previous_input: u32,
+    previous_output: u32,
+}
+
+impl CodePageGuard {
+    /// Switch both code pages to UTF-8 (65001), restoring the current ones on drop.
+    pub fn utf8() -> Result<CodePageGuard> {
+        CodePageGuard::new(CP_UTF8, CP_UTF8)
+    }
+
+    /// Switch to the given input and output code pages, restoring the current ones on drop.
+    ///
+    /// If setting the output code page fails, the input code page is restored before the error
+    /// is returned.
+    pub fn new(input: u32, output: u32) -> Result<CodePageGuard> {
+        let _lock = console_lock()?;
+        let previous_input = trace::call("GetConsoleCP", ptr::null_mut(), || unsafe {
+            GetConsoleCP()
+        });
+        let previous_output = trace::call("GetConsoleOutputCP", ptr::null_mut(), || unsafe {
+            GetConsoleOutputCP()
+        });
+
+        result(trace::call("SetConsoleCP", ptr::null_mut(), || unsafe {
+            SetConsoleCP(input)
+        }))
+        .with_context("SetConsoleCP")?;
+        if let Err(e) = result(trace::call(
+            "SetConsoleOutputCP",
+            ptr::null_mut(),
+            || unsafe { SetConsoleOutputCP(output) },
+        ))
+        .with_context("SetConsoleOutputCP")
+        {
+            trace::call("SetConsoleCP", ptr::null_mut(), || unsafe {
+                SetConsoleCP(previous_input)
+            });
+            return Err(e);
+        }
+
+        Ok(CodePageGuard {
+            previous_input,
+            previous_output,
+        })
+    }
+
+    /// Get the input and output code pages that will be restored on drop.
+    pub fn previous(&self) -> (u32, u32) {
+        (self.previous_input, self.previous_output)
+    }
+}
+
+impl Drop for CodePageGuard {
+    fn drop(&mut self) {
+        // There is nothing sensible to do with an error while dropping.
+        let _lock = console_lock();
+        trace::call("SetConsoleCP", ptr::null_mut(), || unsafe {
+            SetConsoleCP(self.previous_input)
+        });
+        trace::call("SetConsoleOutputCP", ptr::null_mut(), || unsafe {
+            SetConsoleOutputCP(self.previous_output)
+        });
+    }
+}
@@ -0,0 +1,186 @@
+//! This module maps 24-bit colors to the 16 colors of the legacy console.
+//!
+//! Applications that render with true color escape sequences can use a [`ColorMapper`] to pick
+//! the closest [`TextAttributes`] on consoles without virtual terminal processing. The mapper
+//! uses the palette of the screen buffer, so it matches what is actually shown when the user
+//! customized the colors.
+
+use super::{ScreenBufferInfoEx, TextAttributes, DEFAULT_COLOR_TABLE};
+
+/// A 24-bit color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    /// The red component.
+    pub r: u8,
+    /// The green component.
+    pub g: u8,
+    /// The blue component.
+    pub b: u8,
+}
+
+impl Rgb {
+    /// Create a color from its components.
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    /// Create a color from a `COLORREF`, a `0x00BBGGRR` value as used in the color table.
+    pub const fn from_colorref(colorref: u32) -> Rgb {
+        Rgb {
+            r: colorref as u8,
+            g: (colorref >> 8) as u8,
+            b: (colorref >> 16) as u8,
+        }
+    }
+
+    /// Get the color as a `COLORREF`, a `0x00BBGGRR` value.
+    pub const fn to_colorref(self) -> u32 {
+        self.r as u32 | (self.g as u32) << 8 | (self.b as u32) << 16
+    }
+}
+
+/// How the distance between two colors is measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Distance {
+    /// The euclidean distance of the RGB components.
+    Euclidean,
+    /// The euclidean distance weighted by the mean red component ("redmean"), which is closer to
+    /// how the eye perceives the difference at little cost.
+    #[default]
+    Redmean,
+}
+
+impl Distance {
+    /// Get the squared distance between `a` and `b`.
+    fn measure(self, a: Rgb, b: Rgb) -> i64 {
+        let dr = i64::from(a.r) - i64::from(b.r);
+        let dg = i64::from(a.g) - i64::from(b.g);
+        let db = i64::from(a.b) - i64::from(b.b);
+        match self {
+            Distance::Euclidean => dr * dr + dg * dg + db * db,
+            Distance::Redmean => {
+                let mean = (i64::from(a.r) + i64::from(b.r)) / 2;
+                (((512 + mean) * dr * dr) >> 8) + 4 * dg * dg + (((767 - mean) * db * db) >> 8)
+            }
+        }
+    }
+}
+
+/// Maps 24-bit colors to the nearest of the 16 colors of a console palette.
+///
+/// Index `n` of the palette is the color `n` of the attributes, so the result can be used as
+/// the foreground or background nibble directly.
+///
+/// ```no_run
+/// # use crossterm_winapi::color::{ColorMapper, Rgb};
+/// # use crossterm_winapi::ScreenBuffer;
+/// # fn main() -> std::io::Result<()> {
+/// let mapper = ColorMapper::from_info_ex(&ScreenBuffer::current()?.info_ex()?);
+/// let attributes = mapper.map_pair(Rgb::new(0xff, 0x80, 0x00), Rgb::new(0x10, 0x10, 0x10));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorMapper {
+    palette: [Rgb; 16],
+    distance: Distance,
+}
+
+impl Default for ColorMapper {
+    /// Create a mapper for the default palette, see [`DEFAULT_COLOR_TABLE`].
+    fn default() -> Self {
+        ColorMapper::new(DEFAULT_COLOR_TABLE)
+    }
+}
+
+impl ColorMapper {
+    /// Create a mapper for a color table of `0x00BBGGRR` values.
+    pub fn new(color_table: [u32; 16]) -> ColorMapper {
+        ColorMapper {
+            palette: color_table.map(Rgb::from_colorref),
+            distance: Distance::default(),
+        }
+    }
+
+    /// Create a mapper for the palette of a screen buffer.
+    pub fn from_info_ex(info: &ScreenBufferInfoEx) -> ColorMapper {
+        ColorMapper::new(info.color_table())
+    }
+
+    /// Use `distance` to find the nearest color, [`Distance::Redmean`] by default.
+    pub fn distance(mut self, distance: Distance) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Get the palette.
+    pub fn palette(&self) -> &[Rgb; 16] {
+        &self.palette
+    }
+
+    /// Get the index of the palette color nearest to `color`.
+    pub fn map(&self, color: Rgb) -> u8 {
+        self.nearest(color, None)
+    }
+
+    /// Get the attributes for text in `foreground` on `background`.
+    ///
+    /// Two different colors can map to the same palette color, which would make the text
+    /// invisible. In that case the foreground is mapped to the nearest of the other colors.
+    pub fn map_pair(&self, foreground: Rgb, background: Rgb) -> TextAttributes {
+        let background_index = self.map(background);
+        let mut foreground_index = self.map(foreground);
+        if foreground_index == background_index && foreground != background {
+            foreground_index = self.nearest(foreground, Some(background_index));
+        }
+        TextAttributes::new(u16::from(foreground_index) | u16::from(background_index) << 4)
+    }
+
+    fn nearest(&self, color: Rgb, except: Option<u8>) -> u8 {
+        (0u8..16)
+            .filter(|&index| Some(index) != except)
+            .min_by_key(|&index| {
+                self.distance
+                    .measure(color, self.palette[usize::from(index)])
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorMapper, Distance, Rgb};
+    use crate::DEFAULT_COLOR_TABLE;
+
+    #[test]
+    fn test_colorref() {
+        let color = Rgb::from_colorref(0x00da_3700);
+        assert_eq!(color, Rgb::new(0x00, 0x37, 0xda));
+        assert_eq!(color.to_colorref(), 0x00da_3700);
+    }
+
+    #[test]
+    fn test_palette_colors_map_to_themselves() {
+        for distance in [Distance::Euclidean, Distance::Redmean] {
+            let mapper = ColorMapper::default().distance(distance);
+            for (index, colorref) in DEFAULT_COLOR_TABLE.iter().enumerate() {
+                assert_eq!(mapper.map(Rgb::from_colorref(*colorref)), index as u8);
+            }
+        }
+        let mapper = ColorMapper::default();
+        assert_eq!(mapper.map(Rgb::new(0, 0, 0)), 0);
+        assert_eq!(mapper.map(Rgb::new(0xff, 0xff, 0xff)), 15);
+    }
+
+    #[test]
+    fn test_map_pair_keeps_contrast() {
+        let mapper = ColorMapper::default();
+        // Light gray on white would both map to bright white.
+        let attributes = mapper.map_pair(Rgb::new(0xe0, 0xe0, 0xe0), Rgb::new(0xff, 0xff, 0xff));
+        assert_eq!(attributes.background(), 15);
+        assert_eq!(attributes.foreground(), 7);
+
+        let same = mapper.map_pair(Rgb::new(0, 0, 0), Rgb::new(0, 0, 0));
+        assert_eq!(same.bits(), 0x00);
+    }
+}
@@ -0,0 +1,130 @@
+//! This module contains the environment variables end users can set to work around broken
+//! terminal hosts, without changes to the application.
+//!
+//! | Variable | Effect |
+//! | --- | --- |
+//! | `CROSSTERM_WINAPI_FORCE_LEGACY` | Report virtual terminal sequences as unsupported and never enable them. |
+//! | `CROSSTERM_WINAPI_FORCE_VT` | Report virtual terminal sequences as supported. |
+//! | `CROSSTERM_WINAPI_DISABLE_MOUSE` | Never enable mouse input. |
+//!
+//! A variable is considered set when it is present and not empty or `0`.
+//! When both `FORCE_LEGACY` and `FORCE_VT` are set, `FORCE_LEGACY` wins.
+//!
+//! The variables are read once per process. They apply where the crate enables virtual terminal
+//! sequences or mouse input itself, a mode passed to
+//! [`ConsoleMode::set_mode`](crate::ConsoleMode::set_mode) is set as given.
+
+use std::env;
+use std::sync::Mutex;
+
+use winapi::um::wincon::{
+    ENABLE_MOUSE_INPUT, ENABLE_VIRTUAL_TERMINAL_INPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+};
+
+const FORCE_LEGACY: &str = "CROSSTERM_WINAPI_FORCE_LEGACY";
+const FORCE_VT: &str = "CROSSTERM_WINAPI_FORCE_VT";
+const DISABLE_MOUSE: &str = "CROSSTERM_WINAPI_DISABLE_MOUSE";
+
+static CURRENT: Mutex<Option<Overrides>> = Mutex::new(None);
+
+/// The behavior overrides read from the environment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overrides {
+    /// `CROSSTERM_WINAPI_FORCE_LEGACY` is set.
+    pub force_legacy: bool,
+    /// `CROSSTERM_WINAPI_FORCE_VT` is set.
+    pub force_vt: bool,
+    /// `CROSSTERM_WINAPI_DISABLE_MOUSE` is set.
+    pub disable_mouse: bool,
+}
+
+impl Overrides {
+    /// Read the overrides from the environment of the current process.
+    pub fn from_env() -> Overrides {
+        Overrides {
+            force_legacy: is_set(FORCE_LEGACY),
+            force_vt: is_set(FORCE_VT),
+            disable_mouse: is_set(DISABLE_MOUSE),
+        }
+    }
+
+    /// Get the overrides of the current process, reading them from the environment on the first
+    /// call only.
+    ///
+    /// This is what the crate uses when it enables virtual terminal sequences or mouse input
+    /// itself, so changes to the environment after the first call have no effect.
+    pub fn current() -> Overrides {
+        *CURRENT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(Overrides::from_env)
+    }
+
+    /// Returns whether any override is active.
+    pub fn any(&self) -> bool {
+        self.force_legacy || self.force_vt || self.disable_mouse
+    }
+
+    /// Apply the overrides to the result of detecting virtual terminal support.
+    pub fn vt_support(&self, detected: bool) -> bool {
+        if self.force_legacy {
+            false
+        } else if self.force_vt {
+            true
+        } else {
+            detected
+        }
+    }
+
+    /// Remove the flags the overrides forbid from a console input buffer mode.
+    pub fn filter_input_mode(&self, mut mode: u32) -> u32 {
+        if self.force_legacy {
+            mode &= !ENABLE_VIRTUAL_TERMINAL_INPUT;
+        }
+        if self.disable_mouse {
+            mode &= !ENABLE_MOUSE_INPUT;
+        }
+        mode
+    }
+
+    /// Remove the flags the overrides forbid from a console screen buffer mode.
+    pub fn filter_output_mode(&self, mut mode: u32) -> u32 {
+        if self.force_legacy {
+            mode &= !ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+        }
+        mode
+    }
+}
+
+fn is_set(name: &str) -> bool {
+    match env::var_os(name) {
+        Some(value) => !value.is_empty() && value != "0",
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Overrides;
+
+    #[test]
+    fn test_force_legacy_wins() {
+        let overrides = Overrides {
+            force_legacy: true,
+            force_vt: true,
+            disable_mouse: false,
+        };
+        assert!(!overrides.vt_support(true));
+        assert_eq!(overrides.filter_output_mode(0x0007), 0x0003);
+    }
+
+    #[test]
+    fn test_disable_mouse() {
+        let overrides = Overrides {
+            disable_mouse: true,
+            ..Overrides::default()
+        };
+        assert_eq!(overrides.filter_input_mode(0x0217), 0x0207);
+        assert!(overrides.vt_support(true));
+    }
+}
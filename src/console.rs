@@ -1,23 +1,152 @@
+//! This module contains [`Console`] and the functions that report on the console as a whole,
+//! like [`diagnostics`].
+
+use std::hint;
 use std::io::{self, Result};
 use std::iter;
+use std::os::windows::io::{
+    AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle, RawHandle,
+};
+use std::ptr;
 use std::slice;
 use std::str;
+use std::time::{Duration, Instant};
 
 use winapi::ctypes::c_void;
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::ntdef::NULL;
-use winapi::um::consoleapi::{GetNumberOfConsoleInputEvents, ReadConsoleInputW, WriteConsoleW};
+use winapi::shared::windef::POINT;
+use winapi::shared::winerror::ERROR_BROKEN_PIPE;
+use winapi::shared::winerror::{ERROR_INVALID_HANDLE, ERROR_NOT_ENOUGH_MEMORY, WAIT_TIMEOUT};
+use winapi::um::consoleapi::{
+    GetNumberOfConsoleInputEvents, ReadConsoleInputW, ReadConsoleW, WriteConsoleW,
+};
+use winapi::um::fileapi::{ReadFile, WriteFile};
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
 use winapi::um::wincon::{
-    FillConsoleOutputAttribute, FillConsoleOutputCharacterA, GetLargestConsoleWindowSize,
-    SetConsoleTextAttribute, SetConsoleWindowInfo, COORD, INPUT_RECORD, SMALL_RECT,
+    FillConsoleOutputAttribute, FillConsoleOutputCharacterW, GetConsoleCursorInfo,
+    GetLargestConsoleWindowSize, SetConsoleCursorInfo, SetConsoleTextAttribute,
+    SetConsoleWindowInfo, WriteConsoleInputW, WriteConsoleOutputW, CHAR_INFO,
+    COMMON_LVB_LEADING_BYTE, COMMON_LVB_TRAILING_BYTE, CONSOLE_CURSOR_INFO, COORD,
+    ENABLE_VIRTUAL_TERMINAL_PROCESSING, INPUT_RECORD, SMALL_RECT,
+};
+use winapi::um::winnls::CP_UTF8;
+use winapi::um::winuser::{ClientToScreen, VK_RETURN, VK_TAB};
+
+pub use super::diagnostics::{diagnostics, Diagnostics};
+
+use super::{
+    console_lock, cooked_read, encoding, input_filter::InputFilters, io_stats::IoRecorder,
+    read_watchdog, result, screen_buffer, size_report::DEFAULT_DPI, supports_focus_events, trace,
+    AnnouncePriority, ApiError, ConsoleMode, ConsoleModePreset, ConsoleWindow, CookedLine, Coord,
+    CtrlCCaptureGuard, ErrorContext, EventFilter, FilterAction, Handle, HandleType, InputRecord,
+    IoStats, KeyEventRecord, ScreenBuffer, Size, SizeReport, SizeSource, StallReport, WakeupKeys,
+    WindowPositions, WriteSample,
 };
 
-use super::{result, Coord, Handle, HandleType, InputRecord, WindowPositions};
+/// The cursor size of a fresh console, in percent of the cell.
+const DEFAULT_CURSOR_SIZE: u32 = 25;
 
 /// A wrapper around a screen buffer.
 #[derive(Debug, Clone)]
 pub struct Console {
     handle: Handle,
+    write_fallback: bool,
+    include_reserved_events: bool,
+    focus_events: bool,
+    event_filter: EventFilter,
+    filters: InputFilters,
+    io_stats: Option<IoRecorder>,
+}
+
+/// A builder for a [`Console`] with non-default behavior.
+#[derive(Debug, Clone)]
+pub struct ConsoleBuilder {
+    handle: Option<Handle>,
+    handle_type: HandleType,
+    write_fallback: bool,
+    include_reserved_events: bool,
+    event_filter: EventFilter,
+    io_stats_capacity: Option<usize>,
+}
+
+impl ConsoleBuilder {
+    /// Use the given handle instead of the standard output.
+    pub fn handle(mut self, handle: Handle) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Use the handle of the given type instead of the standard output, for example
+    /// [`HandleType::ErrorHandle`] to draw on the standard error.
+    ///
+    /// This is ignored if a handle is set with [`handle`](ConsoleBuilder::handle).
+    pub fn handle_type(mut self, handle_type: HandleType) -> Self {
+        self.handle_type = handle_type;
+        self
+    }
+
+    /// Set whether writes fall back to `WriteFile` when the handle is not a console.
+    ///
+    /// This is enabled by default, so that writing to redirected output, like a file or a pipe,
+    /// writes the UTF-8 bytes instead of failing.
+    pub fn write_fallback(mut self, enable: bool) -> Self {
+        self.write_fallback = enable;
+        self
+    }
+
+    /// Set whether the reading functions return the menu and focus records the console host
+    /// uses internally, see [`InputRecord::reserved`].
+    ///
+    /// This is enabled by default for compatibility, as is
+    /// [`Events::include_reserved_events`](crate::Events::include_reserved_events). When
+    /// disabled, these records are read and dropped, so they never need to be matched.
+    ///
+    /// Focus records are real focus changes on hosts where [`supports_focus_events`] reports
+    /// [`Support::Supported`](crate::Support::Supported), like Windows Terminal. They are kept
+    /// there, only menu records are dropped.
+    pub fn include_reserved_events(mut self, include: bool) -> Self {
+        self.include_reserved_events = include;
+        self
+    }
+
+    /// Set which kinds of records the reading functions return, see [`EventFilter`].
+    ///
+    /// This is [`EventFilter::All`] by default. A record read after waiting for input that is
+    /// dropped by the filter makes [`Console::try_read_single_input_event`] and
+    /// [`Events::poll`](crate::Events::poll) return `None` instead of blocking for the next one.
+    pub fn event_filter(mut self, event_filter: EventFilter) -> Self {
+        self.event_filter = event_filter;
+        self
+    }
+
+    /// Record the size and duration of the last `capacity` writes, see [`Console::io_stats`].
+    ///
+    /// This is disabled by default. The recording is shared by the clones of the console.
+    pub fn record_io_stats(mut self, capacity: usize) -> Self {
+        self.io_stats_capacity = Some(capacity);
+        self
+    }
+
+    /// Create the `Console`.
+    pub fn build(self) -> Result<Console> {
+        let handle = match self.handle {
+            Some(handle) => handle,
+            None => Handle::new(self.handle_type)?,
+        };
+
+        Ok(Console {
+            handle,
+            write_fallback: self.write_fallback,
+            include_reserved_events: self.include_reserved_events,
+            // Only needed to tell which records are reserved when they are dropped.
+            focus_events: !self.include_reserved_events && supports_focus_events().is_supported(),
+            event_filter: self.event_filter,
+            filters: InputFilters::default(),
+            io_stats: self.io_stats_capacity.map(IoRecorder::new),
+        })
+    }
 }
 
 impl Console {
@@ -25,9 +154,24 @@ impl Console {
     ///
     /// This created instance will use the default output handle (STD_OUTPUT_HANDLE) as handle for the function call it wraps.
     pub fn output() -> Result<Console> {
-        Ok(Console {
-            handle: Handle::new(HandleType::OutputHandle)?,
-        })
+        Console::builder().build()
+    }
+
+    /// Create a `Console` using the handle of the given type.
+    pub fn from_handle_type(handle_type: HandleType) -> Result<Console> {
+        Console::builder().handle_type(handle_type).build()
+    }
+
+    /// Create a builder to configure a `Console`.
+    pub fn builder() -> ConsoleBuilder {
+        ConsoleBuilder {
+            handle: None,
+            handle_type: HandleType::OutputHandle,
+            write_fallback: true,
+            include_reserved_events: true,
+            event_filter: EventFilter::All,
+            io_stats_capacity: None,
+        }
     }
 
     /// Sets the attributes of characters written to the console screen buffer by the `WriteFile` or `WriteConsole` functions, or echoed by the `ReadFile` or `ReadConsole` functions.
@@ -39,7 +183,14 @@ impl Console {
     /// This wraps
     /// [`SetConsoleTextAttribute`](https://docs.microsoft.com/en-us/windows/console/setconsoletextattribute).
     pub fn set_text_attribute(&self, value: u16) -> Result<()> {
-        result(unsafe { SetConsoleTextAttribute(*self.handle, value) })?;
+        let _lock = console_lock()?;
+        screen_buffer::capture_default_attributes(&self.handle);
+        result(trace::call(
+            "SetConsoleTextAttribute",
+            *self.handle,
+            || unsafe { SetConsoleTextAttribute(*self.handle, value) },
+        ))
+        .with_handle_context("SetConsoleTextAttribute", &self.handle)?;
         Ok(())
     }
 
@@ -54,7 +205,12 @@ impl Console {
         };
         let a = SMALL_RECT::from(rect);
 
-        result(unsafe { SetConsoleWindowInfo(*self.handle, absolute, &a) })?;
+        result(trace::call(
+            "SetConsoleWindowInfo",
+            *self.handle,
+            || unsafe { SetConsoleWindowInfo(*self.handle, absolute, &a) },
+        ))
+        .with_handle_context("SetConsoleWindowInfo", &self.handle)?;
 
         Ok(())
     }
@@ -62,29 +218,127 @@ impl Console {
     /// Writes a character to the console screen buffer a specified number of times, beginning at the specified coordinates.
     /// Returns the number of characters that have been written.
     ///
+    /// Wide characters, which take two cells, are rejected with [`io::ErrorKind::InvalidInput`],
+    /// as each cell would get the whole character. Use [`Console::fill_wide_char`] for them.
+    ///
     /// This wraps
-    /// [`FillConsoleOutputCharacterA`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputcharacter).
+    /// [`FillConsoleOutputCharacterW`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputcharacter).
     pub fn fill_whit_character(
         &self,
         start_location: Coord,
         cells_to_write: u32,
         filling_char: char,
     ) -> Result<u32> {
+        if screen_buffer::cell_width(filling_char) != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{:?} takes two cells, fill with `fill_wide_char` instead",
+                    filling_char
+                ),
+            ));
+        }
+
         let mut chars_written = 0;
-        result(unsafe {
-            // fill the cells in console with blanks
-            FillConsoleOutputCharacterA(
-                *self.handle,
-                filling_char as i8,
-                cells_to_write,
-                COORD::from(start_location),
-                &mut chars_written,
-            )
-        })?;
+        result(trace::call(
+            "FillConsoleOutputCharacterW",
+            *self.handle,
+            || unsafe {
+                // fill the cells in console with blanks
+                FillConsoleOutputCharacterW(
+                    *self.handle,
+                    filling_char as u16,
+                    cells_to_write,
+                    COORD::from(start_location),
+                    &mut chars_written,
+                )
+            },
+        ))
+        .with_handle_context("FillConsoleOutputCharacterW", &self.handle)?;
 
         Ok(chars_written)
     }
 
+    /// Write the wide character `c` `count` times with `attributes`, beginning at
+    /// `start_location`, returning the number of cells that were written.
+    ///
+    /// Each character takes two cells, marked with the leading and trailing byte attributes the
+    /// way the console host marks wide characters it writes itself. A character that doesn't
+    /// fit at the end of a line is moved to the next line, and the last cell of the line is
+    /// filled with a space. Characters that don't fit in the buffer are not written.
+    ///
+    /// Only wide characters of the basic multilingual plane can be used, others are rejected with
+    /// [`io::ErrorKind::InvalidInput`].
+    ///
+    /// This wraps
+    /// [`WriteConsoleOutputW`](https://docs.microsoft.com/en-us/windows/console/writeconsoleoutput).
+    pub fn fill_wide_char(
+        &self,
+        start_location: Coord,
+        count: u32,
+        c: char,
+        attributes: u16,
+    ) -> Result<u32> {
+        let unit = match u16::try_from(u32::from(c)) {
+            Ok(unit) if screen_buffer::cell_width(c) == 2 => unit,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{:?} is not a wide character of one code unit", c),
+                ))
+            }
+        };
+        let buffer_size = ScreenBuffer::from(self.handle.clone())
+            .info()?
+            .buffer_size();
+
+        let cell = |unit: u16, attributes: u16| {
+            let mut cell = CHAR_INFO::default();
+            unsafe { *cell.Char.UnicodeChar_mut() = unit };
+            cell.Attributes = attributes;
+            cell
+        };
+        let attributes = attributes & !(COMMON_LVB_LEADING_BYTE | COMMON_LVB_TRAILING_BYTE);
+        let mut cells_written = 0;
+        for row in wide_fill_rows(start_location, count, buffer_size) {
+            let mut cells = Vec::with_capacity(row.chars as usize * 2 + 1);
+            for _ in 0..row.chars {
+                cells.push(cell(unit, attributes | COMMON_LVB_LEADING_BYTE));
+                cells.push(cell(unit, attributes | COMMON_LVB_TRAILING_BYTE));
+            }
+            if row.pad {
+                cells.push(cell(u16::from(b' '), attributes));
+            }
+
+            let mut region = SMALL_RECT {
+                Left: row.start.x,
+                Top: row.start.y,
+                Right: row.start.x + cells.len() as i16 - 1,
+                Bottom: row.start.y,
+            };
+            result(trace::call(
+                "WriteConsoleOutputW",
+                *self.handle,
+                || unsafe {
+                    WriteConsoleOutputW(
+                        *self.handle,
+                        cells.as_ptr(),
+                        COORD {
+                            X: cells.len() as i16,
+                            Y: 1,
+                        },
+                        COORD { X: 0, Y: 0 },
+                        &mut region,
+                    )
+                },
+            ))
+            .with_handle_context("WriteConsoleOutputW", &self.handle)?;
+            cells_written += cells.len() as u32;
+        }
+
+        Ok(cells_written)
+    }
+
     /// Sets the character attributes for a specified number of character cells, beginning at the specified coordinates in a screen buffer.
     /// Returns the number of cells that have been modified.
     ///
@@ -98,15 +352,20 @@ impl Console {
     ) -> Result<u32> {
         let mut cells_written = 0;
         // Get the position of the current console window
-        result(unsafe {
-            FillConsoleOutputAttribute(
-                *self.handle,
-                dw_attribute,
-                cells_to_write,
-                COORD::from(start_location),
-                &mut cells_written,
-            )
-        })?;
+        result(trace::call(
+            "FillConsoleOutputAttribute",
+            *self.handle,
+            || unsafe {
+                FillConsoleOutputAttribute(
+                    *self.handle,
+                    dw_attribute,
+                    cells_to_write,
+                    COORD::from(start_location),
+                    &mut cells_written,
+                )
+            },
+        ))
+        .with_handle_context("FillConsoleOutputAttribute", &self.handle)?;
 
         Ok(cells_written)
     }
@@ -115,13 +374,23 @@ impl Console {
     ///
     /// This wraps [`GetLargestConsoleWindowSize`](https://docs.microsoft.com/en-us/windows/console/getlargestconsolewindowsize)
     pub fn largest_window_size(&self) -> Result<Coord> {
-        crate::coord_result(unsafe { GetLargestConsoleWindowSize(*self.handle) })
+        crate::coord_result(trace::call(
+            "GetLargestConsoleWindowSize",
+            *self.handle,
+            || unsafe { GetLargestConsoleWindowSize(*self.handle) },
+        ))
+        .with_handle_context("GetLargestConsoleWindowSize", &self.handle)
     }
 
     /// Writes a character string to a console screen buffer beginning at the current cursor location.
     ///
+    /// If the handle is not a console, for example because the output is redirected to a file,
+    /// the UTF-8 bytes are written with `WriteFile` instead, unless this was disabled with
+    /// [`ConsoleBuilder::write_fallback`].
+    ///
     /// This wraps
-    /// [`WriteConsoleW`](https://docs.microsoft.com/en-us/windows/console/writeconsole).
+    /// [`WriteConsoleW`](https://docs.microsoft.com/en-us/windows/console/writeconsole) and
+    /// [`WriteFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-writefile).
     pub fn write_char_buffer(&self, buf: &[u8]) -> Result<usize> {
         // get string from u8[] and parse it to an c_str
         let utf8 = match str::from_utf8(buf) {
@@ -134,46 +403,274 @@ impl Console {
             }
         };
 
+        let start = Instant::now();
         let utf16: Vec<u16> = utf8.encode_utf16().collect();
-        let utf16_ptr: *const c_void = utf16.as_ptr() as *const _ as *const c_void;
+        let written = self.write_console(&utf16);
+
+        match written {
+            Err(e) if self.write_fallback && is_not_a_console(&e) => {
+                self.write_file(utf8.as_bytes())?;
+            }
+            written => written?,
+        }
+
+        if let Some(io_stats) = &self.io_stats {
+            io_stats.record(WriteSample {
+                bytes: utf8.len(),
+                duration: start.elapsed(),
+            });
+        }
+        Ok(utf8.len())
+    }
+
+    /// Write all code units to the console with `WriteConsoleW`.
+    ///
+    /// Some console hosts fail large writes with `ERROR_NOT_ENOUGH_MEMORY`, in which case the
+    /// write is retried in chunks that are halved until it succeeds.
+    fn write_console(&self, mut utf16: &[u16]) -> Result<()> {
+        let mut max_chunk = utf16.len();
+        while !utf16.is_empty() {
+            let chunk = &utf16[..chunk_len(utf16, max_chunk)];
+            let mut written: u32 = 0;
+            let result = result(trace::call("WriteConsoleW", *self.handle, || unsafe {
+                WriteConsoleW(
+                    *self.handle,
+                    chunk.as_ptr() as *const c_void,
+                    chunk.len() as u32,
+                    &mut written,
+                    NULL,
+                )
+            }))
+            .with_handle_context("WriteConsoleW", &self.handle);
 
-        let mut cells_written: u32 = 0;
+            match result {
+                Err(e)
+                    if ApiError::raw_os_error(&e) == Some(ERROR_NOT_ENOUGH_MEMORY as i32)
+                        && chunk.len() > MIN_WRITE_CHUNK =>
+                {
+                    max_chunk = chunk.len() / 2;
+                    trace::warn(
+                        "WriteConsoleW",
+                        *self.handle,
+                        &format!(
+                            "not enough memory to write {} code units, retrying with {}",
+                            chunk.len(),
+                            max_chunk
+                        ),
+                    );
+                    continue;
+                }
+                result => result?,
+            }
+
+            if written == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "WriteConsoleW wrote nothing",
+                ));
+            }
+            utf16 = &utf16[(written as usize).min(utf16.len())..];
+        }
+
+        Ok(())
+    }
+
+    /// Write all bytes to the handle with `WriteFile`.
+    fn write_file(&self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            let mut bytes_written = 0;
+            result(trace::call("WriteFile", *self.handle, || unsafe {
+                WriteFile(
+                    *self.handle,
+                    buf.as_ptr() as *const c_void,
+                    buf.len().min(u32::MAX as usize) as u32,
+                    &mut bytes_written,
+                    ptr::null_mut(),
+                )
+            }))
+            .with_handle_context("WriteFile", &self.handle)?;
+            if bytes_written == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "WriteFile wrote nothing",
+                ));
+            }
+            buf = &buf[bytes_written as usize..];
+        }
+
+        Ok(())
+    }
+
+    /// Read the next chunk of text from the input as UTF-8 bytes, returning an empty vector at
+    /// the end of piped input.
+    ///
+    /// On a console this reads what the console returns at once, a whole line unless line
+    /// input is disabled. A console read that returns nothing, for example because Ctrl+C
+    /// interrupted it, fails with [`ErrorKind::Interrupted`](io::ErrorKind::Interrupted).
+    ///
+    /// If the handle is not a console, for example because the input is piped into the process,
+    /// the bytes are read with `ReadFile` instead and decoded from the input code page of the
+    /// console, so the same loop handles interactive and piped input.
+    ///
+    /// This wraps
+    /// [`ReadConsoleW`](https://docs.microsoft.com/en-us/windows/console/readconsole) and
+    /// [`ReadFile`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-readfile).
+    pub fn read_bytes(&self) -> Result<Vec<u8>> {
+        let mut units = vec![0u16; READ_CHUNK];
+        let read = match self.read_console(&mut units) {
+            Err(e) if is_not_a_console(&e) => return self.read_file_decoded(),
+            read => read?,
+        };
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the read was interrupted",
+            ));
+        }
+        units.truncate(read);
+        // Don't split a surrogate pair between two reads.
+        if matches!(units.last(), Some(0xD800..=0xDBFF)) {
+            let mut low = [0u16; 1];
+            let read = self.read_console(&mut low)?;
+            units.extend_from_slice(&low[..read]);
+        }
+        Ok(String::from_utf16_lossy(&units).into_bytes())
+    }
 
-        result(unsafe {
-            WriteConsoleW(
+    /// Read up to `units.len()` code units with `ReadConsoleW`, returning how many were read.
+    fn read_console(&self, units: &mut [u16]) -> Result<usize> {
+        let mut read = 0;
+        result(trace::call("ReadConsoleW", *self.handle, || unsafe {
+            ReadConsoleW(
                 *self.handle,
-                utf16_ptr,
-                utf16.len() as u32,
-                &mut cells_written,
-                NULL,
+                units.as_mut_ptr().cast(),
+                units.len() as u32,
+                &mut read,
+                ptr::null_mut(),
             )
-        })?;
+        }))
+        .with_handle_context("ReadConsoleW", &self.handle)?;
+        Ok(read as usize)
+    }
+
+    /// Read the next chunk with `ReadFile` and decode it from the input code page.
+    fn read_file_decoded(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![0u8; READ_CHUNK];
+        let read = self.read_file(&mut bytes)?;
+        bytes.truncate(read);
+
+        let code_page = encoding::input_code_page();
+        if code_page == CP_UTF8 {
+            // Don't split a character between two reads.
+            let mut missing = encoding::missing_utf8_bytes(&bytes);
+            while missing > 0 {
+                let mut tail = [0u8; 3];
+                let read = self.read_file(&mut tail[..missing])?;
+                if read == 0 {
+                    break;
+                }
+                bytes.extend_from_slice(&tail[..read]);
+                missing -= read;
+            }
+            return Ok(bytes);
+        }
+        // Don't split a two byte character between two reads.
+        if encoding::ends_in_lead_byte(code_page, &bytes) {
+            let mut trail = [0u8; 1];
+            let read = self.read_file(&mut trail)?;
+            bytes.extend_from_slice(&trail[..read]);
+        }
+        Ok(encoding::from_code_page(code_page, &bytes)?.into_bytes())
+    }
 
-        Ok(utf8.as_bytes().len())
+    /// Read up to `buf.len()` bytes with `ReadFile`, returning `0` at the end of the input.
+    fn read_file(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut read = 0;
+        let ret = trace::call("ReadFile", *self.handle, || unsafe {
+            ReadFile(
+                *self.handle,
+                buf.as_mut_ptr().cast(),
+                buf.len() as u32,
+                &mut read,
+                ptr::null_mut(),
+            )
+        });
+        match result(ret).with_handle_context("ReadFile", &self.handle) {
+            // The writing end of a pipe was closed.
+            Err(e) if ApiError::raw_os_error(&e) == Some(ERROR_BROKEN_PIPE as i32) => Ok(0),
+            ret => ret.map(|()| read as usize),
+        }
     }
 
-    /// Read one input event.
+    /// Read one input event, blocking until a record that isn't dropped arrives.
+    ///
+    /// Reserved records are skipped if disabled with
+    /// [`ConsoleBuilder::include_reserved_events`], and records are skipped according to the
+    /// [`ConsoleBuilder::event_filter`]. Use
+    /// [`try_read_single_input_event`](Console::try_read_single_input_event) after waiting on
+    /// the handle with a timeout, as this blocks again when a record is skipped.
     ///
     /// This wraps
     /// [`ReadConsoleInputW`](https://docs.microsoft.com/en-us/windows/console/readconsoleinput).
     pub fn read_single_input_event(&self) -> Result<InputRecord> {
-        let mut record: INPUT_RECORD = INPUT_RECORD::default();
-
-        {
-            // Convert an INPUT_RECORD to an &mut [INPUT_RECORD] of length 1
-            let buf = slice::from_mut(&mut record);
-            let num_read = self.read_input(buf)?;
-
-            // The windows API promises that ReadConsoleInput returns at least
-            // 1 element
-            debug_assert!(num_read == 1);
+        loop {
+            if let Some(record) = self.try_read_single_input_event()? {
+                return Ok(record);
+            }
         }
+    }
+
+    /// Read one input record, returning `None` if it is dropped like in
+    /// [`read_single_input_event`](Console::read_single_input_event).
+    ///
+    /// Fails if the host reports a record that can't be converted, see
+    /// [`InputRecord::try_from_winapi`].
+    ///
+    /// This reads exactly one record, so it only blocks when the input buffer is empty. A caller
+    /// that waited for the handle to be signaled doesn't block when the record it was signaled
+    /// for is dropped.
+    ///
+    /// This wraps
+    /// [`ReadConsoleInputW`](https://docs.microsoft.com/en-us/windows/console/readconsoleinput).
+    pub fn try_read_single_input_event(&self) -> Result<Option<InputRecord>> {
+        read_once(
+            || {
+                let mut record: INPUT_RECORD = INPUT_RECORD::default();
+                // Convert an INPUT_RECORD to an &mut [INPUT_RECORD] of length 1
+                let num_read = self.read_input(slice::from_mut(&mut record))?;
+                // The windows API promises that ReadConsoleInput returns at least
+                // 1 element
+                debug_assert!(num_read == 1);
+                Ok(record)
+            },
+            |record| self.accept_raw(record),
+        )
+    }
 
-        Ok(record.into())
+    /// Read one input record like
+    /// [`try_read_single_input_event`](Console::try_read_single_input_event), calling `on_stall`
+    /// every `timeout` while the read blocks although the input buffer holds records.
+    ///
+    /// Returns `None` if the record read is dropped by the filters, the read isn't repeated.
+    ///
+    /// A read that doesn't return while records are pending usually means another reader took
+    /// them, or a filter or mode drops them. The [`StallReport`] contains the
+    /// number of pending records and the input mode to track this down. The watchdog runs on a
+    /// separate thread for the duration of the read.
+    pub fn read_with_watchdog(
+        &self,
+        timeout: Duration,
+        on_stall: impl FnMut(&StallReport) + Send,
+    ) -> Result<Option<InputRecord>> {
+        read_watchdog::read_with_watchdog(self, timeout, on_stall)
     }
 
     /// Read all available input events without blocking.
     ///
+    /// Reserved records are dropped if disabled with
+    /// [`ConsoleBuilder::include_reserved_events`], and records are dropped according to the
+    /// [`ConsoleBuilder::event_filter`].
+    ///
     /// This wraps
     /// [`ReadConsoleInputW`](https://docs.microsoft.com/en-us/windows/console/readconsoleinput).
     pub fn read_console_input(&self) -> Result<Vec<InputRecord>> {
@@ -190,11 +687,45 @@ impl Console {
 
         let num_read = self.read_input(buf.as_mut_slice())?;
 
-        Ok(buf
-            .into_iter()
+        buf.into_iter()
             .take(num_read)
-            .map(InputRecord::from)
-            .collect())
+            .map(|record| self.accept_raw(record))
+            .filter_map(Result::transpose)
+            .collect()
+    }
+
+    /// Collect all input events that arrive within `duration`, returning once it has passed.
+    ///
+    /// This never blocks past the deadline, which makes it suitable for game loops with a fixed
+    /// frame time. The events are read with
+    /// [`read_console_input`](Console::read_console_input), so filters apply.
+    ///
+    /// This wraps
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+    pub fn drain_input_for(&self, duration: Duration) -> Result<Vec<InputRecord>> {
+        let deadline = Instant::now() + duration;
+        let mut records = Vec::new();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            // Round up, so the last wait doesn't return early and spin.
+            let millis =
+                ((remaining.as_micros() + 999) / 1000).min(u128::from(u32::MAX - 1)) as u32;
+            let wait = trace::call("WaitForSingleObject", *self.handle, || unsafe {
+                WaitForSingleObject(*self.handle, millis)
+            });
+
+            match wait {
+                WAIT_OBJECT_0 => records.extend(self.read_console_input()?),
+                WAIT_TIMEOUT => break,
+                _ => return Err(io::Error::last_os_error()),
+            }
+            if remaining.is_zero() {
+                break;
+            }
+        }
+
+        Ok(records)
     }
 
     /// Get the number of available input events that can be read without blocking.
@@ -203,34 +734,669 @@ impl Console {
     /// [`GetNumberOfConsoleInputEvents`](https://docs.microsoft.com/en-us/windows/console/getnumberofconsoleinputevents).
     pub fn number_of_console_input_events(&self) -> Result<u32> {
         let mut buf_len: DWORD = 0;
-        result(unsafe { GetNumberOfConsoleInputEvents(*self.handle, &mut buf_len) })?;
+        result(trace::call(
+            "GetNumberOfConsoleInputEvents",
+            *self.handle,
+            || unsafe { GetNumberOfConsoleInputEvents(*self.handle, &mut buf_len) },
+        ))
+        .with_handle_context("GetNumberOfConsoleInputEvents", &self.handle)?;
         Ok(buf_len)
     }
 
+    /// Wait for input by polling for up to `max_spin`, then blocking if `then_block` is set.
+    ///
+    /// Returns whether input is available. Polling with
+    /// [`number_of_console_input_events`](Console::number_of_console_input_events) notices
+    /// input sooner than a blocking wait, which has to be woken by the scheduler, but burns CPU
+    /// while it spins. Spinning for a short time before blocking gives the latency of the former
+    /// when input arrives quickly, and the idle CPU usage of the latter when it doesn't. The
+    /// `input_polling` benchmark compares spin durations.
+    ///
+    /// A `max_spin` of zero checks once. Without `then_block`, this returns `false` if no input
+    /// arrived while spinning.
+    ///
+    /// This wraps
+    /// [`GetNumberOfConsoleInputEvents`](https://docs.microsoft.com/en-us/windows/console/getnumberofconsoleinputevents)
+    /// and
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+    pub fn spin_wait_for_input(&self, max_spin: Duration, then_block: bool) -> Result<bool> {
+        let start = Instant::now();
+        loop {
+            if self.number_of_console_input_events()? > 0 {
+                return Ok(true);
+            }
+            if start.elapsed() >= max_spin {
+                break;
+            }
+            hint::spin_loop();
+        }
+
+        if !then_block {
+            return Ok(false);
+        }
+        match trace::call("WaitForSingleObject", *self.handle, || unsafe {
+            WaitForSingleObject(*self.handle, INFINITE)
+        }) {
+            WAIT_OBJECT_0 => Ok(true),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    /// Write input events to the input buffer, as if they were input by the user.
+    /// Returns the number of events that have been written.
+    ///
+    /// This must be called on a console input handle. The records can be built with
+    /// [`KeyEventRecord::builder`](crate::KeyEventRecord::builder) and
+    /// [`MouseEvent::builder`](crate::MouseEvent::builder).
+    ///
+    /// This wraps
+    /// [`WriteConsoleInputW`](https://docs.microsoft.com/en-us/windows/console/writeconsoleinput).
+    pub fn write_input(&self, records: &[InputRecord]) -> Result<usize> {
+        let buf: Vec<INPUT_RECORD> = records.iter().cloned().map(INPUT_RECORD::from).collect();
+        let mut num_written = 0;
+
+        result(trace::call("WriteConsoleInputW", *self.handle, || unsafe {
+            WriteConsoleInputW(
+                *self.handle,
+                buf.as_ptr(),
+                buf.len() as u32,
+                &mut num_written,
+            )
+        }))
+        .with_handle_context("WriteConsoleInputW", &self.handle)?;
+
+        Ok(num_written as usize)
+    }
+
+    /// Translate a point in screen pixel coordinates to the cell of the screen buffer under it.
+    ///
+    /// Window messages like `WM_DROPFILES` report positions in pixels, this maps them to the cell
+    /// using the position of the console window, the size of the font and the part of the screen
+    /// buffer that is visible. Points outside the visible window are clamped to its edge.
+    ///
+    /// This must be called on a screen buffer handle. Hosts that don't draw the console window
+    /// themselves, like Windows Terminal, don't report a meaningful window position.
+    ///
+    /// This wraps
+    /// [`GetConsoleWindow`](https://docs.microsoft.com/en-us/windows/console/getconsolewindow) and
+    /// [`ClientToScreen`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-clienttoscreen).
+    pub fn pixel_to_cell(&self, point: (i32, i32)) -> Result<Coord> {
+        let console_window = ConsoleWindow::current()?;
+        let window = console_window.hwnd();
+
+        let mut origin = POINT { x: 0, y: 0 };
+        result(trace::call("ClientToScreen", ptr::null_mut(), || unsafe {
+            ClientToScreen(window, &mut origin)
+        }))
+        .with_context("ClientToScreen")?;
+
+        let screen_buffer = ScreenBuffer::new(self.handle.clone());
+        let font_size = screen_buffer.font_info()?.size();
+        if font_size.width <= 0 || font_size.height <= 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the console reported an empty font size",
+            ));
+        }
+
+        let info = screen_buffer.info()?;
+        let mut point = (point.0 - origin.x, point.1 - origin.y);
+        // A process that is not DPI aware sees logical pixels, while the font size is physical.
+        let dpi = console_window.dpi();
+        let client = console_window.client_size().ok();
+        if SizeReport::measure(&info, font_size, client, dpi, false).source == SizeSource::DpiScaled
+        {
+            point = scale_point(point, dpi);
+        }
+
+        Ok(pixel_to_cell(point, font_size, info.terminal_window()))
+    }
+
+    /// Simulate typing `text` by writing a key down and a key up record for every character to
+    /// the input buffer. Returns the number of records that have been written.
+    ///
+    /// Line breaks (`\n` or `\r\n`) are sent as the enter key, characters outside the basic
+    /// multilingual plane as a pair of records per surrogate, as the console does for typed input.
+    ///
+    /// This must be called on a console input handle, see [`Console::write_input`].
+    pub fn paste_text(&self, text: &str) -> Result<usize> {
+        self.write_input(&paste_records(text))
+    }
+
+    /// Read input records into `buf` without translating them, returning the number of records
+    /// read.
+    ///
+    /// This blocks until at least one record is available, use
+    /// [`number_of_console_input_events`](Console::number_of_console_input_events) or a wait on
+    /// the handle to poll. Nothing is allocated, so a buffer can be reused for every read. An
+    /// empty buffer returns `0` immediately.
+    ///
+    /// This wraps
+    /// [`ReadConsoleInputW`](https://docs.microsoft.com/en-us/windows/console/readconsoleinput).
+    pub fn read_raw_input(&self, buf: &mut [INPUT_RECORD]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min(u32::MAX as usize - 1);
+        self.read_input(&mut buf[..len])
+    }
+
+    /// Deliver Ctrl+C as a key event until the returned guard is dropped, for example to use it
+    /// as a copy shortcut.
+    ///
+    /// This clears `ENABLE_PROCESSED_INPUT` on this input buffer. While the guard is alive:
+    ///
+    /// | Input                         | Key event | Console control handlers                     |
+    /// |-------------------------------|-----------|----------------------------------------------|
+    /// | Ctrl+C                        | yes       | not called                                   |
+    /// | Ctrl+Break                    | no        | called with `CTRL_BREAK_EVENT`               |
+    /// | `GenerateConsoleCtrlEvent`    | no        | called, the mode doesn't affect these events |
+    /// | Closing the console, logoff   | no        | called                                       |
+    ///
+    /// Handlers registered with `SetConsoleCtrlHandler` stay registered, they are simply not
+    /// called for Ctrl+C. The processed input flag also controls the handling of backspace,
+    /// carriage return and line feed in line input mode, which is turned off too. Raw mode, like
+    /// [`TerminalSession`](crate::TerminalSession) sets up, already clears the flag.
+    pub fn capture_ctrl_c(&self) -> Result<CtrlCCaptureGuard> {
+        CtrlCCaptureGuard::new(self.handle.clone())
+    }
+
+    /// Read a line with echo turned off, for example for a password prompt.
+    ///
+    /// Line input and processed input are turned on for the read, and the original mode is
+    /// restored afterwards. If Ctrl+C or Ctrl+Break is pressed during the read, a console control
+    /// handler restores the mode before the default handler ends the process. As the line break
+    /// isn't echoed either, print one after the read. The returned line doesn't contain it.
+    ///
+    /// This must be called on a console input handle.
+    ///
+    /// This wraps
+    /// [`ReadConsoleW`](https://docs.microsoft.com/en-us/windows/console/readconsole).
+    #[cfg(feature = "hidden-input")]
+    pub fn read_line_hidden(&self) -> Result<crate::SecretString> {
+        crate::hidden_input::read_line_hidden(&self.handle)
+    }
+
+    /// Read a line with the console's line editor, returning early when a key in `wakeup` is
+    /// pressed.
+    ///
+    /// This allows a shell to complete the word under the cursor on Tab while keeping the
+    /// editing, history and IME support of the console: read with [`WakeupKeys::TAB`], print the
+    /// completion and read again with the completed text as `initial`. The console treats
+    /// `initial` as already typed, so it must be on the screen before the cursor already.
+    ///
+    /// This must be called on a console input handle with line input enabled.
+    ///
+    /// This wraps
+    /// [`ReadConsoleW`](https://docs.microsoft.com/en-us/windows/console/readconsole) with a
+    /// [`CONSOLE_READCONSOLE_CONTROL`](https://docs.microsoft.com/en-us/windows/console/console-readconsole-control).
+    pub fn read_line_with(&self, initial: &str, wakeup: WakeupKeys) -> Result<CookedLine> {
+        cooked_read::read_line_with(&self.handle, initial, wakeup)
+    }
+
+    /// Start a thread that reads the events of this input buffer and broadcasts them to every
+    /// receiver of the returned [`EventChannel`](crate::EventChannel).
+    ///
+    /// This must be called on a console input handle.
+    #[cfg(feature = "event-channel")]
+    pub fn event_channel(&self) -> Result<crate::EventChannel> {
+        crate::EventChannel::new(self.clone())
+    }
+
+    /// Ask screen readers to announce `text`, for example to report a status change.
+    ///
+    /// This raises a UI Automation notification on the console window. It fails with
+    /// [`io::ErrorKind::Unsupported`] before Windows 10 1709, and with
+    /// [`io::ErrorKind::NotFound`] if the process has no console window. Whether the
+    /// notification is announced depends on the screen reader and the console host.
+    pub fn announce(&self, text: &str, priority: AnnouncePriority) -> Result<()> {
+        crate::announce::announce(ConsoleWindow::current()?.hwnd(), text, priority)
+    }
+
+    /// Add a filter that sees every record read with
+    /// [`read_single_input_event`](Console::read_single_input_event) and
+    /// [`read_console_input`](Console::read_console_input) before it is returned, and can drop
+    /// or replace it.
+    ///
+    /// The filters are shared by all clones of this `Console` and run in the order they were
+    /// added. Use [`Events::with_console`](crate::Events::with_console) to apply them to an event
+    /// stream.
+    pub fn add_input_filter(&self, filter: impl Fn(&InputRecord) -> FilterAction + Send + 'static) {
+        self.filters.add(Box::new(filter));
+    }
+
+    /// Remove all filters added with [`add_input_filter`](Console::add_input_filter).
+    pub fn clear_input_filters(&self) {
+        self.filters.clear();
+    }
+
+    /// Apply the [`EventFilter`] and then [`accept`](Console::accept) to a record read from the
+    /// console, failing if the record can't be converted.
+    fn accept_raw(&self, record: INPUT_RECORD) -> Result<Option<InputRecord>> {
+        if !self.event_filter.accepts(record.EventType) {
+            return Ok(None);
+        }
+        Ok(self.accept(InputRecord::try_from_winapi(record)?))
+    }
+
+    fn accept(&self, record: InputRecord) -> Option<InputRecord> {
+        if !self.include_reserved_events && self.is_reserved(&record) {
+            return None;
+        }
+        self.filters.apply(record)
+    }
+
+    /// Returns whether `record` is only used by the console host, focus records are real focus
+    /// changes on hosts that report them.
+    fn is_reserved(&self, record: &InputRecord) -> bool {
+        match record {
+            InputRecord::FocusEvent(_) => !self.focus_events,
+            _ => record.reserved().is_some(),
+        }
+    }
+
+    /// Bring the console back to a sane state after a crash or a program that left it wedged.
+    ///
+    /// This makes the screen buffer of this `Console` active again and applies
+    /// [`ConsoleModePreset::Cooked`] to it and to the standard input, keeping virtual terminal
+    /// processing as it was so a host that relies on it keeps rendering. It restores the
+    /// [`default_attributes`](ScreenBuffer::default_attributes) of the process, shows the cursor
+    /// and scrolls the window so the cursor is on its last line.
+    ///
+    /// Create the `Console` from [`Handle::console_output_handle`] to reset the primary buffer of
+    /// the standard output, or of the standard error if the output is redirected.
+    ///
+    /// Every step is attempted even if an earlier one fails, the first error is returned.
+    pub fn soft_reset(&self) -> Result<()> {
+        let mut first_error = None;
+        let mut step = |result: Result<()>| {
+            if let Err(e) = result {
+                first_error.get_or_insert(e);
+            }
+        };
+
+        let buffer = ScreenBuffer::from(self.handle.clone());
+        step(buffer.show());
+        let output_mode = ConsoleMode::from(self.handle.clone());
+        step(output_mode.mode().and_then(|mode| {
+            let vt_processing = mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+            output_mode.set_mode(ConsoleModePreset::Cooked.output_mode() | vt_processing)
+        }));
+
+        step(buffer.reset_to_default_attributes());
+        step(self.show_cursor());
+        step(buffer.info().and_then(|info| {
+            let window = bottom_window(
+                info.terminal_window(),
+                info.cursor_pos(),
+                info.buffer_size().height,
+            );
+            self.set_console_info(true, window)
+        }));
+        step(
+            ConsoleMode::from_handle_type(HandleType::InputHandle)
+                .and_then(|mode| ConsoleModePreset::Cooked.apply(&mode)),
+        );
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Make the cursor visible, keeping its size.
+    fn show_cursor(&self) -> Result<()> {
+        let mut info = CONSOLE_CURSOR_INFO {
+            dwSize: DEFAULT_CURSOR_SIZE,
+            bVisible: 1,
+        };
+        trace::call("GetConsoleCursorInfo", *self.handle, || unsafe {
+            GetConsoleCursorInfo(*self.handle, &mut info)
+        });
+        if !(1..=100).contains(&info.dwSize) {
+            info.dwSize = DEFAULT_CURSOR_SIZE;
+        }
+        info.bVisible = 1;
+        result(trace::call(
+            "SetConsoleCursorInfo",
+            *self.handle,
+            || unsafe { SetConsoleCursorInfo(*self.handle, &info) },
+        ))
+        .with_handle_context("SetConsoleCursorInfo", &self.handle)
+    }
+
+    /// Get the size and duration of the most recent writes, or `None` if recording wasn't enabled
+    /// with [`ConsoleBuilder::record_io_stats`].
+    ///
+    /// Only successful writes are recorded.
+    pub fn io_stats(&self) -> Option<IoStats> {
+        self.io_stats.as_ref().map(IoRecorder::snapshot)
+    }
+
+    /// Get the underlying raw `HANDLE` used by this type to execute with.
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
     /// Read input (via ReadConsoleInputW) into buf and return the number
     /// of events read. ReadConsoleInputW guarantees that at least one event
     /// is read, even if it means blocking the thread. buf.len() must fit in
     /// a u32.
     fn read_input(&self, buf: &mut [INPUT_RECORD]) -> Result<usize> {
         let mut num_records = 0;
-        debug_assert!(buf.len() < std::u32::MAX as usize);
+        debug_assert!(buf.len() < u32::MAX as usize);
 
-        result(unsafe {
+        result(trace::call("ReadConsoleInputW", *self.handle, || unsafe {
             ReadConsoleInputW(
                 *self.handle,
                 buf.as_mut_ptr(),
                 buf.len() as u32,
                 &mut num_records,
             )
-        })?;
+        }))
+        .with_handle_context("ReadConsoleInputW", &self.handle)?;
 
         Ok(num_records as usize)
     }
 }
 
+/// Read one record with `read` and pass it through `accept`, without reading again when the
+/// record is dropped.
+fn read_once(
+    read: impl FnOnce() -> Result<INPUT_RECORD>,
+    accept: impl FnOnce(INPUT_RECORD) -> Result<Option<InputRecord>>,
+) -> Result<Option<InputRecord>> {
+    read().and_then(accept)
+}
+
+/// Get the key records that type `text`.
+fn paste_records(text: &str) -> Vec<InputRecord> {
+    let mut records = Vec::with_capacity(text.len() * 2);
+    let mut push_key = |virtual_key_code: u16, u_char: u16| {
+        for key_down in [true, false] {
+            records.push(InputRecord::KeyEvent(
+                KeyEventRecord::builder()
+                    .key_down(key_down)
+                    .virtual_key_code(virtual_key_code)
+                    .u_char(u_char)
+                    .build(),
+            ));
+        }
+    };
+
+    for line_or_break in text.replace("\r\n", "\n").split_inclusive('\n') {
+        let (line, has_break) = match line_or_break.strip_suffix('\n') {
+            Some(line) => (line, true),
+            None => (line_or_break, false),
+        };
+        for unit in line.encode_utf16() {
+            let virtual_key_code = if unit == u16::from(b'\t') { VK_TAB } else { 0 };
+            push_key(virtual_key_code as u16, unit);
+        }
+        if has_break {
+            push_key(VK_RETURN as u16, u16::from(b'\r'));
+        }
+    }
+    records
+}
+
+/// Scale a point from logical pixels to the physical pixels of a window with `dpi`.
+fn scale_point(point: (i32, i32), dpi: u32) -> (i32, i32) {
+    let dpi = i64::from(dpi);
+    let scale = |value: i32| (i64::from(value) * dpi / i64::from(DEFAULT_DPI)) as i32;
+    (scale(point.0), scale(point.1))
+}
+
+/// Map a point in pixels relative to the client area to a cell of the visible `window`.
+fn pixel_to_cell(point: (i32, i32), font_size: Size, window: WindowPositions) -> Coord {
+    let column = point.0.div_euclid(i32::from(font_size.width));
+    let row = point.1.div_euclid(i32::from(font_size.height));
+    Coord::new(
+        (i32::from(window.left) + column).clamp(i32::from(window.left), i32::from(window.right))
+            as i16,
+        (i32::from(window.top) + row).clamp(i32::from(window.top), i32::from(window.bottom)) as i16,
+    )
+}
+
+/// Move `window` vertically so `cursor` is on its last line, or to the top of a buffer of
+/// `buffer_height` lines if the cursor is above the window height.
+fn bottom_window(window: WindowPositions, cursor: Coord, buffer_height: i16) -> WindowPositions {
+    let height = window.bottom - window.top;
+    let top = (cursor.y - height).clamp(0, (buffer_height - 1 - height).max(0));
+    WindowPositions {
+        top,
+        bottom: top + height,
+        ..window
+    }
+}
+
 impl From<Handle> for Console {
     /// Create a `Console` instance who's functions will be executed on the the given `Handle`
     fn from(handle: Handle) -> Self {
-        Console { handle }
+        Console {
+            handle,
+            write_fallback: true,
+            include_reserved_events: true,
+            focus_events: false,
+            event_filter: EventFilter::All,
+            filters: InputFilters::default(),
+            io_stats: None,
+        }
+    }
+}
+
+impl AsRawHandle for Console {
+    /// Get the raw handle, which stays owned by this `Console`.
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle.as_raw_handle()
+    }
+}
+
+impl AsHandle for Console {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+impl TryFrom<Console> for OwnedHandle {
+    type Error = io::Error;
+
+    /// Convert the handle of `console` into an [`OwnedHandle`] like converting a
+    /// [`Handle`], which duplicates it if it is shared.
+    fn try_from(console: Console) -> Result<OwnedHandle> {
+        OwnedHandle::try_from(console.handle)
+    }
+}
+
+impl FromRawHandle for Console {
+    /// Take ownership of `handle`, it is closed when the last clone of the `Console` is
+    /// dropped.
+    unsafe fn from_raw_handle(handle: RawHandle) -> Self {
+        Console::from(Handle::from_raw_handle(handle))
+    }
+}
+
+/// The smallest chunk `WriteConsoleW` is retried with after `ERROR_NOT_ENOUGH_MEMORY`.
+const MIN_WRITE_CHUNK: usize = 256;
+
+/// The most code units or bytes [`Console::read_bytes`] reads at once.
+const READ_CHUNK: usize = 4096;
+
+/// A line of a [`Console::fill_wide_char`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WideFillRow {
+    /// The first cell of the line that is written.
+    start: Coord,
+    /// The number of wide characters on the line.
+    chars: u32,
+    /// Whether the last cell of the line is filled with a space, as the next character doesn't
+    /// fit in it.
+    pad: bool,
+}
+
+/// Split writing `count` wide characters from `start` in a buffer of `buffer_size` into lines.
+fn wide_fill_rows(start: Coord, count: u32, buffer_size: Size) -> Vec<WideFillRow> {
+    let width = i32::from(buffer_size.width);
+    let mut rows = Vec::new();
+    let mut remaining = count;
+    let mut pos = start;
+    while remaining > 0 && pos.y < buffer_size.height && width >= 2 {
+        let cells = (width - i32::from(pos.x)).max(0);
+        let chars = ((cells / 2) as u32).min(remaining);
+        remaining -= chars;
+        let pad = remaining > 0 && cells % 2 == 1;
+        if chars > 0 || pad {
+            rows.push(WideFillRow {
+                start: pos,
+                chars,
+                pad,
+            });
+        }
+        pos = Coord::new(0, pos.y + 1);
+    }
+    rows
+}
+
+/// The length of the next chunk of `units` to write, at most `max` units long and not splitting
+/// a surrogate pair.
+fn chunk_len(units: &[u16], max: usize) -> usize {
+    let len = units.len().min(max.max(1));
+    if len < units.len() && len > 1 && (0xD800..0xDC00).contains(&units[len - 1]) {
+        len - 1
+    } else {
+        len
+    }
+}
+
+/// Returns whether the error is the one console functions fail with when the handle is not a
+/// console.
+fn is_not_a_console(error: &io::Error) -> bool {
+    ApiError::raw_os_error(error) == Some(ERROR_INVALID_HANDLE as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincontypes::INPUT_RECORD;
+
+    use crate::{Coord, InputRecord, Size, WindowPositions};
+
+    #[test]
+    fn test_dropped_record_is_not_read_again() {
+        let mut reads = 0;
+        let record = super::read_once(
+            || {
+                reads += 1;
+                Ok(INPUT_RECORD::default())
+            },
+            |_| Ok(None),
+        );
+        assert_eq!(record.unwrap(), None);
+        assert_eq!(reads, 1);
+    }
+
+    #[test]
+    fn test_bottom_window() {
+        let window = WindowPositions {
+            left: 0,
+            right: 79,
+            top: 0,
+            bottom: 24,
+        };
+        let bottom = super::bottom_window(window, Coord::new(3, 100), 9001);
+        assert_eq!((bottom.top, bottom.bottom), (76, 100));
+        let top = super::bottom_window(window, Coord::new(0, 10), 9001);
+        assert_eq!((top.top, top.bottom), (0, 24));
+        let end = super::bottom_window(window, Coord::new(0, 200), 120);
+        assert_eq!((end.top, end.bottom), (95, 119));
+    }
+
+    #[test]
+    fn test_wide_fill_rows() {
+        let size = Size::new(7, 3);
+        let row = |x, y, chars, pad| super::WideFillRow {
+            start: Coord::new(x, y),
+            chars,
+            pad,
+        };
+        assert_eq!(
+            super::wide_fill_rows(Coord::new(2, 0), 2, size),
+            [row(2, 0, 2, false)]
+        );
+        // 3 characters fit in the first line, the last cell is padded.
+        assert_eq!(
+            super::wide_fill_rows(Coord::new(0, 0), 5, size),
+            [row(0, 0, 3, true), row(0, 1, 2, false)]
+        );
+        // Only the last cell is left on the line.
+        assert_eq!(
+            super::wide_fill_rows(Coord::new(6, 0), 1, size),
+            [row(6, 0, 0, true), row(0, 1, 1, false)]
+        );
+        // The rest doesn't fit in the buffer.
+        assert_eq!(
+            super::wide_fill_rows(Coord::new(0, 2), 9, size),
+            [row(0, 2, 3, true)]
+        );
+    }
+
+    #[test]
+    fn test_chunk_len() {
+        let units: Vec<u16> = "ab😀c".encode_utf16().collect();
+        assert_eq!(super::chunk_len(&units, 10), 5);
+        assert_eq!(super::chunk_len(&units, 2), 2);
+        // Don't split the surrogate pair at index 2 and 3.
+        assert_eq!(super::chunk_len(&units, 3), 2);
+        assert_eq!(super::chunk_len(&units, 0), 1);
+    }
+
+    #[test]
+    fn test_paste_records() {
+        let records = super::paste_records("a\r\n😀");
+        let keys: Vec<(bool, u16, u16)> = records
+            .into_iter()
+            .map(|record| match record {
+                InputRecord::KeyEvent(key) => (key.key_down, key.virtual_key_code, key.u_char),
+                _ => panic!("expected a key event"),
+            })
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                (true, 0, 'a' as u16),
+                (false, 0, 'a' as u16),
+                (true, 0x0d, '\r' as u16),
+                (false, 0x0d, '\r' as u16),
+                (true, 0, 0xd83d),
+                (false, 0, 0xd83d),
+                (true, 0, 0xde00),
+                (false, 0, 0xde00),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scale_point() {
+        assert_eq!(super::scale_point((100, -40), 144), (150, -60));
+        assert_eq!(super::scale_point((100, 40), 96), (100, 40));
+    }
+
+    #[test]
+    fn test_pixel_to_cell() {
+        let font_size = Size::new(8, 16);
+        let window = WindowPositions {
+            left: 0,
+            top: 100,
+            right: 79,
+            bottom: 124,
+        };
+        assert_eq!(
+            super::pixel_to_cell((17, 33), font_size, window),
+            Coord::new(2, 102)
+        );
+        assert_eq!(
+            super::pixel_to_cell((-5, 10_000), font_size, window),
+            Coord::new(0, 124)
+        );
     }
 }
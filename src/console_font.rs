@@ -0,0 +1,188 @@
+use std::fmt;
+use std::io::Result;
+use std::mem;
+use std::ptr;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::um::wincon::{
+    GetCurrentConsoleFontEx, SetCurrentConsoleFontEx, CONSOLE_FONT_INFO, CONSOLE_FONT_INFOEX, COORD,
+};
+use winapi::um::winnt::HANDLE;
+
+use super::{
+    capability::proc_address, console_lock, result, trace, ErrorContext, FontInfo, Handle,
+    ScreenBuffer, Size,
+};
+
+/// The smallest font height [`ConsoleFont::scale_by`] goes down to.
+const MIN_FONT_HEIGHT: i16 = 4;
+
+/// The font of a screen buffer, which can be resized.
+///
+/// On the legacy console host the window can't be resized with virtual terminal sequences, so
+/// zooming in and out, like Ctrl+Plus and Ctrl+Minus in other terminals, changes the font size
+/// instead. Windows Terminal ignores font changes made by applications.
+///
+/// This wraps
+/// [`CONSOLE_FONT_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-font-infoex).
+#[derive(Clone)]
+pub struct ConsoleFont {
+    handle: Handle,
+    info: CONSOLE_FONT_INFOEX,
+}
+
+impl fmt::Debug for ConsoleFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsoleFont")
+            .field("face_name", &self.face_name())
+            .field("size", &self.size())
+            .field("weight", &self.weight())
+            .finish()
+    }
+}
+
+impl ConsoleFont {
+    /// Get the current font of `screen_buffer`.
+    ///
+    /// This wraps
+    /// [`GetCurrentConsoleFontEx`](https://docs.microsoft.com/en-us/windows/console/getcurrentconsolefontex).
+    pub fn current(screen_buffer: &ScreenBuffer) -> Result<ConsoleFont> {
+        let mut font = ConsoleFont {
+            handle: screen_buffer.handle().clone(),
+            info: unsafe { mem::zeroed() },
+        };
+        font.refresh()?;
+        Ok(font)
+    }
+
+    /// List the fonts the console host offers for `screen_buffer`, with their index in the font
+    /// table and their cell size.
+    ///
+    /// The list comes from the undocumented `GetNumberOfConsoleFonts` and `GetConsoleFontInfo`
+    /// functions, which are looked up in `kernel32.dll` at runtime. Console hosts that don't
+    /// have them, or don't report any fonts, only offer the current font, which is returned
+    /// alone. The sizes can be passed to [`set_size`](ConsoleFont::set_size).
+    ///
+    /// This wraps
+    /// [`GetCurrentConsoleFont`](https://docs.microsoft.com/en-us/windows/console/getcurrentconsolefont)
+    /// for the fallback.
+    pub fn enumerate(screen_buffer: &ScreenBuffer) -> Result<Vec<FontInfo>> {
+        match font_table(screen_buffer.handle()) {
+            Some(fonts) if !fonts.is_empty() => Ok(fonts),
+            _ => Ok(vec![screen_buffer.font_info()?]),
+        }
+    }
+
+    /// Get the name of the typeface, like `Consolas`.
+    pub fn face_name(&self) -> String {
+        let len = self
+            .info
+            .FaceName
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(self.info.FaceName.len());
+        String::from_utf16_lossy(&self.info.FaceName[..len])
+    }
+
+    /// Get the size of a cell in pixels.
+    pub fn size(&self) -> Size {
+        Size::from(self.info.dwFontSize)
+    }
+
+    /// Get the weight of the font, `400` is normal and `700` is bold.
+    pub fn weight(&self) -> u32 {
+        self.info.FontWeight
+    }
+
+    /// Re-apply the current typeface with a cell height of `height` pixels.
+    ///
+    /// The console picks the width that matches the height, and may round the height to a size
+    /// the font supports. [`size`](ConsoleFont::size) reports the size that was applied.
+    ///
+    /// This wraps
+    /// [`SetCurrentConsoleFontEx`](https://docs.microsoft.com/en-us/windows/console/setcurrentconsolefontex).
+    pub fn set_size(&mut self, height: i16) -> Result<()> {
+        let mut info = self.info;
+        info.nFont = 0;
+        info.dwFontSize = COORD { X: 0, Y: height };
+        {
+            let _lock = console_lock()?;
+            result(trace::call(
+                "SetCurrentConsoleFontEx",
+                *self.handle,
+                || unsafe { SetCurrentConsoleFontEx(*self.handle, 0, &mut info) },
+            ))
+            .with_handle_context("SetCurrentConsoleFontEx", &self.handle)?;
+        }
+        self.refresh()
+    }
+
+    /// Scale the cell height by `percent`, so `110` zooms in by 10% and `90` zooms out.
+    ///
+    /// The height changes by at least one pixel and doesn't go below 4 pixels.
+    pub fn scale_by(&mut self, percent: u16) -> Result<()> {
+        self.set_size(scaled_height(self.size().height, percent))
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        self.info.cbSize = mem::size_of::<CONSOLE_FONT_INFOEX>() as u32;
+        result(trace::call(
+            "GetCurrentConsoleFontEx",
+            *self.handle,
+            || unsafe { GetCurrentConsoleFontEx(*self.handle, 0, &mut self.info) },
+        ))
+        .with_handle_context("GetCurrentConsoleFontEx", &self.handle)
+    }
+}
+
+/// Read the font table of the console host, or `None` if the functions to read it are missing or
+/// fail.
+fn font_table(handle: &Handle) -> Option<Vec<FontInfo>> {
+    type GetNumberOfConsoleFonts = unsafe extern "system" fn() -> u32;
+    type GetConsoleFontInfo =
+        unsafe extern "system" fn(HANDLE, i32, u32, *mut CONSOLE_FONT_INFO) -> i32;
+
+    let count = proc_address("kernel32.dll\0", "GetNumberOfConsoleFonts\0")?;
+    let count: GetNumberOfConsoleFonts = unsafe { mem::transmute(count) };
+    let info = proc_address("kernel32.dll\0", "GetConsoleFontInfo\0")?;
+    let info: GetConsoleFontInfo = unsafe { mem::transmute(info) };
+
+    let count = trace::call("GetNumberOfConsoleFonts", ptr::null_mut(), || unsafe {
+        count()
+    });
+    let mut fonts = vec![unsafe { mem::zeroed::<CONSOLE_FONT_INFO>() }; count as usize];
+    if count > 0
+        && trace::call("GetConsoleFontInfo", **handle, || unsafe {
+            info(**handle, FALSE, count, fonts.as_mut_ptr())
+        }) == 0
+    {
+        return None;
+    }
+    Some(fonts.into_iter().map(FontInfo).collect())
+}
+
+/// The height `percent` of `height`, changed by at least one pixel if `percent` isn't `100`.
+fn scaled_height(height: i16, percent: u16) -> i16 {
+    let scaled = (i32::from(height) * i32::from(percent) + 50) / 100;
+    let scaled = match scaled.cmp(&i32::from(height)) {
+        std::cmp::Ordering::Equal if percent > 100 => scaled + 1,
+        std::cmp::Ordering::Equal if percent < 100 => scaled - 1,
+        _ => scaled,
+    };
+    scaled.clamp(i32::from(MIN_FONT_HEIGHT), i32::from(i16::MAX)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scaled_height;
+
+    #[test]
+    fn test_scaled_height() {
+        assert_eq!(scaled_height(16, 150), 24);
+        assert_eq!(scaled_height(16, 100), 16);
+        // Small steps still change the size.
+        assert_eq!(scaled_height(16, 102), 17);
+        assert_eq!(scaled_height(16, 98), 15);
+        assert_eq!(scaled_height(5, 10), 4);
+    }
+}
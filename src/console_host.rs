@@ -0,0 +1,230 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, Result};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::ptr::{null_mut, read_unaligned};
+
+use winapi::shared::minwindef::{FALSE, LPVOID};
+use winapi::um::{
+    processthreadsapi::OpenProcess,
+    winbase::QueryFullProcessImageNameW,
+    winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+    winuser::GetWindowThreadProcessId,
+    winver::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW},
+};
+
+use super::{nonnull_handle_result, result, trace, ConsoleWindow, ErrorContext, Handle};
+
+/// The program that hosts the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKind {
+    /// The console host shipped with Windows, `conhost.exe`.
+    Conhost,
+    /// The open source console host, `OpenConsole.exe`, outside of Windows Terminal.
+    OpenConsole,
+    /// Windows Terminal, which hosts the console with its own `OpenConsole.exe`.
+    WindowsTerminal,
+    /// The console host could not be identified.
+    Unknown,
+}
+
+/// The version of an executable, from its version resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileVersion {
+    /// The major version.
+    pub major: u16,
+    /// The minor version.
+    pub minor: u16,
+    /// The build number.
+    pub build: u16,
+    /// The revision.
+    pub revision: u16,
+}
+
+impl FileVersion {
+    /// Create a version from its parts.
+    pub fn new(major: u16, minor: u16, build: u16, revision: u16) -> FileVersion {
+        FileVersion {
+            major,
+            minor,
+            build,
+            revision,
+        }
+    }
+}
+
+impl fmt::Display for FileVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )
+    }
+}
+
+/// The program that hosts the console of the current process, and its version.
+///
+/// Host specific workarounds can be keyed on the [`kind`](ConsoleHost::kind) and
+/// [`version`](ConsoleHost::version).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleHost {
+    kind: HostKind,
+    path: Option<PathBuf>,
+    version: Option<FileVersion>,
+}
+
+impl ConsoleHost {
+    /// Detect the console host.
+    ///
+    /// The host is the process that owns the console window. Windows Terminal hosts the console
+    /// in an `OpenConsole.exe` shipped with it, which is recognized by the `WT_SESSION`
+    /// environment variable. The version of `OpenConsole.exe` matches the version of Windows
+    /// Terminal in that case.
+    ///
+    /// This fails if the process has no console window. If the host process can't be opened,
+    /// for example because it runs elevated, the kind is [`HostKind::Unknown`].
+    ///
+    /// This wraps
+    /// [`GetConsoleWindow`](https://docs.microsoft.com/en-us/windows/console/getconsolewindow),
+    /// [`QueryFullProcessImageNameW`](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-queryfullprocessimagenamew)
+    /// and
+    /// [`GetFileVersionInfoW`](https://docs.microsoft.com/en-us/windows/win32/api/winver/nf-winver-getfileversioninfow).
+    pub fn detect() -> Result<ConsoleHost> {
+        let window = ConsoleWindow::current()?.hwnd();
+
+        let mut process_id = 0;
+        trace::call("GetWindowThreadProcessId", null_mut(), || unsafe {
+            GetWindowThreadProcessId(window, &mut process_id)
+        });
+
+        let path = process_image(process_id).ok();
+        let version = path.as_deref().and_then(|path| file_version(path).ok());
+        let kind = match &path {
+            Some(path) => classify(path, env::var_os("WT_SESSION").is_some()),
+            None => HostKind::Unknown,
+        };
+
+        Ok(ConsoleHost {
+            kind,
+            path,
+            version,
+        })
+    }
+
+    /// Get the kind of console host.
+    pub fn kind(&self) -> HostKind {
+        self.kind
+    }
+
+    /// Get the path of the host executable, if it could be determined.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Get the file version of the host executable, if it could be determined.
+    pub fn version(&self) -> Option<FileVersion> {
+        self.version
+    }
+}
+
+fn classify(path: &Path, windows_terminal: bool) -> HostKind {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_ascii_lowercase(),
+        None => return HostKind::Unknown,
+    };
+    match name.as_str() {
+        "openconsole.exe" if windows_terminal => HostKind::WindowsTerminal,
+        "openconsole.exe" => HostKind::OpenConsole,
+        "conhost.exe" => HostKind::Conhost,
+        _ => HostKind::Unknown,
+    }
+}
+
+fn process_image(process_id: u32) -> Result<PathBuf> {
+    let process = nonnull_handle_result(trace::call("OpenProcess", null_mut(), || unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id)
+    }))
+    .with_context("OpenProcess")?;
+    let process = unsafe { Handle::from_raw(process) };
+
+    let mut buf = [0u16; 1024];
+    let mut len = buf.len() as u32;
+    result(trace::call(
+        "QueryFullProcessImageNameW",
+        *process,
+        || unsafe { QueryFullProcessImageNameW(*process, 0, buf.as_mut_ptr(), &mut len) },
+    ))
+    .with_context("QueryFullProcessImageNameW")?;
+
+    Ok(PathBuf::from(OsString::from_wide(&buf[..len as usize])))
+}
+
+fn file_version(path: &Path) -> Result<FileVersion> {
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    let size = trace::call("GetFileVersionInfoSizeW", null_mut(), || unsafe {
+        GetFileVersionInfoSizeW(path.as_ptr(), null_mut())
+    });
+    if size == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut data = vec![0u8; size as usize];
+    result(trace::call("GetFileVersionInfoW", null_mut(), || unsafe {
+        GetFileVersionInfoW(path.as_ptr(), 0, size, data.as_mut_ptr().cast())
+    }))
+    .with_context("GetFileVersionInfoW")?;
+
+    let root: Vec<u16> = "\\\0".encode_utf16().collect();
+    let mut info: LPVOID = null_mut();
+    let mut len = 0;
+    result(trace::call("VerQueryValueW", null_mut(), || unsafe {
+        VerQueryValueW(data.as_ptr().cast(), root.as_ptr(), &mut info, &mut len)
+    }))
+    .with_context("VerQueryValueW")?;
+
+    // The root block is a `VS_FIXEDFILEINFO`, the file version follows the signature and the
+    // struct version.
+    if len < 16 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the version resource is too small",
+        ));
+    }
+    let fields = info as *const u32;
+    let (ms, ls) = unsafe { (read_unaligned(fields.add(2)), read_unaligned(fields.add(3))) };
+
+    Ok(FileVersion::new(
+        (ms >> 16) as u16,
+        ms as u16,
+        (ls >> 16) as u16,
+        ls as u16,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{classify, FileVersion, HostKind};
+
+    #[test]
+    fn test_classify() {
+        let conhost = Path::new(r"C:\Windows\System32\conhost.exe");
+        let open_console = Path::new(r"C:\Program Files\WindowsApps\Terminal\OpenConsole.exe");
+
+        assert_eq!(classify(conhost, false), HostKind::Conhost);
+        assert_eq!(classify(open_console, false), HostKind::OpenConsole);
+        assert_eq!(classify(open_console, true), HostKind::WindowsTerminal);
+        assert_eq!(classify(Path::new("cmd.exe"), false), HostKind::Unknown);
+    }
+
+    #[test]
+    fn test_version_order() {
+        assert!(FileVersion::new(1, 18, 0, 0) > FileVersion::new(1, 17, 9999, 0));
+        assert_eq!(FileVersion::new(1, 2, 3, 4).to_string(), "1.2.3.4");
+    }
+}
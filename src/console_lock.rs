@@ -0,0 +1,101 @@
+use std::io::{self, Result};
+use std::marker::PhantomData;
+use std::process;
+use std::ptr;
+use std::sync::Mutex;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::um::{
+    synchapi::{CreateMutexW, ReleaseMutex, WaitForSingleObject},
+    winbase::{INFINITE, WAIT_ABANDONED, WAIT_OBJECT_0},
+};
+
+use super::{nonnull_handle_result, trace, ErrorContext, Handle};
+
+/// The mutex of the process, created on first use and never closed.
+static CONSOLE_MUTEX: Mutex<Option<Handle>> = Mutex::new(None);
+
+/// Holds the console lock of the process, see [`console_lock`]. The lock is released on drop.
+///
+/// The lock is owned by the thread that took it, so the guard can't be sent to another thread.
+#[derive(Debug)]
+pub struct ConsoleLockGuard {
+    mutex: Handle,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ConsoleLockGuard {
+    fn drop(&mut self) {
+        trace::call("ReleaseMutex", *self.mutex, || unsafe {
+            ReleaseMutex(*self.mutex)
+        });
+    }
+}
+
+/// Take the console lock of the process, blocking until it is available.
+///
+/// The console modes, the active screen buffer, the text attributes and the code pages are
+/// global to the process. Libraries that change them can take this lock to keep a logger and a
+/// TUI, for example, from interleaving their changes. The lock is a named mutex that is unique to
+/// the process, so it is shared by all versions of this crate and by libraries in other
+/// languages that open the mutex `Local\crossterm-winapi-console-<process id>`.
+///
+/// The lock is reentrant, a thread that holds it can take it again. The APIs of this crate that
+/// change console state take it themselves, like [`ConsoleMode::set_mode`](crate::ConsoleMode::set_mode),
+/// [`ScreenBuffer::show`](crate::ScreenBuffer::show),
+/// [`ScreenBuffer::set_size`](crate::ScreenBuffer::set_size),
+/// [`Console::set_text_attribute`](crate::Console::set_text_attribute) and
+/// [`CodePageGuard`](crate::CodePageGuard). Hold the lock across several of them to make a
+/// sequence of changes atomic.
+///
+/// This wraps
+/// [`CreateMutexW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createmutexw)
+/// and
+/// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject).
+pub fn console_lock() -> Result<ConsoleLockGuard> {
+    let mutex = mutex()?;
+    match trace::call("WaitForSingleObject", *mutex, || unsafe {
+        WaitForSingleObject(*mutex, INFINITE)
+    }) {
+        // An abandoned mutex was held by a thread that exited, it is owned by this thread now.
+        WAIT_OBJECT_0 | WAIT_ABANDONED => Ok(ConsoleLockGuard {
+            mutex,
+            _not_send: PhantomData,
+        }),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Get the mutex of the process, creating or opening it on first use.
+fn mutex() -> Result<Handle> {
+    let mut mutex = CONSOLE_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(handle) = &*mutex {
+        return Ok(handle.clone());
+    }
+
+    let name: Vec<u16> = mutex_name(process::id())
+        .encode_utf16()
+        .chain(Some(0))
+        .collect();
+    let raw = nonnull_handle_result(trace::call("CreateMutexW", ptr::null_mut(), || unsafe {
+        CreateMutexW(ptr::null_mut(), FALSE, name.as_ptr())
+    }))
+    .with_context("CreateMutexW")?;
+    let handle = unsafe { Handle::from_raw(raw) };
+    *mutex = Some(handle.clone());
+    Ok(handle)
+}
+
+fn mutex_name(process_id: u32) -> String {
+    format!("Local\\crossterm-winapi-console-{}", process_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mutex_name;
+
+    #[test]
+    fn test_mutex_name() {
+        assert_eq!(mutex_name(1234), "Local\\crossterm-winapi-console-1234");
+    }
+}
@@ -1,8 +1,9 @@
 use std::io::Result;
 
-use winapi::um::consoleapi::{GetConsoleMode, SetConsoleMode};
+use winapi::um::consoleapi::{GetConsoleMode, GetNumberOfConsoleInputEvents, SetConsoleMode};
+use winapi::um::wincon::{DISABLE_NEWLINE_AUTO_RETURN, ENABLE_WRAP_AT_EOL_OUTPUT};
 
-use super::{result, Handle, HandleType};
+use super::{console_lock, result, trace, ConsoleModeFlags, ErrorContext, Handle, HandleType};
 
 /// A wrapper around a screen buffer, focusing on calls to get and set the console mode.
 ///
@@ -25,14 +26,27 @@ impl ConsoleMode {
         })
     }
 
+    /// Create a `ConsoleMode` using the handle of the given type.
+    pub fn from_handle_type(handle_type: HandleType) -> Result<ConsoleMode> {
+        Ok(ConsoleMode {
+            handle: Handle::new(handle_type)?,
+        })
+    }
+
     /// Set the console mode to the given console mode.
     ///
-    /// This function sets the `dwMode`.
+    /// This function sets the `dwMode` as given. The environment [`Overrides`](crate::Overrides)
+    /// only apply where the crate enables virtual terminal sequences or mouse input itself, like
+    /// [`init`](crate::init) and [`ConsoleModePreset::apply`](crate::ConsoleModePreset::apply).
     ///
     /// This wraps
     /// [`SetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/setconsolemode).
     pub fn set_mode(&self, console_mode: u32) -> Result<()> {
-        result(unsafe { SetConsoleMode(*self.handle, console_mode) })
+        let _lock = console_lock()?;
+        result(trace::call("SetConsoleMode", *self.handle, || unsafe {
+            SetConsoleMode(*self.handle, console_mode)
+        }))
+        .with_handle_context("SetConsoleMode", &self.handle)
     }
 
     /// Get the console mode.
@@ -43,9 +57,60 @@ impl ConsoleMode {
     /// [`GetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/getconsolemode).
     pub fn mode(&self) -> Result<u32> {
         let mut console_mode = 0;
-        result(unsafe { GetConsoleMode(*self.handle, &mut console_mode) })?;
+        result(trace::call("GetConsoleMode", *self.handle, || unsafe {
+            GetConsoleMode(*self.handle, &mut console_mode)
+        }))
+        .with_handle_context("GetConsoleMode", &self.handle)?;
         Ok(console_mode)
     }
+
+    /// Get the console mode as flags that can be printed and compared by name.
+    ///
+    /// Whether the handle is an input buffer or a screen buffer is detected automatically.
+    pub fn flags(&self) -> Result<ConsoleModeFlags> {
+        let mode = self.mode()?;
+        Ok(if self.is_input_buffer() {
+            ConsoleModeFlags::input(mode)
+        } else {
+            ConsoleModeFlags::output(mode)
+        })
+    }
+
+    /// Set whether writing a line feed also moves the cursor to the start of the line.
+    ///
+    /// This clears or sets `DISABLE_NEWLINE_AUTO_RETURN` on a screen buffer while leaving the
+    /// other flags untouched.
+    pub fn set_newline_auto_return(&self, enable: bool) -> Result<()> {
+        self.update_flag(DISABLE_NEWLINE_AUTO_RETURN, !enable)
+    }
+
+    /// Set whether the cursor moves to the next line when it reaches the end of the current line.
+    ///
+    /// This sets or clears `ENABLE_WRAP_AT_EOL_OUTPUT` on a screen buffer while leaving the other
+    /// flags untouched.
+    pub fn set_wrap_at_eol(&self, enable: bool) -> Result<()> {
+        self.update_flag(ENABLE_WRAP_AT_EOL_OUTPUT, enable)
+    }
+
+    /// Set or clear `flag` in the current mode.
+    fn update_flag(&self, flag: u32, set: bool) -> Result<()> {
+        let mode = self.mode()?;
+        let new_mode = if set { mode | flag } else { mode & !flag };
+
+        if new_mode == mode {
+            return Ok(());
+        }
+        self.set_mode(new_mode)
+    }
+
+    /// Returns whether the handle is a console input buffer, the mode flags of input buffers have
+    /// a different meaning than those of screen buffers.
+    fn is_input_buffer(&self) -> bool {
+        let mut events = 0;
+        trace::call("GetNumberOfConsoleInputEvents", *self.handle, || unsafe {
+            GetNumberOfConsoleInputEvents(*self.handle, &mut events)
+        }) != 0
+    }
 }
 
 impl From<Handle> for ConsoleMode {
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use winapi::um::wincon::{
+    DISABLE_NEWLINE_AUTO_RETURN, ENABLE_AUTO_POSITION, ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS,
+    ENABLE_INSERT_MODE, ENABLE_LINE_INPUT, ENABLE_LVB_GRID_WORLDWIDE, ENABLE_MOUSE_INPUT,
+    ENABLE_PROCESSED_INPUT, ENABLE_PROCESSED_OUTPUT, ENABLE_QUICK_EDIT_MODE,
+    ENABLE_VIRTUAL_TERMINAL_INPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING, ENABLE_WINDOW_INPUT,
+    ENABLE_WRAP_AT_EOL_OUTPUT,
+};
+
+const INPUT_FLAGS: &[(u32, &str)] = &[
+    (ENABLE_PROCESSED_INPUT, "ENABLE_PROCESSED_INPUT"),
+    (ENABLE_LINE_INPUT, "ENABLE_LINE_INPUT"),
+    (ENABLE_ECHO_INPUT, "ENABLE_ECHO_INPUT"),
+    (ENABLE_WINDOW_INPUT, "ENABLE_WINDOW_INPUT"),
+    (ENABLE_MOUSE_INPUT, "ENABLE_MOUSE_INPUT"),
+    (ENABLE_INSERT_MODE, "ENABLE_INSERT_MODE"),
+    (ENABLE_QUICK_EDIT_MODE, "ENABLE_QUICK_EDIT_MODE"),
+    (ENABLE_EXTENDED_FLAGS, "ENABLE_EXTENDED_FLAGS"),
+    (ENABLE_AUTO_POSITION, "ENABLE_AUTO_POSITION"),
+    (
+        ENABLE_VIRTUAL_TERMINAL_INPUT,
+        "ENABLE_VIRTUAL_TERMINAL_INPUT",
+    ),
+];
+
+const OUTPUT_FLAGS: &[(u32, &str)] = &[
+    (ENABLE_PROCESSED_OUTPUT, "ENABLE_PROCESSED_OUTPUT"),
+    (ENABLE_WRAP_AT_EOL_OUTPUT, "ENABLE_WRAP_AT_EOL_OUTPUT"),
+    (
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+        "ENABLE_VIRTUAL_TERMINAL_PROCESSING",
+    ),
+    (DISABLE_NEWLINE_AUTO_RETURN, "DISABLE_NEWLINE_AUTO_RETURN"),
+    (ENABLE_LVB_GRID_WORLDWIDE, "ENABLE_LVB_GRID_WORLDWIDE"),
+];
+
+/// Whether a console mode belongs to an input buffer or a screen buffer.
+///
+/// The same bit has a different meaning depending on the kind of buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeKind {
+    /// The mode of a console input buffer.
+    Input,
+    /// The mode of a console screen buffer.
+    Output,
+}
+
+impl ModeKind {
+    fn flags(self) -> &'static [(u32, &'static str)] {
+        match self {
+            ModeKind::Input => INPUT_FLAGS,
+            ModeKind::Output => OUTPUT_FLAGS,
+        }
+    }
+}
+
+/// The flags of a console mode, which can be printed by name.
+///
+/// This can be obtained with [`ConsoleMode::flags`](crate::ConsoleMode::flags).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleModeFlags {
+    bits: u32,
+    kind: ModeKind,
+}
+
+impl ConsoleModeFlags {
+    /// Create the flags of a console input buffer mode.
+    pub fn input(bits: u32) -> ConsoleModeFlags {
+        ConsoleModeFlags {
+            bits,
+            kind: ModeKind::Input,
+        }
+    }
+
+    /// Create the flags of a console screen buffer mode.
+    pub fn output(bits: u32) -> ConsoleModeFlags {
+        ConsoleModeFlags {
+            bits,
+            kind: ModeKind::Output,
+        }
+    }
+
+    /// Get the raw mode.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Get the kind of buffer the mode belongs to.
+    pub fn kind(&self) -> ModeKind {
+        self.kind
+    }
+
+    /// Get the names of the flags that are set.
+    ///
+    /// Bits that don't correspond to a known flag are not included.
+    pub fn names(&self) -> Vec<&'static str> {
+        names(self.kind, self.bits)
+    }
+
+    /// Compare these flags to `other`, the flags of a later mode of the same buffer.
+    pub fn diff(&self, other: &ConsoleModeFlags) -> ModeDiff {
+        ModeDiff {
+            kind: self.kind,
+            added: other.bits & !self.bits,
+            removed: self.bits & !other.bits,
+        }
+    }
+}
+
+impl fmt::Display for ConsoleModeFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(f, self.kind, self.bits, "", " | ")
+    }
+}
+
+/// The flags that were added and removed between two console modes.
+///
+/// The `Display` implementation prints the changes by name, for example
+/// `+ENABLE_PROCESSED_INPUT -ENABLE_VIRTUAL_TERMINAL_INPUT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeDiff {
+    kind: ModeKind,
+    added: u32,
+    removed: u32,
+}
+
+impl ModeDiff {
+    /// Get the bits that were set in the new mode but not in the old one.
+    pub fn added(&self) -> u32 {
+        self.added
+    }
+
+    /// Get the bits that were set in the old mode but not in the new one.
+    pub fn removed(&self) -> u32 {
+        self.removed
+    }
+
+    /// Get the names of the flags that were added.
+    pub fn added_names(&self) -> Vec<&'static str> {
+        names(self.kind, self.added)
+    }
+
+    /// Get the names of the flags that were removed.
+    pub fn removed_names(&self) -> Vec<&'static str> {
+        names(self.kind, self.removed)
+    }
+
+    /// Returns whether both modes are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+impl fmt::Display for ModeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("unchanged");
+        }
+        if self.added != 0 {
+            write_flags(f, self.kind, self.added, "+", " +")?;
+        }
+        if self.added != 0 && self.removed != 0 {
+            f.write_str(" ")?;
+        }
+        if self.removed != 0 {
+            write_flags(f, self.kind, self.removed, "-", " -")?;
+        }
+        Ok(())
+    }
+}
+
+fn names(kind: ModeKind, bits: u32) -> Vec<&'static str> {
+    kind.flags()
+        .iter()
+        .filter(|(flag, _)| bits & flag != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Write the names of the flags in `bits`, and the remaining unknown bits in hex.
+fn write_flags(
+    f: &mut fmt::Formatter<'_>,
+    kind: ModeKind,
+    bits: u32,
+    prefix: &str,
+    separator: &str,
+) -> fmt::Result {
+    let mut unknown = bits;
+    let mut first = true;
+    for (flag, name) in kind.flags() {
+        if bits & flag != 0 {
+            f.write_str(if first { prefix } else { separator })?;
+            f.write_str(name)?;
+            unknown &= !flag;
+            first = false;
+        }
+    }
+    if unknown != 0 || first {
+        f.write_str(if first { prefix } else { separator })?;
+        write!(f, "{:#x}", unknown)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConsoleModeFlags;
+
+    #[test]
+    fn test_diff_display() {
+        let old = ConsoleModeFlags::input(0x0200 | 0x0004);
+        let new = ConsoleModeFlags::input(0x0001 | 0x0004);
+        assert_eq!(
+            old.diff(&new).to_string(),
+            "+ENABLE_PROCESSED_INPUT -ENABLE_VIRTUAL_TERMINAL_INPUT"
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_flags_display() {
+        let flags = ConsoleModeFlags::output(0x0001 | 0x0004 | 0x1000);
+        assert_eq!(
+            flags.to_string(),
+            "ENABLE_PROCESSED_OUTPUT | ENABLE_VIRTUAL_TERMINAL_PROCESSING | 0x1000"
+        );
+        assert_eq!(ConsoleModeFlags::output(0).to_string(), "0x0");
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::Result;
+
+use winapi::um::wincon::{
+    DISABLE_NEWLINE_AUTO_RETURN, ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_INSERT_MODE,
+    ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT, ENABLE_PROCESSED_INPUT, ENABLE_PROCESSED_OUTPUT,
+    ENABLE_QUICK_EDIT_MODE, ENABLE_VIRTUAL_TERMINAL_INPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    ENABLE_WINDOW_INPUT, ENABLE_WRAP_AT_EOL_OUTPUT,
+};
+
+use super::{ConsoleMode, ConsoleModeFlags, ModeKind, Overrides};
+
+/// A well known combination of console mode flags.
+///
+/// Every preset defines the complete mode of both the input buffer and the screen buffer, so
+/// applying it gives the same result regardless of the mode before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleModePreset {
+    /// The default mode of a console: line editing, echo and Ctrl+C handling by the console.
+    Cooked,
+    /// Every key press is reported as it happens, without echo and Ctrl+C handling. Resize
+    /// events are reported. Quick edit mode stays enabled, so the mouse selects text.
+    Raw,
+    /// Like [`Raw`](ConsoleModePreset::Raw), but mouse events are reported and quick edit mode is
+    /// disabled.
+    RawWithMouse,
+    /// Input is reported as virtual terminal sequences and output sequences are processed, for
+    /// applications that do all terminal handling themselves.
+    VtPassthrough,
+}
+
+impl ConsoleModePreset {
+    /// Get the mode of the input buffer.
+    ///
+    /// `ENABLE_EXTENDED_FLAGS` is always included, without it the console ignores changes to
+    /// quick edit and insert mode.
+    pub fn input_mode(self) -> u32 {
+        let mode = match self {
+            ConsoleModePreset::Cooked => {
+                ENABLE_PROCESSED_INPUT
+                    | ENABLE_LINE_INPUT
+                    | ENABLE_ECHO_INPUT
+                    | ENABLE_INSERT_MODE
+                    | ENABLE_QUICK_EDIT_MODE
+            }
+            ConsoleModePreset::Raw => {
+                ENABLE_WINDOW_INPUT | ENABLE_INSERT_MODE | ENABLE_QUICK_EDIT_MODE
+            }
+            ConsoleModePreset::RawWithMouse => ENABLE_WINDOW_INPUT | ENABLE_MOUSE_INPUT,
+            ConsoleModePreset::VtPassthrough => ENABLE_WINDOW_INPUT | ENABLE_VIRTUAL_TERMINAL_INPUT,
+        };
+        mode | ENABLE_EXTENDED_FLAGS
+    }
+
+    /// Get the mode of the screen buffer.
+    pub fn output_mode(self) -> u32 {
+        match self {
+            ConsoleModePreset::Cooked
+            | ConsoleModePreset::Raw
+            | ConsoleModePreset::RawWithMouse => {
+                ENABLE_PROCESSED_OUTPUT | ENABLE_WRAP_AT_EOL_OUTPUT
+            }
+            ConsoleModePreset::VtPassthrough => {
+                ENABLE_PROCESSED_OUTPUT
+                    | ENABLE_WRAP_AT_EOL_OUTPUT
+                    | ENABLE_VIRTUAL_TERMINAL_PROCESSING
+                    | DISABLE_NEWLINE_AUTO_RETURN
+            }
+        }
+    }
+
+    /// Get the mode of a buffer of the given kind.
+    pub fn mode(self, kind: ModeKind) -> u32 {
+        match kind {
+            ModeKind::Input => self.input_mode(),
+            ModeKind::Output => self.output_mode(),
+        }
+    }
+
+    /// Set the mode of the preset on `mode`, which can be an input buffer or a screen buffer.
+    ///
+    /// The flags forbidden by the environment [`Overrides`] are left out. Use
+    /// [`apply_both`](ConsoleModePreset::apply_both) to set the modes of both buffers in a safe
+    /// order.
+    pub fn apply(self, mode: &ConsoleMode) -> Result<()> {
+        let overrides = Overrides::current();
+        let kind = mode.flags()?.kind();
+        mode.set_mode(match kind {
+            ModeKind::Input => overrides.filter_input_mode(self.input_mode()),
+            ModeKind::Output => overrides.filter_output_mode(self.output_mode()),
+        })
+    }
+
+    /// Set the mode of the preset on the screen buffer `output`, then on the input buffer `input`.
+    ///
+    /// If the screen buffer rejects the mode, for example because it doesn't support virtual
+    /// terminal processing, the input buffer is left as it is, so input isn't switched to virtual
+    /// terminal sequences that nothing renders.
+    pub fn apply_both(self, input: &ConsoleMode, output: &ConsoleMode) -> Result<()> {
+        self.apply(output)?;
+        self.apply(input)
+    }
+
+    /// Describe the preset and the flags it sets, for logs and diagnostics.
+    pub fn describe(self) -> String {
+        let name = match self {
+            ConsoleModePreset::Cooked => "cooked",
+            ConsoleModePreset::Raw => "raw",
+            ConsoleModePreset::RawWithMouse => "raw with mouse",
+            ConsoleModePreset::VtPassthrough => "vt passthrough",
+        };
+        format!(
+            "{}: input {}, output {}",
+            name,
+            ConsoleModeFlags::input(self.input_mode()),
+            ConsoleModeFlags::output(self.output_mode())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT, ENABLE_QUICK_EDIT_MODE};
+
+    use super::ConsoleModePreset;
+
+    #[test]
+    fn test_raw_presets() {
+        for preset in [ConsoleModePreset::Raw, ConsoleModePreset::RawWithMouse] {
+            assert_eq!(preset.input_mode() & ENABLE_LINE_INPUT, 0);
+        }
+        let mouse = ConsoleModePreset::RawWithMouse.input_mode();
+        assert_ne!(mouse & ENABLE_MOUSE_INPUT, 0);
+        assert_eq!(mouse & ENABLE_QUICK_EDIT_MODE, 0);
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            ConsoleModePreset::RawWithMouse.describe(),
+            "raw with mouse: input ENABLE_WINDOW_INPUT | ENABLE_MOUSE_INPUT | \
+             ENABLE_EXTENDED_FLAGS, output ENABLE_PROCESSED_OUTPUT | ENABLE_WRAP_AT_EOL_OUTPUT"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::Result;
+use std::ptr;
+use std::thread::{self, JoinHandle};
+
+use winapi::shared::minwindef::{FALSE, TRUE};
+use winapi::um::{
+    synchapi::{CreateEventW, ResetEvent, SetEvent, WaitForMultipleObjects, WaitForSingleObject},
+    winbase::{INFINITE, WAIT_OBJECT_0},
+};
+
+use super::{nonnull_handle_result, result, trace, Console, ErrorContext, Handle};
+
+/// Exposes the readiness of a console input buffer to reactors like `mio` or `polling`.
+///
+/// Console input handles can't be registered with an I/O completion port, so a waiter thread
+/// waits on the input buffer instead. When input is available, it signals
+/// [`ready_event`](ConsoleSource::ready_event) and calls the `wake` function given to
+/// [`new`](ConsoleSource::new), for example to call `mio::Waker::wake` or
+/// `polling::Poller::notify`.
+///
+/// The input buffer stays signaled until it is read, so the waiter thread waits for
+/// [`rearm`](ConsoleSource::rearm) before it waits on the input buffer again. Call it after
+/// reading all available input.
+///
+/// The waiter thread is stopped when the source is dropped.
+pub struct ConsoleSource {
+    ready: Handle,
+    rearm: Handle,
+    stop: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConsoleSource {
+    /// Start waiting for input on `console`, calling `wake` every time input becomes available.
+    ///
+    /// `wake` is called on the waiter thread.
+    pub fn new(console: &Console, wake: impl Fn() + Send + 'static) -> Result<ConsoleSource> {
+        // `ready` is reset by `rearm`, `rearm` and `stop` reset when the waiter sees them.
+        let ready = create_event(true)?;
+        let rearm = create_event(false)?;
+        let stop = create_event(false)?;
+
+        let thread = {
+            let input = console.handle().clone();
+            let ready = ready.clone();
+            let rearm = rearm.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("crossterm-winapi-source".to_owned())
+                .spawn(move || {
+                    while wait_any(&input, &stop) == WAIT_OBJECT_0 {
+                        trace::call("SetEvent", *ready, || unsafe { SetEvent(*ready) });
+                        wake();
+                        if wait_any(&rearm, &stop) != WAIT_OBJECT_0 {
+                            break;
+                        }
+                    }
+                })?
+        };
+
+        Ok(ConsoleSource {
+            ready,
+            rearm,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Get the event that is signaled while input is available and the source isn't rearmed.
+    ///
+    /// This handle can be waited on directly, or registered with a reactor that supports
+    /// waitable handles.
+    pub fn ready_event(&self) -> &Handle {
+        &self.ready
+    }
+
+    /// Returns whether input was signaled and the source wasn't rearmed since.
+    ///
+    /// This wraps
+    /// [`WaitForSingleObject`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject)
+    /// with a zero timeout.
+    pub fn is_ready(&self) -> bool {
+        trace::call("WaitForSingleObject", *self.ready, || unsafe {
+            WaitForSingleObject(*self.ready, 0)
+        }) == WAIT_OBJECT_0
+    }
+
+    /// Let the waiter thread wait for input again, after all available input has been read.
+    ///
+    /// If input is still available, the source is signaled again right away.
+    pub fn rearm(&self) -> Result<()> {
+        result(trace::call("ResetEvent", *self.ready, || unsafe {
+            ResetEvent(*self.ready)
+        }))
+        .with_context("ResetEvent")?;
+        result(trace::call("SetEvent", *self.rearm, || unsafe {
+            SetEvent(*self.rearm)
+        }))
+        .with_context("SetEvent")
+    }
+}
+
+impl fmt::Debug for ConsoleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsoleSource")
+            .field("ready", &self.ready)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ConsoleSource {
+    fn drop(&mut self) {
+        trace::call("SetEvent", *self.stop, || unsafe { SetEvent(*self.stop) });
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Create an unnamed, non-signaled event.
+///
+/// This wraps
+/// [`CreateEventW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createeventw).
+fn create_event(manual_reset: bool) -> Result<Handle> {
+    let manual_reset = if manual_reset { TRUE } else { FALSE };
+    let handle = nonnull_handle_result(trace::call("CreateEventW", ptr::null_mut(), || unsafe {
+        CreateEventW(ptr::null_mut(), manual_reset, FALSE, ptr::null())
+    }))
+    .with_context("CreateEventW")?;
+    Ok(unsafe { Handle::from_raw(handle) })
+}
+
+/// Wait until `first` or `second` is signaled, returning `WAIT_OBJECT_0` for `first`.
+fn wait_any(first: &Handle, second: &Handle) -> u32 {
+    let handles = [**first, **second];
+    trace::call("WaitForMultipleObjects", **first, || unsafe {
+        WaitForMultipleObjects(handles.len() as u32, handles.as_ptr(), FALSE, INFINITE)
+    })
+}
@@ -0,0 +1,333 @@
+use std::fmt;
+use std::io::{self, Result};
+use std::mem;
+use std::ptr::null_mut;
+
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::{
+    wincon::GetConsoleWindow,
+    winuser::{
+        GetClientRect, GetMonitorInfoW, GetWindowLongW, GetWindowPlacement, MonitorFromWindow,
+        SetLayeredWindowAttributes, SetWindowLongW, SetWindowPlacement, SetWindowPos, GWL_EXSTYLE,
+        HWND_NOTOPMOST, HWND_TOPMOST, LWA_ALPHA, MONITORINFO, MONITORINFOF_PRIMARY,
+        MONITOR_DEFAULTTONEAREST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SW_SHOWMAXIMIZED,
+        SW_SHOWMINIMIZED, SW_SHOWNORMAL, WINDOWPLACEMENT, WPF_RESTORETOMAXIMIZED, WS_EX_LAYERED,
+    },
+};
+
+use super::{
+    capability::proc_address, result, size_report::DEFAULT_DPI, trace, ErrorContext, Size,
+};
+
+/// The monitor a [`ConsoleWindow`] is on, see [`ConsoleWindow::monitor`].
+#[derive(Clone, Copy)]
+pub struct MonitorInfo {
+    /// The bounds of the monitor in virtual screen coordinates.
+    pub bounds: RECT,
+    /// The part of the monitor that is not covered by the taskbar and docked toolbars.
+    pub work_area: RECT,
+    /// Whether this is the primary monitor.
+    pub primary: bool,
+    /// The DPI of the monitor, see [`ConsoleWindow::dpi`].
+    pub dpi: u32,
+}
+
+impl MonitorInfo {
+    /// Get the scale factor of the monitor, `1.5` at 150% scaling.
+    pub fn scale_factor(&self) -> f64 {
+        f64::from(self.dpi) / f64::from(DEFAULT_DPI)
+    }
+}
+
+impl fmt::Debug for MonitorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rect = |rect: &RECT| (rect.left, rect.top, rect.right, rect.bottom);
+        f.debug_struct("MonitorInfo")
+            .field("bounds", &rect(&self.bounds))
+            .field("work_area", &rect(&self.work_area))
+            .field("primary", &self.primary)
+            .field("dpi", &self.dpi)
+            .finish()
+    }
+}
+
+/// How a [`ConsoleWindow`] is shown, see [`WindowPlacement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WindowShow {
+    /// The window is shown at its normal bounds.
+    #[default]
+    Normal,
+    /// The window is minimized.
+    Minimized,
+    /// The window is maximized.
+    Maximized,
+}
+
+/// The position, size and show state of a [`ConsoleWindow`], see [`ConsoleWindow::placement`].
+///
+/// The fields are plain numbers so the placement can be stored and restored in a later run.
+/// The bounds are in workspace coordinates, which exclude the taskbar, in the pixels this process
+/// sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowPlacement {
+    /// How the window is shown.
+    pub show: WindowShow,
+    /// Whether a minimized window is maximized rather than shown at its normal bounds when it
+    /// is restored.
+    pub restore_maximized: bool,
+    /// The left edge of the window when it is neither minimized nor maximized.
+    pub left: i32,
+    /// The top edge of the window when it is neither minimized nor maximized.
+    pub top: i32,
+    /// The width of the window when it is neither minimized nor maximized.
+    pub width: i32,
+    /// The height of the window when it is neither minimized nor maximized.
+    pub height: i32,
+}
+
+impl WindowPlacement {
+    fn from_winapi(placement: &WINDOWPLACEMENT) -> WindowPlacement {
+        let bounds = placement.rcNormalPosition;
+        WindowPlacement {
+            show: match placement.showCmd as i32 {
+                SW_SHOWMINIMIZED => WindowShow::Minimized,
+                SW_SHOWMAXIMIZED => WindowShow::Maximized,
+                _ => WindowShow::Normal,
+            },
+            restore_maximized: placement.flags & WPF_RESTORETOMAXIMIZED != 0,
+            left: bounds.left,
+            top: bounds.top,
+            width: bounds.right - bounds.left,
+            height: bounds.bottom - bounds.top,
+        }
+    }
+
+    fn to_winapi(self) -> WINDOWPLACEMENT {
+        let mut placement: WINDOWPLACEMENT = unsafe { mem::zeroed() };
+        placement.length = mem::size_of::<WINDOWPLACEMENT>() as u32;
+        placement.showCmd = match self.show {
+            WindowShow::Normal => SW_SHOWNORMAL,
+            WindowShow::Minimized => SW_SHOWMINIMIZED,
+            WindowShow::Maximized => SW_SHOWMAXIMIZED,
+        } as u32;
+        if self.restore_maximized {
+            placement.flags = WPF_RESTORETOMAXIMIZED;
+        }
+        placement.rcNormalPosition = RECT {
+            left: self.left,
+            top: self.top,
+            right: self.left.saturating_add(self.width),
+            bottom: self.top.saturating_add(self.height),
+        };
+        placement
+    }
+}
+
+/// The window of the console of the current process.
+///
+/// Windows Terminal doesn't draw the console in this window, it only keeps a hidden window for
+/// compatibility. Changing that window has no visible effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleWindow {
+    hwnd: HWND,
+}
+
+impl ConsoleWindow {
+    /// Get the window of the console of the current process.
+    ///
+    /// This fails with [`io::ErrorKind::NotFound`] if the process has no console window.
+    ///
+    /// This wraps
+    /// [`GetConsoleWindow`](https://docs.microsoft.com/en-us/windows/console/getconsolewindow).
+    pub fn current() -> Result<ConsoleWindow> {
+        let hwnd = trace::call("GetConsoleWindow", null_mut(), || unsafe {
+            GetConsoleWindow()
+        });
+        if hwnd.is_null() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the console has no window",
+            ));
+        }
+        Ok(ConsoleWindow { hwnd })
+    }
+
+    /// Get the raw window handle.
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+
+    /// Get the size of the client area in the pixels this process sees, which are logical
+    /// pixels if the process is not DPI aware.
+    pub(crate) fn client_size(&self) -> Result<Size> {
+        let mut rect: RECT = unsafe { mem::zeroed() };
+        result(trace::call("GetClientRect", null_mut(), || unsafe {
+            GetClientRect(self.hwnd, &mut rect)
+        }))
+        .with_context("GetClientRect")?;
+        Ok(Size::new(
+            (rect.right - rect.left).clamp(0, i32::from(i16::MAX)) as i16,
+            (rect.bottom - rect.top).clamp(0, i32::from(i16::MAX)) as i16,
+        ))
+    }
+
+    /// Get the DPI of the window, which follows the monitor it is on, `96` at 100% scaling.
+    ///
+    /// Before Windows 10 1607, which has no `GetDpiForWindow`, this is always `96`.
+    ///
+    /// This wraps
+    /// [`GetDpiForWindow`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getdpiforwindow).
+    pub fn dpi(&self) -> u32 {
+        type GetDpiForWindow = unsafe extern "system" fn(HWND) -> u32;
+
+        let get_dpi_for_window = match proc_address("user32.dll\0", "GetDpiForWindow\0") {
+            Some(get_dpi_for_window) => get_dpi_for_window,
+            None => return DEFAULT_DPI,
+        };
+        let get_dpi_for_window: GetDpiForWindow = unsafe { mem::transmute(get_dpi_for_window) };
+        match trace::call("GetDpiForWindow", null_mut(), || unsafe {
+            get_dpi_for_window(self.hwnd)
+        }) {
+            0 => DEFAULT_DPI,
+            dpi => dpi,
+        }
+    }
+
+    /// Get the monitor the window is on, or the nearest one if it is off screen.
+    ///
+    /// This wraps
+    /// [`MonitorFromWindow`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-monitorfromwindow)
+    /// and
+    /// [`GetMonitorInfoW`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getmonitorinfow).
+    pub fn monitor(&self) -> Result<MonitorInfo> {
+        let monitor = trace::call("MonitorFromWindow", null_mut(), || unsafe {
+            MonitorFromWindow(self.hwnd, MONITOR_DEFAULTTONEAREST)
+        });
+        let mut info: MONITORINFO = unsafe { mem::zeroed() };
+        info.cbSize = mem::size_of::<MONITORINFO>() as u32;
+        result(trace::call("GetMonitorInfoW", null_mut(), || unsafe {
+            GetMonitorInfoW(monitor, &mut info)
+        }))
+        .with_context("GetMonitorInfoW")?;
+        Ok(MonitorInfo {
+            bounds: info.rcMonitor,
+            work_area: info.rcWork,
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            dpi: self.dpi(),
+        })
+    }
+
+    /// Set the opacity of the window, from `0` (invisible) to `100` (opaque) percent.
+    ///
+    /// Values above `100` are treated as `100`.
+    ///
+    /// This wraps
+    /// [`SetLayeredWindowAttributes`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setlayeredwindowattributes).
+    pub fn set_opacity(&self, percent: u8) -> Result<()> {
+        let alpha = (u16::from(percent.min(100)) * 255 / 100) as u8;
+
+        // Only layered windows can be transparent.
+        let ex_style = trace::call("GetWindowLongW", null_mut(), || unsafe {
+            GetWindowLongW(self.hwnd, GWL_EXSTYLE)
+        });
+        if ex_style as u32 & WS_EX_LAYERED == 0 {
+            trace::call("SetWindowLongW", null_mut(), || unsafe {
+                SetWindowLongW(
+                    self.hwnd,
+                    GWL_EXSTYLE,
+                    (ex_style as u32 | WS_EX_LAYERED) as i32,
+                )
+            });
+        }
+
+        result(trace::call(
+            "SetLayeredWindowAttributes",
+            null_mut(),
+            || unsafe { SetLayeredWindowAttributes(self.hwnd, 0, alpha, LWA_ALPHA) },
+        ))
+        .with_context("SetLayeredWindowAttributes")
+    }
+
+    /// Get the position, size and show state of the window, to restore it with
+    /// [`set_placement`](Self::set_placement) in a later run.
+    ///
+    /// This wraps
+    /// [`GetWindowPlacement`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getwindowplacement).
+    pub fn placement(&self) -> Result<WindowPlacement> {
+        let mut placement: WINDOWPLACEMENT = unsafe { mem::zeroed() };
+        placement.length = mem::size_of::<WINDOWPLACEMENT>() as u32;
+        result(trace::call("GetWindowPlacement", null_mut(), || unsafe {
+            GetWindowPlacement(self.hwnd, &mut placement)
+        }))
+        .with_context("GetWindowPlacement")?;
+        Ok(WindowPlacement::from_winapi(&placement))
+    }
+
+    /// Set the position, size and show state of the window.
+    ///
+    /// If the bounds are completely off screen, for example because the monitor they were saved
+    /// on is gone, Windows moves the window so it is visible. Consider restoring a
+    /// [`WindowShow::Minimized`] placement as [`WindowShow::Normal`], so the console doesn't
+    /// start minimized.
+    ///
+    /// This wraps
+    /// [`SetWindowPlacement`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowplacement).
+    pub fn set_placement(&self, placement: WindowPlacement) -> Result<()> {
+        let placement = placement.to_winapi();
+        result(trace::call("SetWindowPlacement", null_mut(), || unsafe {
+            SetWindowPlacement(self.hwnd, &placement)
+        }))
+        .with_context("SetWindowPlacement")
+    }
+
+    /// Set whether the window stays on top of all windows that are not topmost.
+    ///
+    /// This wraps
+    /// [`SetWindowPos`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowpos).
+    pub fn set_always_on_top(&self, on_top: bool) -> Result<()> {
+        let insert_after = if on_top { HWND_TOPMOST } else { HWND_NOTOPMOST };
+        result(trace::call("SetWindowPos", null_mut(), || unsafe {
+            SetWindowPos(
+                self.hwnd,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+            )
+        }))
+        .with_context("SetWindowPos")
+    }
+}
+
+// A window handle is an identifier that can be used from any thread.
+unsafe impl Send for ConsoleWindow {}
+
+unsafe impl Sync for ConsoleWindow {}
+
+#[cfg(test)]
+mod tests {
+    use super::{WindowPlacement, WindowShow};
+
+    #[test]
+    fn test_placement_round_trip() {
+        for show in [
+            WindowShow::Normal,
+            WindowShow::Minimized,
+            WindowShow::Maximized,
+        ] {
+            let placement = WindowPlacement {
+                show,
+                restore_maximized: show == WindowShow::Minimized,
+                left: -1200,
+                top: 40,
+                width: 800,
+                height: 600,
+            };
+            let raw = placement.to_winapi();
+            assert_eq!(raw.rcNormalPosition.right, -400);
+            assert_eq!(WindowPlacement::from_winapi(&raw), placement);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::{self, Write};
+use std::mem;
+use std::str;
+
+use super::Console;
+
+/// The default capacity of the buffer of a [`ConsoleWriter`].
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// A buffered writer that combines small writes into fewer calls to `WriteConsoleW`.
+///
+/// Every call to [`Console::write_char_buffer`] is a syscall, which dominates the time spent
+/// rendering for applications that print many small pieces of text. This writer collects the
+/// written bytes and only hands them to the console when:
+///
+/// - a write doesn't fit in the [`buffer_capacity`](ConsoleWriter::buffer_capacity) anymore,
+/// - [`flush`](Write::flush) is called,
+/// - the writer is dropped (errors are ignored in that case).
+///
+/// The bytes have to be UTF-8. A character that is split across writes is held back until it is
+/// complete, so a flush never writes half of a character. When the buffer contains invalid
+/// UTF-8, the text before it is written, the invalid sequence is dropped and the flush fails with
+/// [`io::ErrorKind::InvalidData`]. The text after it is kept for the next flush.
+///
+/// Writes of at least `buffer_capacity` bytes are passed to the console directly after the buffer
+/// is flushed. A write that fails hasn't taken any of its bytes, so it can be retried.
+#[derive(Debug)]
+pub struct ConsoleWriter {
+    console: Console,
+    buf: Vec<u8>,
+    capacity: usize,
+}
+
+impl ConsoleWriter {
+    /// Create a writer with a buffer of [`DEFAULT_BUFFER_CAPACITY`] bytes.
+    pub fn new(console: Console) -> ConsoleWriter {
+        ConsoleWriter::with_capacity(console, DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Create a writer with a buffer of `capacity` bytes.
+    ///
+    /// A capacity of `0` disables buffering, every write is passed through directly.
+    pub fn with_capacity(console: Console, capacity: usize) -> ConsoleWriter {
+        ConsoleWriter {
+            console,
+            buf: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Get the number of bytes after which the buffer is written to the console.
+    pub fn buffer_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Set the number of bytes after which the buffer is written to the console.
+    ///
+    /// If the buffer already holds more bytes than the new capacity it is flushed.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) -> io::Result<()> {
+        self.capacity = capacity;
+        if self.buf.len() >= capacity {
+            self.flush_buf()?;
+        }
+        Ok(())
+    }
+
+    /// Get the console this writer writes to.
+    pub fn get_ref(&self) -> &Console {
+        &self.console
+    }
+
+    /// Write the complete characters in the buffer to the console.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        let (complete, invalid) = valid_prefix(&self.buf);
+
+        if complete > 0 {
+            let result = self.console.write_char_buffer(&self.buf[..complete]);
+            self.buf.drain(..complete);
+            result?;
+        }
+        if let Some(invalid) = invalid {
+            self.buf.drain(..invalid);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Could not parse to utf8 string",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ConsoleWriter {
+    /// Write `buf` to the console without buffering it, after the incomplete character the
+    /// flushed buffer may still hold.
+    ///
+    /// Returns how many bytes of `buf` were taken, which stops before invalid UTF-8.
+    fn write_through(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let held = self.buf.len();
+        let mut data = mem::take(&mut self.buf);
+        data.extend_from_slice(buf);
+
+        let (complete, invalid) = valid_prefix(&data);
+        if complete == 0 {
+            if invalid.is_some() {
+                // The held bytes and the start of `buf` aren't a character, the held bytes are
+                // dropped.
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Could not parse to utf8 string",
+                ));
+            }
+            self.buf = data;
+            return Ok(buf.len());
+        }
+
+        if let Err(e) = self.console.write_char_buffer(&data[..complete]) {
+            data.truncate(held);
+            self.buf = data;
+            return Err(e);
+        }
+        match invalid {
+            Some(_) => Ok(complete - held),
+            None => {
+                self.buf = data.split_off(complete);
+                Ok(buf.len())
+            }
+        }
+    }
+}
+
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + buf.len() > self.capacity {
+            self.flush_buf()?;
+        }
+        if buf.len() >= self.capacity {
+            return self.write_through(buf);
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()
+    }
+}
+
+impl Drop for ConsoleWriter {
+    fn drop(&mut self) {
+        let _ = self.flush_buf();
+    }
+}
+
+/// Get the length of the complete characters at the start of `buf`, and the length of the
+/// invalid sequence that follows them, if any.
+///
+/// An incomplete character at the end is neither, it may be completed by the next write.
+fn valid_prefix(buf: &[u8]) -> (usize, Option<usize>) {
+    match str::from_utf8(buf) {
+        Ok(_) => (buf.len(), None),
+        Err(e) => (e.valid_up_to(), e.error_len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::valid_prefix;
+
+    #[test]
+    fn test_valid_prefix() {
+        assert_eq!(valid_prefix("abc".as_bytes()), (3, None));
+        // The first two bytes of a three byte character.
+        assert_eq!(valid_prefix(b"ab\xe2\x82"), (2, None));
+        // An invalid byte is dropped, the text around it is kept.
+        assert_eq!(valid_prefix(b"ab\xffcd"), (2, Some(1)));
+        assert_eq!(valid_prefix(b"\xe2\x82x"), (0, Some(2)));
+        assert_eq!(valid_prefix(b""), (0, None));
+    }
+}
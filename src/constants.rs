@@ -0,0 +1,102 @@
+//! This module names the bits of [`ControlKeyState`] and [`ButtonState`], so they can be used
+//! without depending on `winapi`.
+//!
+//! ```
+//! # use crossterm_winapi::{constants, KeyEventRecord};
+//! let record = KeyEventRecord::builder().char('a').alt().build();
+//! assert!(record.control_key_state.contains(constants::LEFT_ALT_PRESSED));
+//! ```
+
+use super::{ButtonState, ControlKeyState};
+
+/// The right alt key is pressed.
+pub const RIGHT_ALT_PRESSED: ControlKeyState = ControlKeyState::new(0x0001);
+/// The left alt key is pressed.
+pub const LEFT_ALT_PRESSED: ControlKeyState = ControlKeyState::new(0x0002);
+/// The right control key is pressed.
+pub const RIGHT_CTRL_PRESSED: ControlKeyState = ControlKeyState::new(0x0004);
+/// The left control key is pressed.
+pub const LEFT_CTRL_PRESSED: ControlKeyState = ControlKeyState::new(0x0008);
+/// The shift key is pressed.
+pub const SHIFT_PRESSED: ControlKeyState = ControlKeyState::new(0x0010);
+/// The num lock light is on.
+pub const NUMLOCK_ON: ControlKeyState = ControlKeyState::new(0x0020);
+/// The scroll lock light is on.
+pub const SCROLLLOCK_ON: ControlKeyState = ControlKeyState::new(0x0040);
+/// The caps lock light is on.
+pub const CAPSLOCK_ON: ControlKeyState = ControlKeyState::new(0x0080);
+/// The key is
+/// [enhanced](https://docs.microsoft.com/en-us/windows/console/key-event-record-str#remarks).
+pub const ENHANCED_KEY: ControlKeyState = ControlKeyState::new(0x0100);
+
+/// The leftmost mouse button is pressed.
+pub const FROM_LEFT_1ST_BUTTON_PRESSED: ButtonState = ButtonState::new(0x0001);
+/// The rightmost mouse button is pressed.
+pub const RIGHTMOST_BUTTON_PRESSED: ButtonState = ButtonState::new(0x0002);
+/// The second button from the left is pressed, usually the middle button.
+pub const FROM_LEFT_2ND_BUTTON_PRESSED: ButtonState = ButtonState::new(0x0004);
+/// The third button from the left is pressed.
+pub const FROM_LEFT_3RD_BUTTON_PRESSED: ButtonState = ButtonState::new(0x0008);
+/// The fourth button from the left is pressed.
+pub const FROM_LEFT_4TH_BUTTON_PRESSED: ButtonState = ButtonState::new(0x0010);
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon;
+
+    #[test]
+    fn test_control_key_state_matches_headers() {
+        let constants = [
+            (super::RIGHT_ALT_PRESSED, wincon::RIGHT_ALT_PRESSED),
+            (super::LEFT_ALT_PRESSED, wincon::LEFT_ALT_PRESSED),
+            (super::RIGHT_CTRL_PRESSED, wincon::RIGHT_CTRL_PRESSED),
+            (super::LEFT_CTRL_PRESSED, wincon::LEFT_CTRL_PRESSED),
+            (super::SHIFT_PRESSED, wincon::SHIFT_PRESSED),
+            (super::NUMLOCK_ON, wincon::NUMLOCK_ON),
+            (super::SCROLLLOCK_ON, wincon::SCROLLLOCK_ON),
+            (super::CAPSLOCK_ON, wincon::CAPSLOCK_ON),
+            (super::ENHANCED_KEY, wincon::ENHANCED_KEY),
+        ];
+        for (constant, header) in constants {
+            assert_eq!(constant.state(), header);
+        }
+    }
+
+    #[test]
+    fn test_button_state_matches_headers() {
+        let constants = [
+            (
+                super::FROM_LEFT_1ST_BUTTON_PRESSED,
+                wincon::FROM_LEFT_1ST_BUTTON_PRESSED,
+            ),
+            (
+                super::RIGHTMOST_BUTTON_PRESSED,
+                wincon::RIGHTMOST_BUTTON_PRESSED,
+            ),
+            (
+                super::FROM_LEFT_2ND_BUTTON_PRESSED,
+                wincon::FROM_LEFT_2ND_BUTTON_PRESSED,
+            ),
+            (
+                super::FROM_LEFT_3RD_BUTTON_PRESSED,
+                wincon::FROM_LEFT_3RD_BUTTON_PRESSED,
+            ),
+            (
+                super::FROM_LEFT_4TH_BUTTON_PRESSED,
+                wincon::FROM_LEFT_4TH_BUTTON_PRESSED,
+            ),
+        ];
+        for (constant, header) in constants {
+            assert_eq!(constant.state() as u32, header);
+        }
+    }
+
+    #[test]
+    fn test_combine() {
+        let ctrl_shift = super::LEFT_CTRL_PRESSED | super::SHIFT_PRESSED;
+        assert!(ctrl_shift.contains(super::SHIFT_PRESSED));
+        assert!(!ctrl_shift.contains(super::LEFT_ALT_PRESSED));
+        let buttons = super::FROM_LEFT_1ST_BUTTON_PRESSED | super::RIGHTMOST_BUTTON_PRESSED;
+        assert!(buttons.left_button() && buttons.right_button());
+    }
+}
@@ -0,0 +1,115 @@
+//! This module contains the policy used for conversions between integer types that can lose data.
+//!
+//! WinAPI uses signed 16 bit integers for coordinates and sizes, while most of the Rust ecosystem
+//! uses unsigned integers. Converting between them either fails or loses information, the
+//! [`ConversionPolicy`] lets the caller choose which of both should happen.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// How conversions that can't represent a value in the target type should behave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConversionPolicy {
+    /// Fail with a [`ConversionError`] when the value doesn't fit in the target type.
+    #[default]
+    Strict,
+    /// Clamp the value to the nearest value that fits in the target type.
+    Lossy,
+}
+
+impl ConversionPolicy {
+    /// Convert an unsigned value to the `i16` used by WinAPI coordinates.
+    ///
+    /// With [`ConversionPolicy::Lossy`] values larger than `i16::MAX` are clamped to `i16::MAX`.
+    pub fn to_i16(self, value: u32) -> Result<i16, ConversionError> {
+        match (i16::try_from(value), self) {
+            (Ok(value), _) => Ok(value),
+            (Err(_), ConversionPolicy::Lossy) => Ok(i16::MAX),
+            (Err(_), ConversionPolicy::Strict) => Err(ConversionError::new(
+                i64::from(value),
+                "i16",
+                "value is larger than i16::MAX",
+            )),
+        }
+    }
+
+    /// Convert a WinAPI `i16` coordinate to an unsigned value.
+    ///
+    /// With [`ConversionPolicy::Lossy`] negative values are clamped to `0`.
+    pub fn to_u16(self, value: i16) -> Result<u16, ConversionError> {
+        match (u16::try_from(value), self) {
+            (Ok(value), _) => Ok(value),
+            (Err(_), ConversionPolicy::Lossy) => Ok(0),
+            (Err(_), ConversionPolicy::Strict) => Err(ConversionError::new(
+                i64::from(value),
+                "u16",
+                "value is negative",
+            )),
+        }
+    }
+}
+
+/// The error returned when a value can't be converted without losing data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    value: i64,
+    target: &'static str,
+    reason: &'static str,
+}
+
+impl ConversionError {
+    pub(crate) fn new(value: i64, target: &'static str, reason: &'static str) -> Self {
+        ConversionError {
+            value,
+            target,
+            reason,
+        }
+    }
+
+    /// Get the value that could not be converted.
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    /// Get the name of the type the value was being converted to.
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot convert {} to {}: {}",
+            self.value, self.target, self.reason
+        )
+    }
+}
+
+impl Error for ConversionError {}
+
+impl From<ConversionError> for io::Error {
+    fn from(error: ConversionError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConversionPolicy;
+
+    #[test]
+    fn test_strict_rejects_out_of_range() {
+        assert_eq!(ConversionPolicy::Strict.to_i16(12), Ok(12));
+        assert!(ConversionPolicy::Strict.to_i16(40_000).is_err());
+        assert!(ConversionPolicy::Strict.to_u16(-1).is_err());
+    }
+
+    #[test]
+    fn test_lossy_clamps() {
+        assert_eq!(ConversionPolicy::Lossy.to_i16(40_000), Ok(i16::MAX));
+        assert_eq!(ConversionPolicy::Lossy.to_u16(-1), Ok(0));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::io::{self, Result};
+use std::mem;
+use std::ops::{BitOr, BitOrAssign};
+
+use winapi::um::consoleapi::ReadConsoleW;
+use winapi::um::wincon::CONSOLE_READCONSOLE_CONTROL;
+
+use super::{result, trace, ControlKeyState, ErrorContext, Handle};
+
+/// The control characters that end a line read early, for example to complete a word on Tab.
+///
+/// The console's line editor normally only returns on Enter. A key in this set also returns the
+/// read, with the text typed so far, see [`Console::read_line_with`](crate::Console::read_line_with).
+/// Only the control characters `0x00` to `0x1F` can be used, which are the keys `Ctrl+@` to
+/// `Ctrl+_`, with Tab being `Ctrl+I`.
+///
+/// ```no_run
+/// use crossterm_winapi::WakeupKeys;
+///
+/// let keys = WakeupKeys::TAB | WakeupKeys::CTRL_D;
+/// assert!(keys.contains('\t'));
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WakeupKeys(u32);
+
+impl WakeupKeys {
+    /// No key ends the read early.
+    pub const NONE: WakeupKeys = WakeupKeys(0);
+    /// Tab, which is `Ctrl+I`.
+    pub const TAB: WakeupKeys = WakeupKeys::control(b'I');
+    /// `Ctrl+D`, end of input on Unix.
+    pub const CTRL_D: WakeupKeys = WakeupKeys::control(b'D');
+    /// `Ctrl+L`, usually clears the screen.
+    pub const CTRL_L: WakeupKeys = WakeupKeys::control(b'L');
+    /// `Ctrl+R`, usually searches the history.
+    pub const CTRL_R: WakeupKeys = WakeupKeys::control(b'R');
+    /// `Ctrl+U`, usually deletes to the start of the line.
+    pub const CTRL_U: WakeupKeys = WakeupKeys::control(b'U');
+    /// `Ctrl+W`, usually deletes the previous word.
+    pub const CTRL_W: WakeupKeys = WakeupKeys::control(b'W');
+    /// `Ctrl+Z`, end of input on Windows.
+    pub const CTRL_Z: WakeupKeys = WakeupKeys::control(b'Z');
+
+    /// The set of the single key `Ctrl+letter`, `letter` being an uppercase ASCII letter or one of
+    /// `@[\]^_`.
+    const fn control(letter: u8) -> WakeupKeys {
+        WakeupKeys(1 << (letter & 0x1F))
+    }
+
+    /// Get the set of the single control character `c`, or `None` if it isn't a control character
+    /// below `0x20`.
+    pub fn from_char(c: char) -> Option<WakeupKeys> {
+        match c {
+            '\0'..='\x1F' => Some(WakeupKeys(1 << c as u32)),
+            _ => None,
+        }
+    }
+
+    /// Create the set from the raw `dwCtrlWakeupMask`, in which bit `n` stands for the control
+    /// character `n`.
+    pub fn from_bits(bits: u32) -> WakeupKeys {
+        WakeupKeys(bits)
+    }
+
+    /// Get the raw `dwCtrlWakeupMask`.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether `c` is in the set.
+    pub fn contains(self, c: char) -> bool {
+        WakeupKeys::from_char(c).map_or(false, |key| self.0 & key.0 != 0)
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the control characters in the set.
+    pub fn chars(self) -> impl Iterator<Item = char> {
+        (0u8..0x20)
+            .filter(move |c| self.0 & (1 << c) != 0)
+            .map(char::from)
+    }
+}
+
+impl BitOr for WakeupKeys {
+    type Output = WakeupKeys;
+
+    fn bitor(self, rhs: WakeupKeys) -> WakeupKeys {
+        WakeupKeys(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for WakeupKeys {
+    fn bitor_assign(&mut self, rhs: WakeupKeys) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl fmt::Debug for WakeupKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(
+                self.chars()
+                    .map(|c| format!("Ctrl+{}", char::from(c as u8 | 0x40))),
+            )
+            .finish()
+    }
+}
+
+/// A line read by [`Console::read_line_with`](crate::Console::read_line_with).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookedLine {
+    /// The text of the line, without the line break or the wakeup key.
+    pub text: String,
+    /// The wakeup key that ended the read, or `None` if it was ended with Enter.
+    pub wakeup: Option<char>,
+    /// The state of the control keys when the read ended.
+    pub control_key_state: ControlKeyState,
+}
+
+/// Read a line from `handle` with the console's line editor, starting from `initial`.
+pub(crate) fn read_line_with(
+    handle: &Handle,
+    initial: &str,
+    wakeup: WakeupKeys,
+) -> Result<CookedLine> {
+    let initial: Vec<u16> = initial.encode_utf16().collect();
+    let mut units = Vec::new();
+    let mut first = true;
+    loop {
+        // The initial characters are passed in the buffer of the first read, the console
+        // returns them as part of the line.
+        let initial_chars = if first { initial.len() } else { 0 };
+        let mut buffer = initial[..initial_chars].to_vec();
+        buffer.resize(initial_chars + 512, 0);
+        let mut control = CONSOLE_READCONSOLE_CONTROL {
+            nLength: mem::size_of::<CONSOLE_READCONSOLE_CONTROL>() as u32,
+            nInitialChars: initial_chars as u32,
+            dwCtrlWakeupMask: wakeup.bits(),
+            dwControlKeyState: 0,
+        };
+        let mut read = 0;
+        result(trace::call("ReadConsoleW", **handle, || unsafe {
+            ReadConsoleW(
+                **handle,
+                buffer.as_mut_ptr().cast(),
+                buffer.len() as u32,
+                &mut read,
+                &mut control,
+            )
+        }))
+        .with_handle_context("ReadConsoleW", handle)?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the read was interrupted",
+            ));
+        }
+        first = false;
+
+        units.extend_from_slice(&buffer[..read as usize]);
+        if let Some((text, key)) = split_line(&units, wakeup) {
+            return Ok(CookedLine {
+                text,
+                wakeup: key,
+                control_key_state: ControlKeyState::new(control.dwControlKeyState),
+            });
+        }
+    }
+}
+
+/// Split the UTF-16 `units` read so far into the text and the wakeup key that ended the read, or
+/// `None` if the line isn't complete yet.
+fn split_line(units: &[u16], wakeup: WakeupKeys) -> Option<(String, Option<char>)> {
+    let is_wakeup = |unit: &u16| *unit < 0x20 && wakeup.bits() & (1 << unit) != 0;
+    if let Some(position) = units.iter().position(is_wakeup) {
+        // The console inserts the key at the cursor, the text after it is kept.
+        let text = units[..position]
+            .iter()
+            .chain(&units[position + 1..])
+            .copied();
+        let text = char::decode_utf16(text)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        return Some((text, Some(char::from(units[position] as u8))));
+    }
+
+    if units.last() != Some(&u16::from(b'\n')) {
+        return None;
+    }
+    let text = String::from_utf16_lossy(units);
+    Some((text.trim_end_matches(&['\r', '\n'][..]).to_owned(), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_line, WakeupKeys};
+
+    fn utf16(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn test_wakeup_keys() {
+        let keys = WakeupKeys::TAB | WakeupKeys::CTRL_D;
+        assert_eq!(keys.bits(), (1 << 9) | (1 << 4));
+        assert!(keys.contains('\t'));
+        assert!(!keys.contains('a'));
+        assert_eq!(keys.chars().collect::<Vec<_>>(), vec!['\x04', '\t']);
+        assert_eq!(WakeupKeys::from_char('\t'), Some(WakeupKeys::TAB));
+        assert_eq!(WakeupKeys::from_char(' '), None);
+        assert_eq!(format!("{:?}", keys), "{\"Ctrl+D\", \"Ctrl+I\"}");
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line(&utf16("git ch"), WakeupKeys::TAB), None);
+        assert_eq!(
+            split_line(&utf16("git ch\r\n"), WakeupKeys::TAB),
+            Some(("git ch".to_owned(), None))
+        );
+        assert_eq!(
+            split_line(&utf16("git ch\t foo"), WakeupKeys::TAB),
+            Some(("git ch foo".to_owned(), Some('\t')))
+        );
+        assert_eq!(
+            split_line(&utf16("a\tb\r\n"), WakeupKeys::NONE),
+            Some(("a\tb".to_owned(), None))
+        );
+    }
+}
@@ -30,6 +30,12 @@ impl fmt::Debug for ScreenBufferInfo {
     }
 }
 
+impl Default for ScreenBufferInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ScreenBufferInfo {
     /// Create a new console screen buffer without all zeroed properties.
     pub fn new() -> ScreenBufferInfo {
@@ -46,6 +52,9 @@ impl ScreenBufferInfo {
     /// Get the size of the terminal display window.
     ///
     /// Will calculate the width and height from `srWindow` and convert it into a [`Size`].
+    ///
+    /// See [`ScreenBuffer::size_report`](crate::ScreenBuffer::size_report) for the size in
+    /// pixels.
     pub fn terminal_size(&self) -> Size {
         Size::new(
             self.0.srWindow.Right - self.0.srWindow.Left,
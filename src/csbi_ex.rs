@@ -0,0 +1,156 @@
+use std::fmt;
+use std::mem::{size_of, zeroed};
+
+use winapi::um::wincon::{CONSOLE_SCREEN_BUFFER_INFO, CONSOLE_SCREEN_BUFFER_INFOEX};
+
+use super::ScreenBufferInfo;
+
+/// The colors of the default console palette ("Campbell"), as `0x00BBGGRR` values.
+pub const DEFAULT_COLOR_TABLE: [u32; 16] = [
+    0x000c_0c0c,
+    0x00da_3700,
+    0x000e_a113,
+    0x00dd_963a,
+    0x001f_0fc5,
+    0x0098_1788,
+    0x0000_9cc1,
+    0x00cc_cccc,
+    0x0076_7676,
+    0x00ff_783b,
+    0x000c_c616,
+    0x00d6_d661,
+    0x0056_48e7,
+    0x009e_00b4,
+    0x00a5_f1f9,
+    0x00f2_f2f2,
+];
+
+/// The default attributes of popups, like the command history, white on magenta.
+pub const DEFAULT_POPUP_ATTRIBUTES: u16 = 0x00f5;
+
+/// How a [`ScreenBufferInfoEx`] was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoExSource {
+    /// `GetConsoleScreenBufferInfoEx` succeeded.
+    Native,
+    /// `GetConsoleScreenBufferInfoEx` failed, as it does on some versions of Wine and ReactOS.
+    /// The basic information comes from `GetConsoleScreenBufferInfo`, the rest are defaults.
+    Fallback,
+}
+
+/// Extended information about a console screen buffer, see
+/// [`ScreenBuffer::info_ex`](crate::ScreenBuffer::info_ex).
+///
+/// This wraps
+/// [`CONSOLE_SCREEN_BUFFER_INFOEX`](https://docs.microsoft.com/en-us/windows/console/console-screen-buffer-infoex).
+#[derive(Clone)]
+pub struct ScreenBufferInfoEx {
+    raw: CONSOLE_SCREEN_BUFFER_INFOEX,
+    source: InfoExSource,
+}
+
+impl ScreenBufferInfoEx {
+    /// Create an empty structure to pass to `GetConsoleScreenBufferInfoEx`.
+    pub(crate) fn empty() -> CONSOLE_SCREEN_BUFFER_INFOEX {
+        let mut raw: CONSOLE_SCREEN_BUFFER_INFOEX = unsafe { zeroed() };
+        raw.cbSize = size_of::<CONSOLE_SCREEN_BUFFER_INFOEX>() as u32;
+        raw
+    }
+
+    pub(crate) fn native(raw: CONSOLE_SCREEN_BUFFER_INFOEX) -> ScreenBufferInfoEx {
+        ScreenBufferInfoEx {
+            raw,
+            source: InfoExSource::Native,
+        }
+    }
+
+    /// Fill in the extended information with defaults.
+    pub(crate) fn fallback(info: &ScreenBufferInfo) -> ScreenBufferInfoEx {
+        let mut raw = ScreenBufferInfoEx::empty();
+        raw.dwSize = info.0.dwSize;
+        raw.dwCursorPosition = info.0.dwCursorPosition;
+        raw.wAttributes = info.0.wAttributes;
+        raw.srWindow = info.0.srWindow;
+        raw.dwMaximumWindowSize = info.0.dwMaximumWindowSize;
+        raw.wPopupAttributes = DEFAULT_POPUP_ATTRIBUTES;
+        raw.bFullscreenSupported = 0;
+        raw.ColorTable = DEFAULT_COLOR_TABLE;
+        ScreenBufferInfoEx {
+            raw,
+            source: InfoExSource::Fallback,
+        }
+    }
+
+    /// Get how this information was obtained.
+    pub fn source(&self) -> InfoExSource {
+        self.source
+    }
+
+    /// Get the basic information, like the buffer size, the window and the cursor position.
+    pub fn info(&self) -> ScreenBufferInfo {
+        ScreenBufferInfo(CONSOLE_SCREEN_BUFFER_INFO {
+            dwSize: self.raw.dwSize,
+            dwCursorPosition: self.raw.dwCursorPosition,
+            wAttributes: self.raw.wAttributes,
+            srWindow: self.raw.srWindow,
+            dwMaximumWindowSize: self.raw.dwMaximumWindowSize,
+        })
+    }
+
+    /// Get the attributes of popups, like the command history.
+    pub fn popup_attributes(&self) -> u16 {
+        self.raw.wPopupAttributes
+    }
+
+    /// Returns whether the console supports full screen mode.
+    pub fn fullscreen_supported(&self) -> bool {
+        self.raw.bFullscreenSupported != 0
+    }
+
+    /// Get the 16 colors of the palette as `0x00BBGGRR` values, indexed by the color bits of the
+    /// [`TextAttributes`](crate::TextAttributes).
+    pub fn color_table(&self) -> [u32; 16] {
+        self.raw.ColorTable
+    }
+
+    /// Get the raw structure.
+    pub fn raw(&self) -> &CONSOLE_SCREEN_BUFFER_INFOEX {
+        &self.raw
+    }
+}
+
+impl fmt::Debug for ScreenBufferInfoEx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScreenBufferInfoEx")
+            .field("source", &self.source)
+            .field("info", &self.info())
+            .field("popup_attributes", &self.popup_attributes())
+            .field("fullscreen_supported", &self.fullscreen_supported())
+            .field("color_table", &self.color_table())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InfoExSource, ScreenBufferInfoEx, DEFAULT_COLOR_TABLE, DEFAULT_POPUP_ATTRIBUTES};
+    use crate::{Coord, ScreenBufferInfo, Size};
+
+    #[test]
+    fn test_fallback() {
+        let mut info = ScreenBufferInfo::new();
+        info.0.dwSize.X = 120;
+        info.0.dwSize.Y = 9001;
+        info.0.dwCursorPosition.Y = 3;
+        info.0.wAttributes = 0x0007;
+
+        let ex = ScreenBufferInfoEx::fallback(&info);
+        assert_eq!(ex.source(), InfoExSource::Fallback);
+        assert_eq!(ex.info().buffer_size(), Size::new(120, 9001));
+        assert_eq!(ex.info().cursor_pos(), Coord::new(0, 3));
+        assert_eq!(ex.info().attributes(), 0x0007);
+        assert_eq!(ex.popup_attributes(), DEFAULT_POPUP_ATTRIBUTES);
+        assert_eq!(ex.color_table(), DEFAULT_COLOR_TABLE);
+        assert!(!ex.fullscreen_supported());
+    }
+}
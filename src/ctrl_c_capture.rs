@@ -0,0 +1,56 @@
+use std::io::Result;
+
+use winapi::um::wincon::ENABLE_PROCESSED_INPUT;
+
+use super::{ConsoleMode, Handle};
+
+/// Delivers Ctrl+C as a key event instead of a signal until dropped, see
+/// [`Console::capture_ctrl_c`](crate::Console::capture_ctrl_c).
+///
+/// The input mode that was active before is restored on drop, on an early return or a panic as
+/// well.
+#[derive(Debug)]
+pub struct CtrlCCaptureGuard {
+    mode: ConsoleMode,
+    previous: u32,
+}
+
+impl CtrlCCaptureGuard {
+    pub(crate) fn new(handle: Handle) -> Result<CtrlCCaptureGuard> {
+        let mode = ConsoleMode::from(handle);
+        let previous = mode.mode()?;
+        mode.set_mode(capture_mode(previous))?;
+        Ok(CtrlCCaptureGuard { mode, previous })
+    }
+
+    /// Get the input mode that will be restored on drop.
+    pub fn previous(&self) -> u32 {
+        self.previous
+    }
+}
+
+impl Drop for CtrlCCaptureGuard {
+    fn drop(&mut self) {
+        // There is nothing sensible to do with an error while dropping.
+        let _ = self.mode.set_mode(self.previous);
+    }
+}
+
+fn capture_mode(mode: u32) -> u32 {
+    mode & !ENABLE_PROCESSED_INPUT
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT, ENABLE_WINDOW_INPUT};
+
+    use super::capture_mode;
+
+    #[test]
+    fn test_capture_mode() {
+        assert_eq!(
+            capture_mode(ENABLE_PROCESSED_INPUT | ENABLE_LINE_INPUT | ENABLE_WINDOW_INPUT),
+            ENABLE_LINE_INPUT | ENABLE_WINDOW_INPUT
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::io::Result;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+use winapi::um::wincon::{
+    CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT,
+};
+
+use super::{result, trace, ErrorContext};
+
+/// A console control signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CtrlEvent {
+    /// Ctrl+C was pressed.
+    CtrlC,
+    /// Ctrl+Break was pressed.
+    CtrlBreak,
+    /// The console window is being closed. The process is terminated once the handlers
+    /// returned, regardless of what they do.
+    Close,
+    /// The user is logging off, only sent to services.
+    Logoff,
+    /// The system is shutting down, only sent to services.
+    Shutdown,
+}
+
+impl CtrlEvent {
+    fn from_raw(ctrl_type: DWORD) -> Option<CtrlEvent> {
+        match ctrl_type {
+            CTRL_C_EVENT => Some(CtrlEvent::CtrlC),
+            CTRL_BREAK_EVENT => Some(CtrlEvent::CtrlBreak),
+            CTRL_CLOSE_EVENT => Some(CtrlEvent::Close),
+            CTRL_LOGOFF_EVENT => Some(CtrlEvent::Logoff),
+            CTRL_SHUTDOWN_EVENT => Some(CtrlEvent::Shutdown),
+            _ => None,
+        }
+    }
+}
+
+type Callback = Arc<dyn Fn(CtrlEvent) + Send + Sync>;
+
+struct Entry {
+    id: u64,
+    priority: i32,
+    propagate_to_default: bool,
+    callback: Callback,
+}
+
+struct Registry {
+    next_id: u64,
+    entries: Vec<Entry>,
+}
+
+/// The handlers of the process, ordered by descending priority and then by registration.
+static HANDLERS: Mutex<Registry> = Mutex::new(Registry {
+    next_id: 0,
+    entries: Vec::new(),
+});
+
+/// A console control handler written in Rust.
+///
+/// `SetConsoleCtrlHandler` keeps a chain of handlers that is shared by everything in the process.
+/// This crate adds a single native handler to that chain, the first time a `CtrlHandler` is
+/// registered, and dispatches the signals to all Rust handlers from it. Handlers with a higher
+/// [`priority`](CtrlHandler::priority) run first, handlers with the same priority in the order
+/// they were registered.
+///
+/// A handler that [propagates to the default](CtrlHandler::propagate_to_default) lets the next
+/// handler see the signal. Once all Rust handlers propagated, the handlers registered before this
+/// crate's, and finally the default handler that exits the process, see it too. A handler that
+/// doesn't propagate consumes the signal.
+///
+/// ```no_run
+/// # use crossterm_winapi::{CtrlEvent, CtrlHandler};
+/// # fn main() -> std::io::Result<()> {
+/// let _flush_logs = CtrlHandler::new(|_| println!("flushing logs"))
+///     .priority(10)
+///     .register()?;
+/// let _cancel = CtrlHandler::new(|event| {
+///     if event == CtrlEvent::CtrlC {
+///         println!("cancelling the current operation");
+///     }
+/// })
+/// .propagate_to_default(false)
+/// .register()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CtrlHandler {
+    priority: i32,
+    propagate_to_default: bool,
+    callback: Callback,
+}
+
+impl fmt::Debug for CtrlHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CtrlHandler")
+            .field("priority", &self.priority)
+            .field("propagate_to_default", &self.propagate_to_default)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CtrlHandler {
+    /// Create a handler that calls `callback` with every signal.
+    ///
+    /// The callback runs on a thread the system creates for the signal, so it must not wait for
+    /// the thread that registered it. By default the handler has priority `0` and propagates to
+    /// the default.
+    pub fn new(callback: impl Fn(CtrlEvent) + Send + Sync + 'static) -> CtrlHandler {
+        CtrlHandler {
+            priority: 0,
+            propagate_to_default: true,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Set the priority, handlers with a higher priority run first.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set whether the signal is passed on to the next handler after this one, and eventually to
+    /// the default handler that exits the process.
+    pub fn propagate_to_default(mut self, propagate: bool) -> Self {
+        self.propagate_to_default = propagate;
+        self
+    }
+
+    /// Register the handler, it is unregistered when the guard is dropped.
+    ///
+    /// This wraps
+    /// [`SetConsoleCtrlHandler`](https://docs.microsoft.com/en-us/windows/console/setconsolectrlhandler).
+    pub fn register(self) -> Result<CtrlHandlerGuard> {
+        let mut registry = HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+        if registry.entries.is_empty() {
+            result(trace::call(
+                "SetConsoleCtrlHandler",
+                ptr::null_mut(),
+                || unsafe { SetConsoleCtrlHandler(Some(dispatch), TRUE) },
+            ))
+            .with_context("SetConsoleCtrlHandler")?;
+        }
+
+        let id = registry.next_id;
+        registry.next_id += 1;
+        let index = insert_position(&registry.entries, self.priority);
+        registry.entries.insert(
+            index,
+            Entry {
+                id,
+                priority: self.priority,
+                propagate_to_default: self.propagate_to_default,
+                callback: self.callback,
+            },
+        );
+        Ok(CtrlHandlerGuard { id })
+    }
+}
+
+/// Keeps a [`CtrlHandler`] registered, it is unregistered on drop.
+#[derive(Debug)]
+pub struct CtrlHandlerGuard {
+    id: u64,
+}
+
+impl Drop for CtrlHandlerGuard {
+    fn drop(&mut self) {
+        let mut registry = HANDLERS.lock().unwrap_or_else(|e| e.into_inner());
+        registry.entries.retain(|entry| entry.id != self.id);
+        if registry.entries.is_empty() {
+            trace::call("SetConsoleCtrlHandler", ptr::null_mut(), || unsafe {
+                SetConsoleCtrlHandler(Some(dispatch), FALSE)
+            });
+        }
+    }
+}
+
+/// The index to insert a handler with `priority` at, after the handlers with the same priority.
+fn insert_position(entries: &[Entry], priority: i32) -> usize {
+    entries
+        .iter()
+        .position(|entry| entry.priority < priority)
+        .unwrap_or(entries.len())
+}
+
+/// Run `handlers` in order until one doesn't propagate, returning whether the signal was
+/// consumed.
+fn run(handlers: &[(Callback, bool)], event: CtrlEvent) -> bool {
+    for (callback, propagate_to_default) in handlers {
+        callback(event);
+        if !propagate_to_default {
+            return true;
+        }
+    }
+    false
+}
+
+/// The native handler that dispatches signals to the registered handlers.
+unsafe extern "system" fn dispatch(ctrl_type: DWORD) -> BOOL {
+    let event = match CtrlEvent::from_raw(ctrl_type) {
+        Some(event) => event,
+        None => return FALSE,
+    };
+    // Call the handlers without holding the lock, so they can register and unregister handlers.
+    let handlers: Vec<(Callback, bool)> = HANDLERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entries
+        .iter()
+        .map(|entry| (entry.callback.clone(), entry.propagate_to_default))
+        .collect();
+    if run(&handlers, event) {
+        TRUE
+    } else {
+        FALSE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{insert_position, run, Callback, CtrlEvent, Entry};
+
+    fn entry(priority: i32) -> Entry {
+        Entry {
+            id: 0,
+            priority,
+            propagate_to_default: true,
+            callback: Arc::new(|_| {}),
+        }
+    }
+
+    #[test]
+    fn test_insert_position() {
+        let entries = [entry(10), entry(0), entry(0), entry(-5)];
+        assert_eq!(insert_position(&entries, 20), 0);
+        assert_eq!(insert_position(&entries, 0), 3);
+        assert_eq!(insert_position(&entries, -10), 4);
+    }
+
+    #[test]
+    fn test_run_stops_at_consuming_handler() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let handler = |name: &'static str| -> Callback {
+            let calls = calls.clone();
+            Arc::new(move |_| calls.lock().unwrap().push(name))
+        };
+
+        let handlers = [
+            (handler("first"), true),
+            (handler("second"), false),
+            (handler("third"), true),
+        ];
+        assert!(run(&handlers, CtrlEvent::CtrlC));
+        assert_eq!(*calls.lock().unwrap(), ["first", "second"]);
+
+        assert!(!run(&handlers[2..], CtrlEvent::CtrlBreak));
+    }
+}
@@ -0,0 +1,86 @@
+//! Bookkeeping for the handles opened by this crate, enabled with the `debug-handles` feature.
+//!
+//! Every handle that is exclusively owned by a [`Handle`](crate::Handle) (and thus closed by it on
+//! drop) is recorded together with the backtrace of the place where it was created. Calling
+//! [`handle_report`] lists the handles that are still alive, which makes it possible to tell
+//! whether a growing handle count comes from this crate or from somewhere else.
+
+use std::backtrace::Backtrace;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+use winapi::um::winnt::HANDLE;
+
+static LIVE_HANDLES: Mutex<Vec<LiveHandle>> = Mutex::new(Vec::new());
+
+/// A handle opened by this crate that has not been closed yet.
+#[derive(Debug, Clone)]
+pub struct LiveHandle {
+    /// The raw value of the `HANDLE`.
+    pub raw: usize,
+    /// The backtrace captured when the handle was created.
+    pub backtrace: String,
+}
+
+/// A list of all the handles opened by this crate that are still alive.
+#[derive(Debug, Clone)]
+pub struct HandleReport {
+    /// The live handles, in the order they were created.
+    pub handles: Vec<LiveHandle>,
+}
+
+impl HandleReport {
+    /// Get the number of live handles.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns whether there are no live handles.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+}
+
+impl fmt::Display for HandleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} live handle(s)", self.handles.len())?;
+        for handle in &self.handles {
+            writeln!(f, "handle {:#x} created at:", handle.raw)?;
+            writeln!(f, "{}", handle.backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get a report of all the handles opened by this crate that have not been closed yet.
+///
+/// Handles that are shared with the process, like the ones returned by
+/// [`Handle::output_handle`](crate::Handle::output_handle), are never closed by this crate and
+/// are therefore not part of the report.
+pub fn handle_report() -> HandleReport {
+    HandleReport {
+        handles: live_handles().clone(),
+    }
+}
+
+pub(crate) fn track(handle: HANDLE) {
+    let backtrace = Backtrace::force_capture().to_string();
+    live_handles().push(LiveHandle {
+        raw: handle as usize,
+        backtrace,
+    });
+}
+
+pub(crate) fn untrack(handle: HANDLE) {
+    let mut handles = live_handles();
+    if let Some(index) = handles.iter().position(|h| h.raw == handle as usize) {
+        handles.remove(index);
+    }
+}
+
+fn live_handles() -> MutexGuard<'static, Vec<LiveHandle>> {
+    // A panic while holding the lock can't leave the list in an inconsistent state.
+    LIVE_HANDLES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,135 @@
+//! This module contains a structured report about the state of the console, meant to be attached to
+//! bug reports.
+
+use std::env;
+use std::fmt;
+use std::io::Result;
+use std::ptr;
+
+use winapi::um::{
+    consoleapi::{GetConsoleCP, GetConsoleOutputCP},
+    wincon::ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+};
+
+use super::{
+    supports_conpty, supports_vt_input, trace, windows_version, ConsoleMode, FontInfo, Handle,
+    HandleKind, HandleType, Overrides, ScreenBuffer, ScreenBufferInfo, Support, WindowsVersion,
+};
+
+/// A snapshot of the console state of the current process.
+///
+/// Every part of the report is gathered independently, so a failure in one of them doesn't
+/// prevent the others from being reported. Use the `Display` implementation to get a
+/// human readable dump.
+#[derive(Debug)]
+pub struct Diagnostics {
+    /// The input code page, see [`GetConsoleCP`](https://docs.microsoft.com/en-us/windows/console/getconsolecp).
+    pub input_code_page: u32,
+    /// The output code page, see [`GetConsoleOutputCP`](https://docs.microsoft.com/en-us/windows/console/getconsoleoutputcp).
+    pub output_code_page: u32,
+    /// The mode of the console input buffer.
+    pub input_mode: Result<u32>,
+    /// The mode of the active console screen buffer.
+    pub output_mode: Result<u32>,
+    /// Information about the active screen buffer, like its size and window rectangle.
+    pub screen_buffer: Result<ScreenBufferInfo>,
+    /// The font used by the active screen buffer.
+    pub font: Result<FontInfo>,
+    /// Whether virtual terminal processing is enabled on the active screen buffer, as set in its
+    /// mode.
+    pub vt_processing: bool,
+    /// The behavior overrides set in the environment.
+    pub overrides: Overrides,
+    /// Whether the process seems to be running inside Windows Terminal.
+    pub windows_terminal: bool,
+    /// The version of Windows, see [`windows_version`](crate::windows_version).
+    pub windows_version: Result<WindowsVersion>,
+    /// Whether the pseudo console API is available.
+    pub conpty: Support,
+    /// Whether the console input buffer can report input as virtual terminal sequences.
+    pub vt_input: Support,
+    /// The kind of the standard input handle.
+    pub stdin: Result<HandleKind>,
+    /// The kind of the standard output handle.
+    pub stdout: Result<HandleKind>,
+    /// The kind of the standard error handle.
+    pub stderr: Result<HandleKind>,
+}
+
+/// Gather a [`Diagnostics`] report about the console of the current process.
+pub fn diagnostics() -> Diagnostics {
+    let output_mode =
+        Handle::current_out_handle().and_then(|handle| ConsoleMode::from(handle).mode());
+    let vt_processing = match output_mode {
+        Ok(mode) => mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0,
+        Err(_) => false,
+    };
+
+    Diagnostics {
+        input_code_page: trace::call("GetConsoleCP", ptr::null_mut(), || unsafe {
+            GetConsoleCP()
+        }),
+        output_code_page: trace::call("GetConsoleOutputCP", ptr::null_mut(), || unsafe {
+            GetConsoleOutputCP()
+        }),
+        input_mode: Handle::current_in_handle().and_then(|handle| ConsoleMode::from(handle).mode()),
+        output_mode,
+        screen_buffer: ScreenBuffer::current().and_then(|buffer| buffer.info()),
+        font: ScreenBuffer::current().and_then(|buffer| buffer.font_info()),
+        vt_processing,
+        overrides: Overrides::current(),
+        windows_terminal: env::var_os("WT_SESSION").is_some(),
+        windows_version: windows_version(),
+        conpty: supports_conpty(),
+        vt_input: supports_vt_input(),
+        stdin: Handle::new(HandleType::InputHandle).map(|handle| handle.kind()),
+        stdout: Handle::new(HandleType::OutputHandle).map(|handle| handle.kind()),
+        stderr: Handle::new(HandleType::ErrorHandle).map(|handle| handle.kind()),
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input code page: {}", self.input_code_page)?;
+        writeln!(f, "output code page: {}", self.output_code_page)?;
+        match &self.input_mode {
+            Ok(mode) => writeln!(f, "input mode: {:#06x}", mode)?,
+            Err(e) => writeln!(f, "input mode: unavailable ({})", e)?,
+        }
+        match &self.output_mode {
+            Ok(mode) => writeln!(f, "output mode: {:#06x}", mode)?,
+            Err(e) => writeln!(f, "output mode: unavailable ({})", e)?,
+        }
+        match &self.screen_buffer {
+            Ok(info) => {
+                writeln!(f, "buffer size: {:?}", info.buffer_size())?;
+                writeln!(f, "window: {:?}", info.terminal_window())?;
+            }
+            Err(e) => writeln!(f, "screen buffer: unavailable ({})", e)?,
+        }
+        match &self.font {
+            Ok(font) => writeln!(f, "font: {:?}", font)?,
+            Err(e) => writeln!(f, "font: unavailable ({})", e)?,
+        }
+        writeln!(f, "vt processing: {}", self.vt_processing)?;
+        writeln!(f, "overrides: {:?}", self.overrides)?;
+        writeln!(f, "windows terminal: {}", self.windows_terminal)?;
+        match &self.windows_version {
+            Ok(version) => writeln!(f, "windows version: {}", version)?,
+            Err(e) => writeln!(f, "windows version: unavailable ({})", e)?,
+        }
+        writeln!(f, "conpty: {}", self.conpty)?;
+        writeln!(f, "vt input: {}", self.vt_input)?;
+        for (name, kind) in [
+            ("stdin", &self.stdin),
+            ("stdout", &self.stdout),
+            ("stderr", &self.stderr),
+        ] {
+            match kind {
+                Ok(kind) => writeln!(f, "{}: {:?}", name, kind)?,
+                Err(e) => writeln!(f, "{}: unavailable ({})", name, e)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! This module contains functions to convert text from and to the code page of the console.
+//!
+//! Child processes that don't use Unicode write their output in the output code page of the
+//! console, which isn't UTF-8 on most systems. Use these to decode output read from such a
+//! process through a pipe, or to encode its input.
+//!
+//! If the process has no console, the ANSI code page of the system is used.
+
+use std::io::{self, Result};
+use std::ptr::null_mut;
+
+use winapi::um::{
+    consoleapi::{GetConsoleCP, GetConsoleOutputCP},
+    stringapiset::{MultiByteToWideChar, WideCharToMultiByte},
+    winnls::IsDBCSLeadByteEx,
+};
+
+use super::trace;
+
+/// Encode `text` in the output code page of the console.
+///
+/// Characters that the code page can't represent are replaced with its default character,
+/// usually `?`.
+pub fn to_console_bytes(text: &str) -> Result<Vec<u8>> {
+    to_code_page(output_code_page(), text)
+}
+
+/// Decode `bytes` from the output code page of the console.
+///
+/// Invalid sequences are replaced with `U+FFFD`.
+pub fn from_console_bytes(bytes: &[u8]) -> Result<String> {
+    from_code_page(output_code_page(), bytes)
+}
+
+/// Encode `text` in the given code page.
+///
+/// This wraps
+/// [`WideCharToMultiByte`](https://docs.microsoft.com/en-us/windows/win32/api/stringapiset/nf-stringapiset-widechartomultibyte).
+pub fn to_code_page(code_page: u32, text: &str) -> Result<Vec<u8>> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let wide_len = length(wide.len())?;
+
+    let convert = |buf: *mut i8, buf_len: i32| {
+        trace::call("WideCharToMultiByte", null_mut(), || unsafe {
+            WideCharToMultiByte(
+                code_page,
+                0,
+                wide.as_ptr(),
+                wide_len,
+                buf,
+                buf_len,
+                null_mut(),
+                null_mut(),
+            )
+        })
+    };
+
+    let len = convert(null_mut(), 0);
+    if len == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut bytes = vec![0u8; len as usize];
+    let len = convert(bytes.as_mut_ptr().cast(), len);
+    if len == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    bytes.truncate(len as usize);
+    Ok(bytes)
+}
+
+/// Decode `bytes` from the given code page.
+///
+/// This wraps
+/// [`MultiByteToWideChar`](https://docs.microsoft.com/en-us/windows/win32/api/stringapiset/nf-stringapiset-multibytetowidechar).
+pub fn from_code_page(code_page: u32, bytes: &[u8]) -> Result<String> {
+    if bytes.is_empty() {
+        return Ok(String::new());
+    }
+    let bytes_len = length(bytes.len())?;
+
+    let convert = |buf: *mut u16, buf_len: i32| {
+        trace::call("MultiByteToWideChar", null_mut(), || unsafe {
+            MultiByteToWideChar(code_page, 0, bytes.as_ptr().cast(), bytes_len, buf, buf_len)
+        })
+    };
+
+    let len = convert(null_mut(), 0);
+    if len == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut wide = vec![0u16; len as usize];
+    let len = convert(wide.as_mut_ptr(), len);
+    if len == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(String::from_utf16_lossy(&wide[..len as usize]))
+}
+
+/// Get the output code page of the console, or `CP_ACP` (`0`) if there is no console.
+fn output_code_page() -> u32 {
+    trace::call("GetConsoleOutputCP", null_mut(), || unsafe {
+        GetConsoleOutputCP()
+    })
+}
+
+/// Get the input code page of the console, or `CP_ACP` (`0`) if there is no console.
+pub(crate) fn input_code_page() -> u32 {
+    trace::call("GetConsoleCP", null_mut(), || unsafe { GetConsoleCP() })
+}
+
+/// Returns whether `bytes` in `code_page` end with the first byte of a two byte character,
+/// whose second byte is still missing.
+pub(crate) fn ends_in_lead_byte(code_page: u32, bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = trace::call("IsDBCSLeadByteEx", null_mut(), || unsafe {
+            IsDBCSLeadByteEx(code_page, bytes[i])
+        }) != 0;
+        if lead && i + 1 == bytes.len() {
+            return true;
+        }
+        i += if lead { 2 } else { 1 };
+    }
+    false
+}
+
+/// Returns how many bytes are missing from a UTF-8 sequence cut off at the end of `bytes`.
+pub(crate) fn missing_utf8_bytes(bytes: &[u8]) -> usize {
+    for i in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - i];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let width: usize = match byte {
+            0xF0..=0xF7 => 4,
+            0xE0..=0xEF => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        return width.saturating_sub(i);
+    }
+    0
+}
+
+fn length(len: usize) -> Result<i32> {
+    i32::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the text is too long to convert at once",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ends_in_lead_byte, from_code_page, missing_utf8_bytes, to_code_page};
+
+    #[test]
+    fn test_code_page_round_trip() {
+        // Windows-1252
+        let bytes = to_code_page(1252, "café").unwrap();
+        assert_eq!(bytes, b"caf\xe9");
+        assert_eq!(from_code_page(1252, &bytes).unwrap(), "café");
+    }
+
+    #[test]
+    fn test_ends_in_lead_byte() {
+        // Shift JIS, あ is `82 a0`.
+        assert!(!ends_in_lead_byte(932, b"a\x82\xa0"));
+        assert!(ends_in_lead_byte(932, b"a\x82\xa0\x82"));
+        // The second byte of a character can be in the lead byte range.
+        assert!(!ends_in_lead_byte(932, b"\x82\x82"));
+        assert!(!ends_in_lead_byte(1252, b"caf\xe9"));
+    }
+
+    #[test]
+    fn test_missing_utf8_bytes() {
+        assert_eq!(missing_utf8_bytes(b""), 0);
+        assert_eq!(missing_utf8_bytes("café".as_bytes()), 0);
+        // é is `c3 a9`, € is `e2 82 ac` and 😀 is `f0 9f 98 80`.
+        assert_eq!(missing_utf8_bytes(b"caf\xc3"), 1);
+        assert_eq!(missing_utf8_bytes(b"\xe2\x82"), 1);
+        assert_eq!(missing_utf8_bytes(b"a\xf0"), 3);
+        assert_eq!(missing_utf8_bytes(b"\xf0\x9f\x98"), 1);
+        assert_eq!(missing_utf8_bytes(b"\xf0\x9f\x98\x80"), 0);
+    }
+}
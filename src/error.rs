@@ -0,0 +1,167 @@
+//! This module adds the name of the failed API and the kind of handle it was called on to the
+//! errors of WinAPI calls.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use super::{Handle, HandleKind};
+
+/// The result type of the functions of this crate.
+///
+/// This is [`io::Result`], errors of failed WinAPI calls carry an [`ApiError`] with the API that
+/// failed and the kind of handle it was called on.
+pub type Result<T> = io::Result<T>;
+
+/// The context of an error returned by a WinAPI call.
+///
+/// It is wrapped in an [`io::Error`] of the same kind as the error of the call, use
+/// [`ApiError::of`] to get it back and [`ApiError::raw_os_error`] to get the error code.
+///
+/// An `io::Error` can't hold both an error code and a payload, so
+/// [`io::Error::raw_os_error`] returns `None` for the errors of this crate. Code that matches
+/// on error codes has to use [`ApiError::raw_os_error`], which also works for errors without
+/// context.
+#[derive(Debug)]
+pub struct ApiError {
+    api: &'static str,
+    handle_kind: Option<HandleKind>,
+    source: io::Error,
+}
+
+impl ApiError {
+    /// Get the context of `error`, if it has one.
+    pub fn of(error: &io::Error) -> Option<&ApiError> {
+        error.get_ref()?.downcast_ref::<ApiError>()
+    }
+
+    /// Get the Windows error code of `error`, looking through its context.
+    ///
+    /// Use this instead of [`io::Error::raw_os_error`] for errors returned by this crate.
+    pub fn raw_os_error(error: &io::Error) -> Option<i32> {
+        match ApiError::of(error) {
+            Some(context) => context.source.raw_os_error(),
+            None => error.raw_os_error(),
+        }
+    }
+
+    /// Get the name of the API that failed, like `SetConsoleMode`.
+    pub fn api(&self) -> &'static str {
+        self.api
+    }
+
+    /// Get the kind of the console handle the API was called on, or `None` if it wasn't called
+    /// on one, like `GetConsoleWindow`.
+    pub fn handle_kind(&self) -> Option<HandleKind> {
+        self.handle_kind
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.handle_kind {
+            Some(kind) => write!(
+                f,
+                "{} failed on a {:?} handle: {}",
+                self.api, kind, self.source
+            ),
+            None => write!(f, "{} failed: {}", self.api, self.source),
+        }
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<ApiError> for io::Error {
+    fn from(error: ApiError) -> Self {
+        io::Error::new(error.source.kind(), error)
+    }
+}
+
+/// Adds the context of a WinAPI call to its errors, see [`ApiError`].
+pub trait ErrorContext<T> {
+    /// Add the name of an API that isn't called on a console handle to the error.
+    ///
+    /// An error that already has a context is returned as it is.
+    fn with_context(self, api: &'static str) -> Result<T>;
+
+    /// Add the name of the API and the kind of the handle it was called on to the error.
+    ///
+    /// An error that already has a context is returned as it is.
+    fn with_api_context(self, api: &'static str, handle_kind: HandleKind) -> Result<T>;
+
+    /// Add the name of the API and the kind of `handle` to the error.
+    ///
+    /// The kind is only determined when there is an error.
+    fn with_handle_context(self, api: &'static str, handle: &Handle) -> Result<T>;
+}
+
+impl<T> ErrorContext<T> for Result<T> {
+    fn with_context(self, api: &'static str) -> Result<T> {
+        self.map_err(|source| add_context(source, api, || None))
+    }
+
+    fn with_api_context(self, api: &'static str, handle_kind: HandleKind) -> Result<T> {
+        self.map_err(|source| add_context(source, api, || Some(handle_kind)))
+    }
+
+    fn with_handle_context(self, api: &'static str, handle: &Handle) -> Result<T> {
+        self.map_err(|source| add_context(source, api, || Some(handle.kind())))
+    }
+}
+
+fn add_context(
+    source: io::Error,
+    api: &'static str,
+    handle_kind: impl FnOnce() -> Option<HandleKind>,
+) -> io::Error {
+    if ApiError::of(&source).is_some() {
+        return source;
+    }
+    ApiError {
+        api,
+        handle_kind: handle_kind(),
+        source,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{ApiError, ErrorContext, Result};
+    use crate::HandleKind;
+
+    #[test]
+    fn test_context() {
+        let result: Result<()> = Err(io::Error::from_raw_os_error(6));
+        let error = result
+            .with_api_context("SetConsoleMode", HandleKind::Pipe)
+            .with_api_context("GetConsoleMode", HandleKind::Console)
+            .unwrap_err();
+        let context = ApiError::of(&error).unwrap();
+        assert_eq!(context.api(), "SetConsoleMode");
+        assert_eq!(context.handle_kind(), Some(HandleKind::Pipe));
+        assert_eq!(error.raw_os_error(), None);
+        assert_eq!(ApiError::raw_os_error(&error), Some(6));
+        assert_eq!(
+            error.kind(),
+            io::Error::from_raw_os_error(6).kind(),
+            "the kind is kept"
+        );
+    }
+
+    #[test]
+    fn test_context_without_handle() {
+        let result: Result<()> = Err(io::Error::from_raw_os_error(5));
+        let error = result.with_context("GetConsoleWindow").unwrap_err();
+        assert_eq!(ApiError::of(&error).unwrap().handle_kind(), None);
+        assert_eq!(ApiError::raw_os_error(&error), Some(5));
+        assert!(error.to_string().starts_with("GetConsoleWindow failed: "));
+    }
+}
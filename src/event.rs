@@ -0,0 +1,379 @@
+//! This module contains a stream of input events that can be extended with events that don't
+//! come from the console input buffer.
+
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::io::{self, Result};
+use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::winerror::WAIT_TIMEOUT;
+use winapi::um::{
+    shellapi::{DragFinish, DragQueryFileW, HDROP},
+    synchapi::WaitForMultipleObjects,
+    winbase::{INFINITE, WAIT_FAILED, WAIT_OBJECT_0},
+};
+
+use super::{
+    alt_numpad::{AltNumpadDecoder, Decoded},
+    key_up::KeyUpTracker,
+    supports_focus_events, trace, Console, Handle, InputRecord, KeyRepeatFilter, KeyUpPolicy,
+    MouseEvent, ReservedEvent, ScreenBuffer, Semaphore, VtKeyEvent,
+};
+
+/// An event read from [`Events`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Event {
+    /// An input record read from the console input buffer.
+    Input(InputRecord),
+    /// The console gained (`true`) or lost (`false`) focus.
+    ///
+    /// This is only reported if the console host supports focus events, see
+    /// [`supports_focus_events`](crate::supports_focus_events). Otherwise focus records are
+    /// reserved records, reported as [`Event::Reserved`] unless disabled with
+    /// [`Events::include_reserved_events`].
+    Focus(bool),
+    /// A record the console host uses internally, unless disabled with
+    /// [`Events::include_reserved_events`].
+    Reserved(ReservedEvent),
+    /// Files were dropped on a window of the application, see
+    /// [`EventSender::send_dropped_files`].
+    ///
+    /// This crate doesn't generate this event for drops on the console window itself, it is only
+    /// sent by applications that receive `WM_DROPFILES` on a window they own.
+    FileDropped(Vec<PathBuf>),
+    /// The mouse was dragged outside the window, reported after the [`Event::Input`] of the
+    /// move if enabled with [`Events::report_drag_outside`].
+    ///
+    /// The position of the event is clamped to the edge of the window, see
+    /// [`MouseEvent::is_clamped`].
+    DragOutside(MouseEvent),
+    /// A hotkey registered with [`GlobalHotkeys`](crate::GlobalHotkeys) was pressed, with the id
+    /// it was registered with.
+    HotkeyPressed(i32),
+}
+
+impl Event {
+    /// Get the logical key of a key down event, see [`VtKeyEvent::from_key_record`].
+    pub fn key(&self) -> Option<VtKeyEvent> {
+        match self {
+            Event::Input(InputRecord::KeyEvent(record)) => VtKeyEvent::from_key_record(record),
+            _ => None,
+        }
+    }
+}
+
+/// A stream of [`Event`]s.
+///
+/// The events are read from the console input buffer, together with the events sent by any
+/// [`EventSender`] of this stream. Events that were sent take precedence over input records.
+#[derive(Debug)]
+pub struct Events {
+    handle: Handle,
+    console: Console,
+    semaphore: Semaphore,
+    sender: Sender<Event>,
+    receiver: Receiver<Event>,
+    focus_events: bool,
+    include_reserved_events: bool,
+    key_up: KeyUpTracker,
+    key_repeats: Option<KeyRepeatFilter>,
+    alt_numpad: Option<AltNumpadDecoder>,
+    pending: VecDeque<Event>,
+    drag_outside: Option<ScreenBuffer>,
+}
+
+impl Events {
+    /// Create a stream of events reading from the given console input handle.
+    pub fn new(handle: Handle) -> Result<Events> {
+        Events::with_console(Console::from(handle))
+    }
+
+    /// Create a stream of events reading from the given console input buffer.
+    ///
+    /// The input filters of `console` are applied to the records, see
+    /// [`Console::add_input_filter`].
+    pub fn with_console(console: Console) -> Result<Events> {
+        let (sender, receiver) = mpsc::channel();
+        Ok(Events {
+            handle: console.handle().clone(),
+            console,
+            semaphore: Semaphore::new()?,
+            sender,
+            receiver,
+            focus_events: supports_focus_events().is_supported(),
+            include_reserved_events: true,
+            key_up: KeyUpTracker::default(),
+            key_repeats: None,
+            alt_numpad: Some(AltNumpadDecoder::default()),
+            pending: VecDeque::new(),
+            drag_outside: None,
+        })
+    }
+
+    /// Set whether menu records, and focus records on hosts that don't report real focus changes,
+    /// are reported as [`Event::Reserved`].
+    ///
+    /// This is enabled by default, like [`ConsoleBuilder::include_reserved_events`]. When
+    /// disabled, the records are dropped. A console built with reserved records disabled never
+    /// passes them to the stream in the first place.
+    ///
+    /// [`ConsoleBuilder::include_reserved_events`]: crate::ConsoleBuilder::include_reserved_events
+    pub fn include_reserved_events(&mut self, include: bool) {
+        self.include_reserved_events = include;
+    }
+
+    /// Set how key up records are reported, see [`KeyUpPolicy`].
+    ///
+    /// The default is [`KeyUpPolicy::Emit`]. Changing the policy forgets which keys are held.
+    pub fn key_up_policy(&mut self, policy: KeyUpPolicy) {
+        if self.key_up.policy() != policy {
+            self.key_up = KeyUpTracker::new(policy);
+        }
+    }
+
+    /// Set whether the auto repeats of a held key are dropped, so each key reports a single key
+    /// down until it is released, see [`KeyRepeatFilter`].
+    ///
+    /// The first key down of a key is reported with a [`repeat_count`] of `1`. This is disabled
+    /// by default.
+    ///
+    /// [`repeat_count`]: crate::KeyEventRecord::repeat_count
+    pub fn suppress_key_repeats(&mut self, suppress: bool) {
+        if suppress != self.key_repeats.is_some() {
+            self.key_repeats = if suppress {
+                Some(KeyRepeatFilter::new())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Set whether characters entered with Alt+numpad are reported as one key down record.
+    ///
+    /// While Alt is held the console reports the digits typed on the numeric keypad as key
+    /// events without a character, and only puts the character in the release of Alt. When
+    /// enabled, which is the default, the digits are held back until Alt is released. If the
+    /// release carries a character, the digits are dropped and the character is reported as a
+    /// key down record right before the release of Alt, which no longer carries it. Otherwise,
+    /// like for Alt+Home with Num Lock off, the digits are reported after all.
+    pub fn decode_alt_numpad(&mut self, decode: bool) {
+        if decode == self.alt_numpad.is_some() {
+            return;
+        }
+        match self.alt_numpad.take() {
+            // Report the digits held back so far.
+            Some(mut alt_numpad) => {
+                for record in alt_numpad.take_digits() {
+                    self.process(record);
+                }
+            }
+            None => self.alt_numpad = Some(AltNumpadDecoder::default()),
+        }
+    }
+
+    /// Report mouse moves that were clamped to the window of `screen_buffer` as
+    /// [`Event::DragOutside`], or stop doing so with `None`.
+    ///
+    /// Each move with a button held queries the window of the screen buffer, so this is disabled
+    /// by default. If the query fails the move is reported as not clamped.
+    pub fn report_drag_outside(&mut self, screen_buffer: Option<ScreenBuffer>) {
+        self.drag_outside = screen_buffer;
+    }
+
+    /// Get a sender to add events to this stream, for example from another thread.
+    pub fn sender(&self) -> EventSender {
+        EventSender {
+            sender: self.sender.clone(),
+            semaphore: self.semaphore.clone(),
+        }
+    }
+
+    /// Read the next event, blocking until one is available.
+    pub fn read(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.poll(None)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the next event, or without a limit if `timeout` is `None`.
+    ///
+    /// Returns `None` if no event was available in time. This can happen before the timeout if
+    /// only a record dropped by the filters of the console, a reserved record or an Alt+numpad
+    /// digit was read.
+    ///
+    /// This wraps
+    /// [`WaitForMultipleObjects`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects).
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<Option<Event>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        if let Ok(event) = self.receiver.try_recv() {
+            return Ok(Some(event));
+        }
+
+        let millis = match timeout {
+            Some(timeout) => timeout.as_millis().min(u128::from(INFINITE - 1)) as u32,
+            None => INFINITE,
+        };
+        let handles = [*self.handle, **self.semaphore.handle()];
+        let wait = trace::call("WaitForMultipleObjects", *self.handle, || unsafe {
+            WaitForMultipleObjects(handles.len() as u32, handles.as_ptr(), FALSE, millis)
+        });
+
+        match wait {
+            WAIT_OBJECT_0 => {
+                // Read only the record that signaled the handle, so a dropped one doesn't
+                // block past the timeout.
+                let record = match self.console.try_read_single_input_event()? {
+                    Some(record) => record,
+                    None => return Ok(None),
+                };
+                let decoded = match self.alt_numpad.as_mut() {
+                    Some(alt_numpad) => alt_numpad.process(record),
+                    None => Decoded::Record(record),
+                };
+                match decoded {
+                    Decoded::Record(record) => self.process(record),
+                    Decoded::Digit => return Ok(None),
+                    Decoded::Replay(records) => {
+                        for record in records {
+                            self.process(record);
+                        }
+                    }
+                    Decoded::Char(character, release) => {
+                        // The character has no key to release, so it bypasses the key up policy.
+                        self.pending
+                            .push_back(Event::Input(InputRecord::KeyEvent(character)));
+                        self.process(release);
+                    }
+                }
+                Ok(self.pending.pop_front())
+            }
+            // The semaphore only wakes the wait, the events are in the channel.
+            w if w == WAIT_OBJECT_0 + 1 => Ok(self.receiver.try_recv().ok()),
+            WAIT_TIMEOUT => Ok(None),
+            WAIT_FAILED => Err(io::Error::last_os_error()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unexpected result from WaitForMultipleObjects: {:#x}", wait),
+            )),
+        }
+    }
+
+    /// Apply the key up policy, the key repeat filter and the event translation to a record read
+    /// from the console, adding the resulting events to the pending ones.
+    fn process(&mut self, mut record: InputRecord) {
+        if !self.key_up.process(&record, &mut self.pending) {
+            return;
+        }
+        if let Some(key_repeats) = self.key_repeats.as_mut() {
+            if !key_repeats.process(&mut record) {
+                return;
+            }
+        }
+        let drag_outside = match (&self.drag_outside, &record) {
+            // The record is already taken from the input buffer, so a failed query reports it as
+            // not clamped rather than losing it.
+            (Some(screen_buffer), InputRecord::MouseEvent(mouse))
+                if screen_buffer
+                    .info()
+                    .map_or(false, |info| mouse.is_clamped(&info)) =>
+            {
+                Some(Event::DragOutside(*mouse))
+            }
+            _ => None,
+        };
+        let event = match record {
+            InputRecord::FocusEvent(focus) if self.focus_events => {
+                Some(Event::Focus(focus.focus_gained()))
+            }
+            record => match record.reserved() {
+                Some(reserved) if self.include_reserved_events => Some(Event::Reserved(reserved)),
+                Some(_) => None,
+                None => Some(Event::Input(record)),
+            },
+        };
+        // Synthesized releases come before the record that caused them.
+        self.pending.extend(event);
+        self.pending.extend(drag_outside);
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.read())
+    }
+}
+
+/// Adds events to an [`Events`] stream.
+///
+/// This is created with [`Events::sender`] and can be sent to other threads.
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    sender: Sender<Event>,
+    semaphore: Semaphore,
+}
+
+impl EventSender {
+    /// Add an event to the stream.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] if the stream was dropped.
+    pub fn send(&self, event: Event) -> Result<()> {
+        self.sender.send(event).map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "the event stream was dropped")
+        })?;
+        self.wake();
+        Ok(())
+    }
+
+    /// Wake up the reader without sending an event.
+    pub(crate) fn wake(&self) {
+        // The semaphore only holds one permit, if it is already released the reader will still
+        // drain the channel.
+        let _ = self.semaphore.release();
+    }
+
+    /// Send an [`Event::FileDropped`] with the paths of a `WM_DROPFILES` message and release the
+    /// drop handle.
+    ///
+    /// The console window is owned by the console host process, so this crate can't register
+    /// it as a drop target itself: a hidden window of this process never receives the drop, and
+    /// hooking the window procedure of another process requires injecting a DLL into it. The
+    /// console host types the paths of files dropped on its window as input instead. An
+    /// application that owns a window, for example one hosting a pseudo console, calls this from
+    /// its window procedure when it receives `WM_DROPFILES`.
+    ///
+    /// This wraps
+    /// [`DragQueryFileW`](https://docs.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-dragqueryfilew).
+    ///
+    /// # Safety
+    ///
+    /// `hdrop` must be the handle of a `WM_DROPFILES` message that wasn't released yet.
+    pub unsafe fn send_dropped_files(&self, hdrop: HDROP) -> Result<()> {
+        let query = |i: u32, buf: *mut u16, len: u32| {
+            trace::call("DragQueryFileW", hdrop.cast(), || {
+                DragQueryFileW(hdrop, i, buf, len)
+            })
+        };
+        let count = query(u32::MAX, std::ptr::null_mut(), 0);
+        let mut paths = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let len = query(i, std::ptr::null_mut(), 0);
+            let mut buf = vec![0u16; len as usize + 1];
+            let len = query(i, buf.as_mut_ptr(), buf.len() as u32);
+            paths.push(PathBuf::from(OsString::from_wide(&buf[..len as usize])));
+        }
+        trace::call("DragFinish", hdrop.cast(), || DragFinish(hdrop));
+
+        self.send(Event::FileDropped(paths))
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use super::{Console, Event, EventSender, Events};
+
+/// Broadcasts the events of an input buffer to any number of receivers.
+///
+/// This is created with [`Console::event_channel`](crate::Console::event_channel). A
+/// background thread reads the [`Events`] and sends a clone of every event to each receiver
+/// returned by [`subscribe`](EventChannel::subscribe), so several parts of an application can
+/// observe the input without taking events from each other.
+///
+/// The thread stops when the channel is dropped, or when reading fails. The receivers are
+/// disconnected in both cases.
+#[derive(Debug)]
+pub struct EventChannel {
+    subscribers: Arc<Mutex<Vec<Sender<Event>>>>,
+    sender: EventSender,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EventChannel {
+    pub(crate) fn new(console: Console) -> Result<EventChannel> {
+        let mut events = Events::with_console(console)?;
+        let subscribers: Arc<Mutex<Vec<Sender<Event>>>> = Arc::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let sender = events.sender();
+
+        let thread = {
+            let subscribers = subscribers.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("crossterm-winapi-events".to_owned())
+                .spawn(move || {
+                    // `poll` returns early when the channel wakes the thread to stop it.
+                    while let Ok(event) = events.poll(None) {
+                        if stop.load(Ordering::Acquire) {
+                            break;
+                        }
+                        if let Some(event) = event {
+                            let mut subscribers =
+                                subscribers.lock().unwrap_or_else(|e| e.into_inner());
+                            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+                        }
+                    }
+                    subscribers
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .clear();
+                })?
+        };
+
+        Ok(EventChannel {
+            subscribers,
+            sender,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Get a receiver for all events read after this call.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = unbounded();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Get a sender to add events to the broadcast, see [`EventSender`].
+    pub fn sender(&self) -> EventSender {
+        self.sender.clone()
+    }
+}
+
+impl Drop for EventChannel {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        self.sender.wake();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, Result};
+use std::mem;
+use std::panic;
+use std::sync::Mutex;
+use std::thread;
+
+use super::{CtrlEvent, CtrlHandler};
+
+type Cleanup = Box<dyn FnOnce() + Send>;
+
+struct Registry {
+    installed: bool,
+    cleanups: Vec<Cleanup>,
+}
+
+/// The cleanups registered with [`on_exit`], in registration order.
+static CLEANUPS: Mutex<Registry> = Mutex::new(Registry {
+    installed: false,
+    cleanups: Vec::new(),
+});
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> i32;
+}
+
+/// Register `cleanup` to run when the process exits, for example to restore the console modes.
+///
+/// The cleanups run once, in reverse registration order, at the first of these:
+///
+/// - `main` returns.
+/// - The main thread panics. The cleanups run before the panic message is printed, so it is
+///   printed to a restored console.
+/// - Ctrl+C, Ctrl+Break or closing the window reach the default handler, which exits the
+///   process. The cleanups run after all [`CtrlHandler`]s, so they don't run when a handler
+///   consumes the signal.
+///
+/// Cleanups don't run when the process is terminated with `TerminateProcess` or
+/// [`std::process::exit`], call [`run_exit_cleanups`] before the latter.
+///
+/// Use this instead of a separate panic hook and control handler per library, so each
+/// restoration runs exactly once.
+pub fn on_exit(cleanup: impl FnOnce() + Send + 'static) -> Result<()> {
+    let mut registry = CLEANUPS.lock().unwrap_or_else(|e| e.into_inner());
+    if !registry.installed {
+        install()?;
+        registry.installed = true;
+    }
+    registry.cleanups.push(Box::new(cleanup));
+    Ok(())
+}
+
+/// Run the cleanups registered with [`on_exit`] now, in reverse registration order.
+///
+/// Cleanups that already ran don't run again.
+pub fn run_exit_cleanups() {
+    let cleanups = mem::take(&mut CLEANUPS.lock().unwrap_or_else(|e| e.into_inner()).cleanups);
+    run(cleanups);
+}
+
+/// Hook into the ways the process exits, see [`on_exit`].
+fn install() -> Result<()> {
+    if unsafe { atexit(at_exit) } != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "the exit callback could not be registered",
+        ));
+    }
+
+    let guard = CtrlHandler::new(|event| {
+        if matches!(
+            event,
+            CtrlEvent::CtrlC | CtrlEvent::CtrlBreak | CtrlEvent::Close
+        ) {
+            run_exit_cleanups();
+        }
+    })
+    .priority(i32::MIN)
+    .register()?;
+    // The handler stays registered for the rest of the process.
+    mem::forget(guard);
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // A panic on another thread doesn't end the process.
+        if thread::current().name() == Some("main") {
+            run_exit_cleanups();
+        }
+        previous(info);
+    }));
+    Ok(())
+}
+
+extern "C" fn at_exit() {
+    run_exit_cleanups();
+}
+
+/// Run `cleanups` in reverse order.
+fn run(cleanups: Vec<Cleanup>) {
+    for cleanup in cleanups.into_iter().rev() {
+        cleanup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{run, Cleanup};
+
+    #[test]
+    fn test_run_in_reverse_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let cleanup = |name: &'static str| -> Cleanup {
+            let calls = calls.clone();
+            Box::new(move || calls.lock().unwrap().push(name))
+        };
+
+        run(vec![cleanup("terminal"), cleanup("cursor")]);
+        assert_eq!(*calls.lock().unwrap(), ["cursor", "terminal"]);
+    }
+}
@@ -1,37 +1,108 @@
 //! This module contains some logic for working with the console handle.
 
-use std::io::Result;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Result, Stderr, Stdin, Stdout};
+use std::mem;
 use std::ops::Deref;
+use std::os::windows::io::{
+    AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, IntoRawHandle, OwnedHandle, RawHandle,
+};
 use std::ptr::null_mut;
 use std::sync::Arc;
 
 use winapi::shared::minwindef::DWORD;
 use winapi::um::{
-    fileapi::{CreateFileW, OPEN_EXISTING},
+    consoleapi::{AllocConsole, GetConsoleMode},
+    fileapi::{CreateFileW, GetFileType, OPEN_EXISTING},
     handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
     processenv::GetStdHandle,
-    winbase::{STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
+    winbase::{
+        FILE_TYPE_CHAR, FILE_TYPE_DISK, FILE_TYPE_PIPE, STD_ERROR_HANDLE, STD_INPUT_HANDLE,
+        STD_OUTPUT_HANDLE,
+    },
     winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE, HANDLE},
 };
 
-use super::handle_result;
+use super::{capability::proc_address, handle_result, result, trace, ErrorContext};
 
 /// The standard handles of a process.
 ///
 /// See [the Windows documentation on console
 /// handles](https://docs.microsoft.com/en-us/windows/console/console-handles) for more info.
 #[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum HandleType {
     /// The process' standard output.
     OutputHandle,
     /// The process' standard input.
     InputHandle,
+    /// The process' standard error.
+    ErrorHandle,
     /// The process' active console screen buffer, `CONOUT$`.
     CurrentOutputHandle,
     /// The process' console input buffer, `CONIN$`.
     CurrentInputHandle,
 }
 
+/// The kind of object a handle refers to.
+///
+/// See [`Handle::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleKind {
+    /// A console input buffer or screen buffer.
+    Console,
+    /// A file on disk, for example when the output is redirected to a file.
+    Disk,
+    /// A pipe, for example when the output is piped into another process.
+    Pipe,
+    /// A character device that isn't a console, like `NUL`.
+    Char,
+    /// The kind of the object could not be determined.
+    Unknown,
+}
+
+/// Whether a [`Handle`] closes the raw handle it wraps when the last clone is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ownership {
+    /// The handle is closed on drop.
+    Owned,
+    /// The handle is left open, it is owned by someone else.
+    Borrowed,
+}
+
+/// The error returned when the process has no standard handle of the requested type.
+///
+/// This happens in processes that are not attached to a console, like GUI applications and
+/// services. It is wrapped in an [`io::Error`] of kind [`io::ErrorKind::NotFound`], use
+/// [`NoStdHandle::is`] to check for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoStdHandle;
+
+impl NoStdHandle {
+    /// Returns whether `error` was caused by a missing standard handle.
+    pub fn is(error: &io::Error) -> bool {
+        match error.get_ref() {
+            Some(inner) => inner.is::<NoStdHandle>(),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for NoStdHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the process has no standard handle, it is probably not attached to a console")
+    }
+}
+
+impl Error for NoStdHandle {}
+
+impl From<NoStdHandle> for io::Error {
+    fn from(error: NoStdHandle) -> Self {
+        io::Error::new(io::ErrorKind::NotFound, error)
+    }
+}
+
 /// Inner structure for closing a handle on Drop.
 ///
 /// The second parameter indicates if the HANDLE is exclusively owned or not.
@@ -46,6 +117,9 @@ struct Inner {
 
 impl Inner {
     fn new_exclusive(handle: HANDLE) -> Self {
+        #[cfg(feature = "debug-handles")]
+        crate::debug_handles::track(handle);
+
         Inner {
             handle,
             is_exclusive: true,
@@ -63,8 +137,13 @@ impl Inner {
 impl Drop for Inner {
     fn drop(&mut self) {
         if self.is_exclusive {
+            #[cfg(feature = "debug-handles")]
+            crate::debug_handles::untrack(self.handle);
+
             assert!(
-                unsafe { CloseHandle(self.handle) != 0 },
+                trace::call("CloseHandle", self.handle, || unsafe {
+                    CloseHandle(self.handle)
+                }) != 0,
                 "failed to close handle"
             )
         }
@@ -78,7 +157,11 @@ unsafe impl Sync for Inner {}
 /// This abstracts away some WinAPI calls to set and get some console handles.
 ///
 /// It wraps WinAPI's [`HANDLE`] type.
-#[derive(Debug, Clone)]
+///
+/// The `Debug` output contains the raw value and the [`Ownership`] of the handle. With the
+/// `handle-names` feature, it also contains what kind of object the handle refers to and the NT
+/// name of the object, where it can be queried.
+#[derive(Clone)]
 pub struct Handle {
     handle: Arc<Inner>,
 }
@@ -89,6 +172,7 @@ impl Handle {
         match handle {
             HandleType::OutputHandle => Handle::output_handle(),
             HandleType::InputHandle => Handle::input_handle(),
+            HandleType::ErrorHandle => Handle::error_handle(),
             HandleType::CurrentOutputHandle => Handle::current_out_handle(),
             HandleType::CurrentInputHandle => Handle::current_in_handle(),
         }
@@ -106,6 +190,35 @@ impl Handle {
         }
     }
 
+    /// Construct a handle from a raw handle, closing it on drop only if it is
+    /// [`Ownership::Owned`].
+    ///
+    /// Use [`Ownership::Borrowed`] for handles that belong to another library, so they are not
+    /// closed twice.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`Handle::from_raw`]. A borrowed handle must stay open as long as this
+    /// `Handle` or any of its clones is in use.
+    pub unsafe fn from_raw_with_ownership(handle: HANDLE, ownership: Ownership) -> Self {
+        let inner = match ownership {
+            Ownership::Owned => Inner::new_exclusive(handle),
+            Ownership::Borrowed => Inner::new_shared(handle),
+        };
+        Self {
+            handle: Arc::new(inner),
+        }
+    }
+
+    /// Get whether the raw handle is closed when the last clone of this handle is dropped.
+    pub fn ownership(&self) -> Ownership {
+        if self.handle.is_exclusive {
+            Ownership::Owned
+        } else {
+            Ownership::Borrowed
+        }
+    }
+
     /// Get the handle of the active screen buffer.
     /// When using multiple screen buffers this will always point to the to the current screen output buffer.
     ///
@@ -117,7 +230,7 @@ impl Handle {
         let utf16: Vec<u16> = "CONOUT$\0".encode_utf16().collect();
         let utf16_ptr: *const u16 = utf16.as_ptr();
 
-        let handle = handle_result(unsafe {
+        let handle = handle_result(trace::call("CreateFileW", null_mut(), || unsafe {
             CreateFileW(
                 utf16_ptr,
                 GENERIC_READ | GENERIC_WRITE,
@@ -127,7 +240,8 @@ impl Handle {
                 0,
                 null_mut(),
             )
-        })?;
+        }))
+        .with_context("CreateFileW")?;
 
         Ok(Handle {
             handle: Arc::new(Inner::new_exclusive(handle)),
@@ -144,7 +258,7 @@ impl Handle {
         let utf16: Vec<u16> = "CONIN$\0".encode_utf16().collect();
         let utf16_ptr: *const u16 = utf16.as_ptr();
 
-        let handle = handle_result(unsafe {
+        let handle = handle_result(trace::call("CreateFileW", null_mut(), || unsafe {
             CreateFileW(
                 utf16_ptr,
                 GENERIC_READ | GENERIC_WRITE,
@@ -154,7 +268,8 @@ impl Handle {
                 0,
                 null_mut(),
             )
-        })?;
+        }))
+        .with_context("CreateFileW")?;
 
         Ok(Handle {
             handle: Arc::new(Inner::new_exclusive(handle)),
@@ -164,6 +279,7 @@ impl Handle {
     /// Get the handle of the standard output.
     ///
     /// On success this function returns the `HANDLE` to `STD_OUTPUT_HANDLE`.
+    /// If the process has no standard output this fails with [`NoStdHandle`].
     ///
     /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
     /// called with `STD_OUTPUT_HANDLE`.
@@ -171,9 +287,30 @@ impl Handle {
         Self::std_handle(STD_OUTPUT_HANDLE)
     }
 
+    /// Get the handle of the standard output, allocating a console if the process has none.
+    ///
+    /// This is useful for GUI applications and services, which are not attached to a console
+    /// by default.
+    ///
+    /// This wraps [`AllocConsole`](https://docs.microsoft.com/en-us/windows/console/allocconsole)
+    /// when [`Handle::output_handle`] fails with [`NoStdHandle`].
+    pub fn output_or_alloc() -> Result<Handle> {
+        match Self::output_handle() {
+            Err(e) if NoStdHandle::is(&e) => {
+                result(trace::call("AllocConsole", null_mut(), || unsafe {
+                    AllocConsole()
+                }))
+                .with_context("AllocConsole")?;
+                Self::output_handle()
+            }
+            handle => handle,
+        }
+    }
+
     /// Get the handle of the input screen buffer.
     ///
     /// On success this function returns the `HANDLE` to `STD_INPUT_HANDLE`.
+    /// If the process has no standard input this fails with [`NoStdHandle`].
     ///
     /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
     /// called with `STD_INPUT_HANDLE`.
@@ -181,14 +318,98 @@ impl Handle {
         Self::std_handle(STD_INPUT_HANDLE)
     }
 
-    fn std_handle(which_std: DWORD) -> Result<Handle> {
-        let handle = handle_result(unsafe { GetStdHandle(which_std) })?;
+    /// Get the handle of the standard error.
+    ///
+    /// This is useful for interfaces like progress bars that must not mix with the standard
+    /// output. If the process has no standard error this fails with [`NoStdHandle`].
+    ///
+    /// This wraps [`GetStdHandle`](https://docs.microsoft.com/en-us/windows/console/getstdhandle)
+    /// called with `STD_ERROR_HANDLE`.
+    pub fn error_handle() -> Result<Handle> {
+        Self::std_handle(STD_ERROR_HANDLE)
+    }
+
+    /// Get the handle of the console screen buffer the process draws on, even if some of its
+    /// standard handles are redirected.
+    ///
+    /// This is the standard output if it is a console, otherwise the standard error if it is a
+    /// console, like for a picker that renders on the standard error while its output is piped,
+    /// and otherwise the active screen buffer, `CONOUT$`.
+    pub fn console_output_handle() -> Result<Handle> {
+        let stdout = Handle::output_handle();
+        let stderr = Handle::error_handle();
+        let kind = |handle: &Result<Handle>| handle.as_ref().ok().map(Handle::kind);
+        match console_output_type(kind(&stdout), kind(&stderr)) {
+            HandleType::OutputHandle => stdout,
+            HandleType::ErrorHandle => stderr,
+            handle_type => Handle::new(handle_type),
+        }
+    }
+
+    pub(crate) fn std_handle(which_std: DWORD) -> Result<Handle> {
+        let handle = handle_result(trace::call("GetStdHandle", null_mut(), || unsafe {
+            GetStdHandle(which_std)
+        }))
+        .with_context("GetStdHandle")?;
+        if handle.is_null() {
+            return Err(NoStdHandle.into());
+        }
 
         Ok(Handle {
             handle: Arc::new(Inner::new_shared(handle)),
         })
     }
 
+    /// Get the kind of object this handle refers to.
+    ///
+    /// A handle is reported as [`HandleKind::Console`] if
+    /// [`GetConsoleMode`](https://docs.microsoft.com/en-us/windows/console/getconsolemode)
+    /// succeeds on it, otherwise this wraps
+    /// [`GetFileType`](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfiletype).
+    pub fn kind(&self) -> HandleKind {
+        let mut mode = 0;
+        if trace::call("GetConsoleMode", **self, || unsafe {
+            GetConsoleMode(**self, &mut mode)
+        }) != 0
+        {
+            return HandleKind::Console;
+        }
+
+        match trace::call("GetFileType", **self, || unsafe { GetFileType(**self) }) {
+            FILE_TYPE_DISK => HandleKind::Disk,
+            FILE_TYPE_PIPE => HandleKind::Pipe,
+            FILE_TYPE_CHAR => HandleKind::Char,
+            _ => HandleKind::Unknown,
+        }
+    }
+
+    /// Returns whether this handle and `other` refer to the same kernel object, for example when
+    /// the standard output and the standard error are both the console screen buffer.
+    ///
+    /// Different handles can refer to the same object, as handles duplicated with
+    /// `DuplicateHandle` or opened separately do. This is also what `==` compares. There is no
+    /// `Hash` implementation, as the identity of the object can't be turned into a hash value.
+    ///
+    /// This wraps
+    /// [`CompareObjectHandles`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-compareobjecthandles),
+    /// which is looked up in `kernelbase.dll` at runtime. It is available since Windows 10, on
+    /// older versions only handles with the same raw value are the same.
+    pub fn same_object(&self, other: &Handle) -> bool {
+        type CompareObjectHandles = unsafe extern "system" fn(HANDLE, HANDLE) -> i32;
+
+        if **self == **other {
+            return true;
+        }
+        let compare = match proc_address("kernelbase.dll\0", "CompareObjectHandles\0") {
+            Some(compare) => compare,
+            None => return false,
+        };
+        let compare: CompareObjectHandles = unsafe { mem::transmute(compare) };
+        trace::call("CompareObjectHandles", **self, || unsafe {
+            compare(**self, **other)
+        }) != 0
+    }
+
     /// Checks if the console handle is an invalid handle value.
     ///
     /// This is done by checking if the passed `HANDLE` is equal to `INVALID_HANDLE_VALUE`.
@@ -197,6 +418,144 @@ impl Handle {
     }
 }
 
+/// Pick the standard handle that is a console, falling back to `CONOUT$`.
+fn console_output_type(stdout: Option<HandleKind>, stderr: Option<HandleKind>) -> HandleType {
+    match (stdout, stderr) {
+        (Some(HandleKind::Console), _) => HandleType::OutputHandle,
+        (_, Some(HandleKind::Console)) => HandleType::ErrorHandle,
+        _ => HandleType::CurrentOutputHandle,
+    }
+}
+
+impl PartialEq for Handle {
+    /// Compares the kernel objects the handles refer to, see [`Handle::same_object`].
+    fn eq(&self, other: &Handle) -> bool {
+        self.same_object(other)
+    }
+}
+
+impl Eq for Handle {}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Handle");
+        debug
+            .field("raw", &format_args!("{:#x}", **self as usize))
+            .field("ownership", &self.ownership());
+        #[cfg(feature = "handle-names")]
+        {
+            let kind = self.kind();
+            debug.field("kind", &names::describe_kind(self, kind));
+            // Querying the name of a synchronous pipe blocks while a read is pending on it.
+            if kind != HandleKind::Pipe {
+                if let Some(name) = names::object_name(self) {
+                    debug.field("name", &name);
+                }
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl Handle {
+    /// Borrow a handle of the standard library, failing with [`NoStdHandle`] if it is null.
+    fn borrow_std(handle: BorrowedHandle<'_>) -> Result<Handle> {
+        let handle: HANDLE = handle.as_raw_handle().cast();
+        if handle.is_null() {
+            return Err(NoStdHandle.into());
+        }
+        Ok(Handle {
+            handle: Arc::new(Inner::new_shared(handle)),
+        })
+    }
+}
+
+impl TryFrom<&Stdin> for Handle {
+    type Error = io::Error;
+
+    /// Borrow the handle of the standard input, like [`Handle::input_handle`].
+    fn try_from(stdin: &Stdin) -> Result<Handle> {
+        Handle::borrow_std(stdin.as_handle())
+    }
+}
+
+impl TryFrom<&Stdout> for Handle {
+    type Error = io::Error;
+
+    /// Borrow the handle of the standard output, like [`Handle::output_handle`].
+    fn try_from(stdout: &Stdout) -> Result<Handle> {
+        Handle::borrow_std(stdout.as_handle())
+    }
+}
+
+impl TryFrom<&Stderr> for Handle {
+    type Error = io::Error;
+
+    /// Borrow the handle of the standard error, like [`Handle::error_handle`].
+    fn try_from(stderr: &Stderr) -> Result<Handle> {
+        Handle::borrow_std(stderr.as_handle())
+    }
+}
+
+impl From<OwnedHandle> for Handle {
+    /// Take ownership of `handle`, it is closed when the last clone of the `Handle` is dropped.
+    fn from(handle: OwnedHandle) -> Self {
+        Handle {
+            handle: Arc::new(Inner::new_exclusive(handle.into_raw_handle().cast())),
+        }
+    }
+}
+
+impl TryFrom<Handle> for OwnedHandle {
+    type Error = io::Error;
+
+    /// Convert `handle` into an [`OwnedHandle`].
+    ///
+    /// If this is the last clone of an [`Ownership::Owned`] handle, the raw handle is moved
+    /// without a system call. Otherwise it is still in use elsewhere, so it is duplicated, which
+    /// is the only way this can fail.
+    fn try_from(handle: Handle) -> Result<OwnedHandle> {
+        match Arc::try_unwrap(handle.handle) {
+            Ok(mut inner) if inner.is_exclusive => {
+                #[cfg(feature = "debug-handles")]
+                crate::debug_handles::untrack(inner.handle);
+
+                // The `OwnedHandle` closes it from now on.
+                inner.is_exclusive = false;
+                Ok(unsafe { OwnedHandle::from_raw_handle(inner.handle.cast()) })
+            }
+            Ok(inner) => {
+                unsafe { BorrowedHandle::borrow_raw(inner.handle.cast()) }.try_clone_to_owned()
+            }
+            Err(shared) => {
+                unsafe { BorrowedHandle::borrow_raw(shared.handle.cast()) }.try_clone_to_owned()
+            }
+        }
+    }
+}
+
+impl AsRawHandle for Handle {
+    /// Get the raw handle, which stays owned by this `Handle`.
+    fn as_raw_handle(&self) -> RawHandle {
+        (**self).cast()
+    }
+}
+
+impl AsHandle for Handle {
+    /// Borrow the raw handle for as long as this `Handle` lives.
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        unsafe { BorrowedHandle::borrow_raw(self.as_raw_handle()) }
+    }
+}
+
+impl FromRawHandle for Handle {
+    /// Take ownership of `handle` like [`Handle::from_raw`], it is closed when the last clone of
+    /// the `Handle` is dropped.
+    unsafe fn from_raw_handle(handle: RawHandle) -> Self {
+        Handle::from_raw(handle.cast())
+    }
+}
+
 impl Deref for Handle {
     type Target = HANDLE;
 
@@ -205,15 +564,133 @@ impl Deref for Handle {
     }
 }
 
+#[cfg(feature = "handle-names")]
+mod names {
+    use std::mem;
+
+    use winapi::shared::ntdef::UNICODE_STRING;
+    use winapi::um::wincon::{GetConsoleScreenBufferInfo, CONSOLE_SCREEN_BUFFER_INFO};
+    use winapi::um::winnt::HANDLE;
+
+    use super::{Handle, HandleKind};
+    use crate::{capability::proc_address, trace};
+
+    /// `ObjectNameInformation` of the `OBJECT_INFORMATION_CLASS` enum.
+    const OBJECT_NAME_INFORMATION: i32 = 1;
+
+    /// Describe the kind of object `handle` refers to, telling console input and screen buffers
+    /// apart.
+    pub(super) fn describe_kind(handle: &Handle, kind: HandleKind) -> &'static str {
+        match kind {
+            HandleKind::Console => {
+                let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { mem::zeroed() };
+                if trace::call("GetConsoleScreenBufferInfo", **handle, || unsafe {
+                    GetConsoleScreenBufferInfo(**handle, &mut info)
+                }) != 0
+                {
+                    "console screen buffer"
+                } else {
+                    "console input"
+                }
+            }
+            HandleKind::Disk => "file",
+            HandleKind::Pipe => "pipe",
+            HandleKind::Char => "character device",
+            HandleKind::Unknown => "unknown",
+        }
+    }
+
+    /// Get the NT name of the object `handle` refers to, like `\Device\ConDrv`.
+    ///
+    /// This calls `NtQueryObject`, which is looked up in `ntdll.dll` at runtime.
+    pub(super) fn object_name(handle: &Handle) -> Option<String> {
+        type NtQueryObject = unsafe extern "system" fn(HANDLE, i32, *mut u8, u32, *mut u32) -> i32;
+
+        let nt_query_object = proc_address("ntdll.dll\0", "NtQueryObject\0")?;
+        let nt_query_object: NtQueryObject = unsafe { mem::transmute(nt_query_object) };
+
+        // `u64`s to align the `UNICODE_STRING` at the start of the buffer.
+        let mut buf = [0u64; 256];
+        let mut len = 0;
+        let status = unsafe {
+            nt_query_object(
+                **handle,
+                OBJECT_NAME_INFORMATION,
+                buf.as_mut_ptr().cast(),
+                mem::size_of_val(&buf) as u32,
+                &mut len,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+
+        let name = unsafe { &*buf.as_ptr().cast::<UNICODE_STRING>() };
+        if name.Buffer.is_null() || name.Length == 0 {
+            return None;
+        }
+        let units =
+            unsafe { std::slice::from_raw_parts(name.Buffer, usize::from(name.Length) / 2) };
+        Some(String::from_utf16_lossy(units))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Handle, HandleType};
+    use std::os::windows::io::{AsRawHandle, OwnedHandle};
+
+    use super::{console_output_type, Handle, HandleKind, HandleType, Ownership};
 
     #[test]
     fn test_get_handle() {
         assert!(Handle::new(HandleType::OutputHandle).is_ok());
         assert!(Handle::new(HandleType::InputHandle).is_ok());
+        assert!(Handle::new(HandleType::ErrorHandle).is_ok());
         assert!(Handle::new(HandleType::CurrentOutputHandle).is_ok());
         assert!(Handle::new(HandleType::CurrentInputHandle).is_ok());
     }
+
+    #[test]
+    fn test_console_output_type() {
+        let console = Some(HandleKind::Console);
+        let pipe = Some(HandleKind::Pipe);
+        assert!(matches!(
+            console_output_type(console, pipe),
+            HandleType::OutputHandle
+        ));
+        // Output piped, drawing on the standard error.
+        assert!(matches!(
+            console_output_type(pipe, console),
+            HandleType::ErrorHandle
+        ));
+        assert!(matches!(
+            console_output_type(pipe, Some(HandleKind::Disk)),
+            HandleType::CurrentOutputHandle
+        ));
+        assert!(matches!(
+            console_output_type(None, None),
+            HandleType::CurrentOutputHandle
+        ));
+    }
+
+    #[test]
+    fn test_same_object() {
+        let handle = Handle::current_out_handle().unwrap();
+        assert_eq!(handle, handle.clone());
+        let owned = OwnedHandle::try_from(handle.clone()).unwrap();
+        assert_eq!(handle, Handle::from(owned));
+        assert_ne!(handle, Handle::current_in_handle().unwrap());
+    }
+
+    #[test]
+    fn test_owned_handle_round_trip() {
+        let handle = Handle::try_from(&std::io::stdout()).unwrap();
+        let owned = OwnedHandle::try_from(handle.clone()).unwrap();
+        assert_ne!(owned.as_raw_handle() as usize, *handle as usize);
+
+        let raw = owned.as_raw_handle();
+        let handle = Handle::from(owned);
+        assert_eq!(handle.ownership(), Ownership::Owned);
+        assert_eq!(OwnedHandle::try_from(handle).unwrap().as_raw_handle(), raw);
+    }
 }
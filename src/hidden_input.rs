@@ -0,0 +1,202 @@
+//! This module contains the line reading without echo used by
+//! [`Console::read_line_hidden`](crate::Console::read_line_hidden).
+
+use std::fmt;
+use std::io::{self, Result};
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::sync::Mutex;
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::um::{
+    consoleapi::{GetConsoleMode, ReadConsoleW, SetConsoleCtrlHandler, SetConsoleMode},
+    wincon::{ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT},
+    winnt::HANDLE,
+};
+
+use super::{result, trace, ErrorContext, Handle};
+
+/// The input handle and the mode to restore if the process is interrupted during a hidden read.
+///
+/// The raw handle is stored as `usize`, as `HANDLE` is not `Send`.
+static INTERRUPTED_RESTORE: Mutex<Option<(usize, u32)>> = Mutex::new(None);
+
+/// A string that is overwritten with zeros when dropped, see
+/// [`Console::read_line_hidden`](crate::Console::read_line_hidden).
+///
+/// The `Debug` output doesn't contain the string.
+pub struct SecretString(String);
+
+impl SecretString {
+    /// Get the string.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(..)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        zero(unsafe { self.0.as_mut_vec() });
+    }
+}
+
+/// Overwrite `buf` with zeros in a way the compiler can't optimize away.
+fn zero<T: Copy + Default>(buf: &mut [T]) {
+    for item in buf.iter_mut() {
+        unsafe { ptr::write_volatile(item, T::default()) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Turns off echo on an input handle and restores the mode on drop, or from the console control
+/// handler if the process is interrupted.
+struct EchoGuard {
+    handle: Handle,
+    original: u32,
+}
+
+impl EchoGuard {
+    fn new(handle: Handle) -> Result<EchoGuard> {
+        let mut original = 0;
+        result(trace::call("GetConsoleMode", *handle, || unsafe {
+            GetConsoleMode(*handle, &mut original)
+        }))
+        .with_handle_context("GetConsoleMode", &handle)?;
+
+        result(trace::call(
+            "SetConsoleCtrlHandler",
+            ptr::null_mut(),
+            || unsafe { SetConsoleCtrlHandler(Some(restore_on_interrupt), TRUE) },
+        ))
+        .with_context("SetConsoleCtrlHandler")?;
+        *INTERRUPTED_RESTORE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some((*handle as usize, original));
+        // From here on dropping the guard clears the restore state and removes the handler, also
+        // when setting the mode fails.
+        let guard = EchoGuard { handle, original };
+
+        let hidden = (original & !ENABLE_ECHO_INPUT) | ENABLE_LINE_INPUT | ENABLE_PROCESSED_INPUT;
+        result(trace::call("SetConsoleMode", *guard.handle, || unsafe {
+            SetConsoleMode(*guard.handle, hidden)
+        }))
+        .with_handle_context("SetConsoleMode", &guard.handle)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        trace::call("SetConsoleMode", *self.handle, || unsafe {
+            SetConsoleMode(*self.handle, self.original)
+        });
+        *INTERRUPTED_RESTORE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+        trace::call("SetConsoleCtrlHandler", ptr::null_mut(), || unsafe {
+            SetConsoleCtrlHandler(Some(restore_on_interrupt), FALSE)
+        });
+    }
+}
+
+/// Restore the mode when Ctrl+C or Ctrl+Break is pressed during a hidden read, then let the next
+/// handler, usually the default one that exits the process, handle the event.
+unsafe extern "system" fn restore_on_interrupt(_ctrl_type: DWORD) -> BOOL {
+    let restore = INTERRUPTED_RESTORE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+    if let Some((handle, mode)) = restore {
+        trace::call("SetConsoleMode", handle as HANDLE, || {
+            SetConsoleMode(handle as HANDLE, mode)
+        });
+    }
+    FALSE
+}
+
+/// Read a line from `handle` with echo turned off, without the line break.
+pub(crate) fn read_line_hidden(handle: &Handle) -> Result<SecretString> {
+    let _guard = EchoGuard::new(handle.clone())?;
+
+    let mut units: Vec<u16> = Vec::with_capacity(256);
+    let mut chunk = [0u16; 256];
+    let line = loop {
+        let mut read = 0;
+        let read_result = result(trace::call("ReadConsoleW", **handle, || unsafe {
+            ReadConsoleW(
+                **handle,
+                chunk.as_mut_ptr().cast(),
+                chunk.len() as u32,
+                &mut read,
+                ptr::null_mut(),
+            )
+        }))
+        .with_handle_context("ReadConsoleW", handle);
+        if let Err(e) = read_result {
+            break Err(e);
+        }
+        if read == 0 {
+            break Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the read was interrupted",
+            ));
+        }
+
+        let read = &chunk[..read as usize];
+        if units.len() + read.len() > units.capacity() {
+            // Grow by hand, so the old allocation is zeroed instead of freed with the secret.
+            let mut grown = Vec::with_capacity((units.len() + read.len()) * 2);
+            grown.extend_from_slice(&units);
+            zero(&mut units);
+            units = grown;
+        }
+        units.extend_from_slice(read);
+        if read.last() == Some(&u16::from(b'\n')) {
+            break Ok(decode_line(&units));
+        }
+    };
+
+    zero(&mut chunk);
+    zero(&mut units);
+    line
+}
+
+/// Decode the UTF-16 `line` without its line break.
+fn decode_line(line: &[u16]) -> SecretString {
+    let mut end = line.len();
+    while end > 0 && (line[end - 1] == u16::from(b'\n') || line[end - 1] == u16::from(b'\r')) {
+        end -= 1;
+    }
+    let line = &line[..end];
+
+    let mut decoded = String::with_capacity(line.len() * 3);
+    decoded.extend(
+        char::decode_utf16(line.iter().copied()).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+    );
+    SecretString(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_line, zero};
+
+    #[test]
+    fn test_decode_line() {
+        let line: Vec<u16> = "hunter2\r\n".encode_utf16().collect();
+        assert_eq!(decode_line(&line).expose(), "hunter2");
+        assert_eq!(format!("{:?}", decode_line(&line)), "SecretString(..)");
+    }
+
+    #[test]
+    fn test_zero() {
+        let mut buf = [1u16, 2, 3];
+        zero(&mut buf);
+        assert_eq!(buf, [0, 0, 0]);
+    }
+}
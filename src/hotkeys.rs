@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::io::{self, Result};
+use std::mem::zeroed;
+use std::ptr::null_mut;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use winapi::um::{
+    processthreadsapi::GetCurrentThreadId,
+    winuser::{
+        GetMessageW, PeekMessageW, PostThreadMessageW, RegisterHotKey, UnregisterHotKey,
+        PM_NOREMOVE, WM_APP, WM_HOTKEY, WM_QUIT,
+    },
+};
+
+use super::{result, trace, ErrorContext, Event, EventSender};
+
+/// Posted to the message pump thread when a request is queued.
+const WM_HOTKEY_REQUEST: u32 = WM_APP + 1;
+
+enum Request {
+    Register {
+        id: i32,
+        modifiers: u32,
+        virtual_key: u32,
+        reply: Sender<Result<()>>,
+    },
+    Unregister {
+        id: i32,
+        reply: Sender<Result<()>>,
+    },
+}
+
+/// System wide hotkeys, reported as [`Event::HotkeyPressed`].
+///
+/// Windows delivers hotkeys as window messages, which console applications don't receive. This
+/// runs a message pump on a background thread that owns the hotkeys and forwards them to an
+/// [`Events`](crate::Events) stream. The hotkeys are unregistered when this is dropped.
+///
+/// ```no_run
+/// # use crossterm_winapi::{Event, Events, GlobalHotkeys, Handle};
+/// # use winapi::um::winuser::{MOD_CONTROL, MOD_NOREPEAT, VK_F12};
+/// # fn main() -> std::io::Result<()> {
+/// let mut events = Events::new(Handle::current_in_handle()?)?;
+/// let hotkeys = GlobalHotkeys::new(events.sender())?;
+/// hotkeys.register(1, (MOD_CONTROL | MOD_NOREPEAT) as u32, VK_F12 as u32)?;
+/// for event in &mut events {
+///     if let Event::HotkeyPressed(1) = event? {
+///         // bring the console to the front...
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GlobalHotkeys {
+    thread_id: u32,
+    requests: Sender<Request>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl GlobalHotkeys {
+    /// Start the message pump thread, which sends hotkey events to `sender`.
+    pub fn new(sender: EventSender) -> Result<GlobalHotkeys> {
+        let (requests, request_receiver) = mpsc::channel();
+        let (thread_id_sender, thread_id_receiver) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("crossterm-winapi-hotkeys".to_owned())
+            .spawn(move || message_pump(sender, request_receiver, thread_id_sender))?;
+        let thread_id = thread_id_receiver
+            .recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "the hotkey thread exited early"))?;
+
+        Ok(GlobalHotkeys {
+            thread_id,
+            requests,
+            thread: Some(thread),
+        })
+    }
+
+    /// Register a hotkey with the given id.
+    ///
+    /// `modifiers` is a combination of the `MOD_*` flags and `virtual_key` a virtual-key code.
+    /// This fails if another application already registered the same combination.
+    ///
+    /// This wraps
+    /// [`RegisterHotKey`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerhotkey).
+    pub fn register(&self, id: i32, modifiers: u32, virtual_key: u32) -> Result<()> {
+        self.request(|reply| Request::Register {
+            id,
+            modifiers,
+            virtual_key,
+            reply,
+        })
+    }
+
+    /// Unregister the hotkey with the given id.
+    ///
+    /// This wraps
+    /// [`UnregisterHotKey`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-unregisterhotkey).
+    pub fn unregister(&self, id: i32) -> Result<()> {
+        self.request(|reply| Request::Unregister { id, reply })
+    }
+
+    /// Hotkeys belong to the thread that registers them, so the requests are run on the message
+    /// pump thread.
+    fn request(&self, request: impl FnOnce(Sender<Result<()>>) -> Request) -> Result<()> {
+        let stopped = || io::Error::new(io::ErrorKind::BrokenPipe, "the hotkey thread stopped");
+        let (reply, reply_receiver) = mpsc::channel();
+        self.requests.send(request(reply)).map_err(|_| stopped())?;
+        result(trace::call("PostThreadMessageW", null_mut(), || unsafe {
+            PostThreadMessageW(self.thread_id, WM_HOTKEY_REQUEST, 0, 0)
+        }))
+        .with_context("PostThreadMessageW")?;
+        reply_receiver.recv().map_err(|_| stopped())?
+    }
+}
+
+impl Drop for GlobalHotkeys {
+    fn drop(&mut self) {
+        trace::call("PostThreadMessageW", null_mut(), || unsafe {
+            PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0)
+        });
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn message_pump(sender: EventSender, requests: Receiver<Request>, thread_id: Sender<u32>) {
+    // The message queue of a thread is created by its first call to a message function, it has
+    // to exist before the thread id is handed out.
+    let mut msg = unsafe { zeroed() };
+    trace::call("PeekMessageW", null_mut(), || unsafe {
+        PeekMessageW(&mut msg, null_mut(), 0, 0, PM_NOREMOVE)
+    });
+    let current = trace::call("GetCurrentThreadId", null_mut(), || unsafe {
+        GetCurrentThreadId()
+    });
+    if thread_id.send(current).is_err() {
+        return;
+    }
+
+    let mut registered = HashSet::new();
+    while trace::call("GetMessageW", null_mut(), || unsafe {
+        GetMessageW(&mut msg, null_mut(), 0, 0)
+    }) > 0
+    {
+        match msg.message {
+            WM_HOTKEY => {
+                let _ = sender.send(Event::HotkeyPressed(msg.wParam as i32));
+            }
+            WM_HOTKEY_REQUEST => {
+                for request in requests.try_iter() {
+                    handle_request(request, &mut registered);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for id in registered {
+        trace::call("UnregisterHotKey", null_mut(), || unsafe {
+            UnregisterHotKey(null_mut(), id)
+        });
+    }
+}
+
+fn handle_request(request: Request, registered: &mut HashSet<i32>) {
+    match request {
+        Request::Register {
+            id,
+            modifiers,
+            virtual_key,
+            reply,
+        } => {
+            let res = result(trace::call("RegisterHotKey", null_mut(), || unsafe {
+                RegisterHotKey(null_mut(), id, modifiers, virtual_key)
+            }))
+            .with_context("RegisterHotKey");
+            if res.is_ok() {
+                registered.insert(id);
+            }
+            let _ = reply.send(res);
+        }
+        Request::Unregister { id, reply } => {
+            let res = result(trace::call("UnregisterHotKey", null_mut(), || unsafe {
+                UnregisterHotKey(null_mut(), id)
+            }))
+            .with_context("UnregisterHotKey");
+            registered.remove(&id);
+            let _ = reply.send(res);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::Result;
+use std::sync::Mutex;
+
+use winapi::um::wincon::{
+    ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+    ENABLE_PROCESSED_INPUT, ENABLE_QUICK_EDIT_MODE, ENABLE_VIRTUAL_TERMINAL_INPUT,
+    ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+};
+
+use super::{screen_buffer::capture_default_attributes, ConsoleMode, Handle, Overrides};
+
+/// The console features a library needs, see [`init`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InitOptions {
+    /// Enable virtual terminal processing on the screen buffer.
+    pub vt_processing: bool,
+    /// Enable virtual terminal input on the input buffer.
+    pub vt_input: bool,
+    /// Enable mouse input, this disables quick edit mode.
+    pub mouse: bool,
+    /// Disable line input, echo and Ctrl+C processing.
+    pub raw: bool,
+}
+
+/// The flags set and cleared on top of the original mode of a buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ModeChanges {
+    set: u32,
+    clear: u32,
+}
+
+impl ModeChanges {
+    fn apply(&self, original: u32) -> u32 {
+        (original & !self.clear) | self.set
+    }
+}
+
+#[derive(Debug)]
+struct Registry {
+    count: usize,
+    original_input: u32,
+    original_output: u32,
+    input: ModeChanges,
+    output: ModeChanges,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+/// Set up the console for a library, sharing the changes with other libraries in the process.
+///
+/// Every call adds the features in `options` to the changes of earlier calls, so libraries that
+/// need different features don't undo each other's modes. The modes are restored when
+/// [`shutdown`] was called as many times as `init`.
+pub fn init(options: InitOptions) -> Result<()> {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    let input = ConsoleMode::from(Handle::current_in_handle()?);
+    let output_handle = Handle::current_out_handle()?;
+    capture_default_attributes(&output_handle);
+    let output = ConsoleMode::from(output_handle);
+
+    let mut state = match registry.take() {
+        Some(state) => state,
+        None => Registry {
+            count: 0,
+            original_input: input.mode()?,
+            original_output: output.mode()?,
+            input: ModeChanges::default(),
+            output: ModeChanges::default(),
+        },
+    };
+
+    let (input_changes, output_changes) = changes(options, Overrides::current());
+    let mut next_input = state.input;
+    next_input.set |= input_changes.set;
+    next_input.clear |= input_changes.clear;
+    let mut next_output = state.output;
+    next_output.set |= output_changes.set;
+    next_output.clear |= output_changes.clear;
+
+    let result = output
+        .set_mode(next_output.apply(state.original_output))
+        .and_then(|_| input.set_mode(next_input.apply(state.original_input)));
+
+    if result.is_ok() {
+        state.count += 1;
+        state.input = next_input;
+        state.output = next_output;
+    } else {
+        // Go back to the modes of the previous calls.
+        let _ = output.set_mode(state.output.apply(state.original_output));
+        let _ = input.set_mode(state.input.apply(state.original_input));
+    }
+
+    if state.count > 0 {
+        *registry = Some(state);
+    }
+    result
+}
+
+/// Release one call to [`init`].
+///
+/// The last call restores the console modes from before the first `init`. Calling this more
+/// often than `init` does nothing.
+pub fn shutdown() -> Result<()> {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    let state = match registry.as_mut() {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+
+    state.count -= 1;
+    if state.count > 0 {
+        return Ok(());
+    }
+
+    let state = registry.take().expect("registry was checked above");
+    let output = ConsoleMode::from(Handle::current_out_handle()?).set_mode(state.original_output);
+    let input = ConsoleMode::from(Handle::current_in_handle()?).set_mode(state.original_input);
+    output.and(input)
+}
+
+/// Get the input and output mode changes needed for `options`, leaving out the flags forbidden
+/// by `overrides`.
+fn changes(options: InitOptions, overrides: Overrides) -> (ModeChanges, ModeChanges) {
+    let mut input = ModeChanges::default();
+    let mut output = ModeChanges::default();
+
+    if options.vt_processing && !overrides.force_legacy {
+        output.set |= ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+    }
+    if options.vt_input && !overrides.force_legacy {
+        input.set |= ENABLE_VIRTUAL_TERMINAL_INPUT;
+    }
+    if options.mouse && !overrides.disable_mouse {
+        input.set |= ENABLE_MOUSE_INPUT | ENABLE_EXTENDED_FLAGS;
+        input.clear |= ENABLE_QUICK_EDIT_MODE;
+    }
+    if options.raw {
+        input.clear |= ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT;
+    }
+
+    (input, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{
+        ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+        ENABLE_PROCESSED_INPUT, ENABLE_QUICK_EDIT_MODE,
+    };
+
+    use super::{changes, InitOptions};
+    use crate::Overrides;
+
+    #[test]
+    fn test_changes() {
+        let options = InitOptions {
+            mouse: true,
+            raw: true,
+            ..InitOptions::default()
+        };
+        let (input, output) = changes(options, Overrides::default());
+        let original = ENABLE_PROCESSED_INPUT
+            | ENABLE_LINE_INPUT
+            | ENABLE_ECHO_INPUT
+            | ENABLE_QUICK_EDIT_MODE
+            | ENABLE_EXTENDED_FLAGS;
+
+        assert_eq!(
+            input.apply(original),
+            ENABLE_MOUSE_INPUT | ENABLE_EXTENDED_FLAGS
+        );
+        assert_eq!(output.apply(7), 7);
+
+        let overrides = Overrides {
+            disable_mouse: true,
+            ..Overrides::default()
+        };
+        let (input, _) = changes(options, overrides);
+        assert_eq!(
+            input.apply(original),
+            ENABLE_QUICK_EDIT_MODE | ENABLE_EXTENDED_FLAGS
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use winapi::um::wincon::{KEY_EVENT, WINDOW_BUFFER_SIZE_EVENT};
+
+use super::InputRecord;
+
+/// Which kinds of records the reading functions of a [`Console`](crate::Console) return, see
+/// [`ConsoleBuilder::event_filter`](crate::ConsoleBuilder::event_filter).
+///
+/// Records are dropped by their raw event type before they are translated or run through the
+/// input filters, which keeps floods of mouse move records cheap for applications that don't use
+/// them. To keep the console from generating mouse records at all, turn off
+/// `ENABLE_MOUSE_INPUT` in the input mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EventFilter {
+    /// Return every record.
+    #[default]
+    All,
+    /// Return key and resize records.
+    KeysAndResize,
+    /// Return key records only.
+    KeysOnly,
+}
+
+impl EventFilter {
+    /// Returns whether records of the raw `EventType` pass this filter.
+    pub(crate) fn accepts(self, event_type: u16) -> bool {
+        match self {
+            EventFilter::All => true,
+            EventFilter::KeysAndResize => {
+                event_type == KEY_EVENT || event_type == WINDOW_BUFFER_SIZE_EVENT
+            }
+            EventFilter::KeysOnly => event_type == KEY_EVENT,
+        }
+    }
+}
+
+/// What an input filter does with a record, see
+/// [`Console::add_input_filter`](crate::Console::add_input_filter).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Pass the record on unchanged.
+    Pass,
+    /// Drop the record, the application never sees it.
+    Drop,
+    /// Pass this record on instead.
+    Replace(InputRecord),
+}
+
+type Filter = Box<dyn Fn(&InputRecord) -> FilterAction + Send>;
+
+/// The filters of a console, shared by its clones.
+#[derive(Clone, Default)]
+pub(crate) struct InputFilters(Arc<Mutex<Vec<Filter>>>);
+
+impl InputFilters {
+    pub(crate) fn add(&self, filter: Filter) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(filter);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Run `record` through the filters in the order they were added, every filter sees the
+    /// result of the ones before.
+    pub(crate) fn apply(&self, record: InputRecord) -> Option<InputRecord> {
+        let filters = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut record = record;
+        for filter in filters.iter() {
+            match filter(&record) {
+                FilterAction::Pass => {}
+                FilterAction::Drop => return None,
+                FilterAction::Replace(replacement) => record = replacement,
+            }
+        }
+        Some(record)
+    }
+}
+
+impl fmt::Debug for InputFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.0.lock().map_or(0, |filters| filters.len());
+        write!(f, "InputFilters({})", len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{KEY_EVENT, MOUSE_EVENT, WINDOW_BUFFER_SIZE_EVENT};
+
+    use super::{EventFilter, FilterAction, InputFilters};
+    use crate::{InputRecord, KeyEventRecord};
+
+    fn key(c: char) -> InputRecord {
+        InputRecord::KeyEvent(KeyEventRecord::builder().char(c).build())
+    }
+
+    #[test]
+    fn test_event_filter() {
+        assert!(EventFilter::All.accepts(MOUSE_EVENT));
+        assert!(EventFilter::KeysAndResize.accepts(WINDOW_BUFFER_SIZE_EVENT));
+        assert!(!EventFilter::KeysAndResize.accepts(MOUSE_EVENT));
+        assert!(EventFilter::KeysOnly.accepts(KEY_EVENT));
+        assert!(!EventFilter::KeysOnly.accepts(WINDOW_BUFFER_SIZE_EVENT));
+    }
+
+    #[test]
+    fn test_filters_chain() {
+        let filters = InputFilters::default();
+        filters.add(Box::new(|record| {
+            if *record == key('a') {
+                FilterAction::Replace(key('b'))
+            } else {
+                FilterAction::Pass
+            }
+        }));
+        filters.add(Box::new(|record| {
+            if *record == key('b') {
+                FilterAction::Drop
+            } else {
+                FilterAction::Pass
+            }
+        }));
+
+        assert_eq!(filters.apply(key('a')), None);
+        assert_eq!(filters.apply(key('c')), Some(key('c')));
+
+        filters.clear();
+        assert_eq!(filters.apply(key('a')), Some(key('a')));
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The size and duration of one write to the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteSample {
+    /// The number of UTF-8 bytes written.
+    pub bytes: usize,
+    /// How long the write took, including the fallback to `WriteFile`.
+    pub duration: Duration,
+}
+
+/// The most recent writes of a [`Console`](crate::Console), see
+/// [`ConsoleBuilder::record_io_stats`](crate::ConsoleBuilder::record_io_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IoStats {
+    samples: Vec<WriteSample>,
+    dropped: u64,
+}
+
+impl IoStats {
+    /// Get the recorded writes, oldest first.
+    pub fn samples(&self) -> &[WriteSample] {
+        &self.samples
+    }
+
+    /// Get the number of older writes that were dropped because the ring buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Get the number of bytes of the recorded writes.
+    pub fn total_bytes(&self) -> usize {
+        self.samples.iter().map(|sample| sample.bytes).sum()
+    }
+
+    /// Get the time spent in the recorded writes.
+    pub fn total_duration(&self) -> Duration {
+        self.samples.iter().map(|sample| sample.duration).sum()
+    }
+
+    /// Get the throughput of the recorded writes in bytes per second, or `None` if no time was
+    /// spent writing.
+    pub fn bytes_per_second(&self) -> Option<f64> {
+        let seconds = self.total_duration().as_secs_f64();
+        if seconds > 0.0 {
+            Some(self.total_bytes() as f64 / seconds)
+        } else {
+            None
+        }
+    }
+}
+
+/// The ring buffer of write samples of a console, shared by its clones.
+#[derive(Debug, Clone)]
+pub(crate) struct IoRecorder(Arc<Mutex<Ring>>);
+
+#[derive(Debug)]
+struct Ring {
+    capacity: usize,
+    samples: VecDeque<WriteSample>,
+    dropped: u64,
+}
+
+impl IoRecorder {
+    pub(crate) fn new(capacity: usize) -> IoRecorder {
+        IoRecorder(Arc::new(Mutex::new(Ring {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+            dropped: 0,
+        })))
+    }
+
+    pub(crate) fn record(&self, sample: WriteSample) {
+        let mut ring = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if ring.capacity == 0 {
+            ring.dropped += 1;
+            return;
+        }
+        if ring.samples.len() == ring.capacity {
+            ring.samples.pop_front();
+            ring.dropped += 1;
+        }
+        ring.samples.push_back(sample);
+    }
+
+    pub(crate) fn snapshot(&self) -> IoStats {
+        let ring = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        IoStats {
+            samples: ring.samples.iter().copied().collect(),
+            dropped: ring.dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{IoRecorder, WriteSample};
+
+    fn sample(bytes: usize, millis: u64) -> WriteSample {
+        WriteSample {
+            bytes,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let recorder = IoRecorder::new(2);
+        recorder.record(sample(10, 1));
+        recorder.record(sample(20, 1));
+        recorder.record(sample(30, 2));
+
+        let stats = recorder.snapshot();
+        assert_eq!(stats.samples(), [sample(20, 1), sample(30, 2)]);
+        assert_eq!(stats.dropped(), 1);
+        assert_eq!(stats.total_bytes(), 50);
+        assert_eq!(stats.bytes_per_second(), Some(50.0 / 0.003));
+    }
+}
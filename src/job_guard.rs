@@ -0,0 +1,85 @@
+use std::io::Result;
+use std::mem::{size_of, zeroed};
+use std::os::windows::io::AsRawHandle;
+use std::ptr::null_mut;
+
+use winapi::um::{
+    jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject},
+    winnt::{
+        JobObjectExtendedLimitInformation, HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    },
+};
+
+use super::{nonnull_handle_result, result, trace, ErrorContext, Handle};
+
+/// A job object that kills the processes assigned to it when it is dropped.
+///
+/// Terminal emulators use this to make sure the shells they start don't outlive them, even
+/// when the emulator crashes: the system closes the job handle when the process exits.
+///
+/// ```no_run
+/// # use std::process::Command;
+/// # use crossterm_winapi::JobGuard;
+/// # fn main() -> std::io::Result<()> {
+/// let job = JobGuard::new()?;
+/// let child = Command::new("cmd.exe").spawn()?;
+/// job.assign(&child)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JobGuard {
+    job: Handle,
+}
+
+impl JobGuard {
+    /// Create a job object with the kill on close limit.
+    ///
+    /// This wraps
+    /// [`CreateJobObjectW`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-createjobobjectw)
+    /// and
+    /// [`SetInformationJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-setinformationjobobject).
+    pub fn new() -> Result<JobGuard> {
+        let job = nonnull_handle_result(trace::call("CreateJobObjectW", null_mut(), || unsafe {
+            CreateJobObjectW(null_mut(), null_mut())
+        }))
+        .with_context("CreateJobObjectW")?;
+        let job = unsafe { Handle::from_raw(job) };
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { zeroed() };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        result(trace::call("SetInformationJobObject", *job, || unsafe {
+            SetInformationJobObject(
+                *job,
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as *mut _,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        }))
+        .with_context("SetInformationJobObject")?;
+
+        Ok(JobGuard { job })
+    }
+
+    /// Assign a process to the job, it is killed when the job is dropped.
+    ///
+    /// This accepts a [`std::process::Child`] or any other process handle.
+    ///
+    /// This wraps
+    /// [`AssignProcessToJobObject`](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-assignprocesstojobobject).
+    pub fn assign(&self, process: &impl AsRawHandle) -> Result<()> {
+        let process = process.as_raw_handle() as HANDLE;
+        result(trace::call(
+            "AssignProcessToJobObject",
+            *self.job,
+            || unsafe { AssignProcessToJobObject(*self.job, process) },
+        ))
+        .with_context("AssignProcessToJobObject")
+    }
+
+    /// Get the handle of the job object.
+    pub fn handle(&self) -> &Handle {
+        &self.job
+    }
+}
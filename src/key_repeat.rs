@@ -0,0 +1,131 @@
+//! This module tells the auto repeats of a held key apart from the first press.
+
+use super::{InputRecord, KeyEventRecord};
+
+impl KeyEventRecord {
+    /// Returns whether this is a key down record that the console coalesced from several auto
+    /// repeats, with a [`repeat_count`](Self::repeat_count) above `1`.
+    ///
+    /// Most hosts report every repeat as its own record with a count of `1`, which this can't
+    /// tell apart from a press. Use a [`KeyRepeatFilter`] to detect those too.
+    pub fn is_auto_repeat(&self) -> bool {
+        self.key_down && self.repeat_count > 1
+    }
+}
+
+/// Tracks which keys are held to detect the auto repeats of a key, see
+/// [`Events::suppress_key_repeats`](crate::Events::suppress_key_repeats).
+///
+/// A key down record for a key that is already held is a repeat. Records without a virtual key
+/// code, like characters written to the input buffer by another program or pasted, have no key
+/// to hold and are never repeats.
+///
+/// The console doesn't report releases while the window doesn't have focus, so call
+/// [`reset`](Self::reset) when it loses focus, or a key released in the meantime stays held.
+#[derive(Debug, Clone, Default)]
+pub struct KeyRepeatFilter {
+    held: Vec<u16>,
+}
+
+impl KeyRepeatFilter {
+    /// Create a filter that considers no key held.
+    pub fn new() -> KeyRepeatFilter {
+        KeyRepeatFilter::default()
+    }
+
+    /// Returns whether `record` is an auto repeat of a held key, updating which keys are held.
+    ///
+    /// The first press of a key isn't a repeat, even if the console coalesced repeats into it.
+    pub fn is_auto_repeat(&mut self, record: &KeyEventRecord) -> bool {
+        let code = record.virtual_key_code;
+        if code == 0 {
+            return false;
+        }
+        let held = self.held.iter().position(|&held| held == code);
+        match (record.key_down, held) {
+            (true, Some(_)) => true,
+            (true, None) => {
+                self.held.push(code);
+                false
+            }
+            (false, Some(i)) => {
+                self.held.remove(i);
+                false
+            }
+            (false, None) => false,
+        }
+    }
+
+    /// Forget which keys are held.
+    pub fn reset(&mut self) {
+        self.held.clear();
+    }
+
+    /// Decide whether `record` is reported when repeats are suppressed, reducing the first press
+    /// of a key to a single press.
+    pub(crate) fn process(&mut self, record: &mut InputRecord) -> bool {
+        match record {
+            InputRecord::KeyEvent(key) => {
+                if self.is_auto_repeat(key) {
+                    return false;
+                }
+                if key.key_down {
+                    key.repeat_count = key.repeat_count.min(1);
+                }
+                true
+            }
+            InputRecord::FocusEvent(focus) if !focus.focus_gained() => {
+                self.reset();
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyRepeatFilter;
+    use crate::{FocusEventRecord, InputRecord, KeyEventRecord};
+
+    fn key(virtual_key_code: u16, key_down: bool, repeat_count: u16) -> KeyEventRecord {
+        KeyEventRecord::builder()
+            .virtual_key_code(virtual_key_code)
+            .key_down(key_down)
+            .repeat_count(repeat_count)
+            .build()
+    }
+
+    #[test]
+    fn test_is_auto_repeat() {
+        assert!(!key(0x41, true, 1).is_auto_repeat());
+        assert!(key(0x41, true, 3).is_auto_repeat());
+        assert!(!key(0x41, false, 3).is_auto_repeat());
+
+        let mut filter = KeyRepeatFilter::new();
+        assert!(!filter.is_auto_repeat(&key(0x41, true, 1)));
+        assert!(filter.is_auto_repeat(&key(0x41, true, 1)));
+        assert!(!filter.is_auto_repeat(&key(0x10, true, 1)));
+        assert!(filter.is_auto_repeat(&key(0x41, true, 1)));
+        assert!(!filter.is_auto_repeat(&key(0x41, false, 1)));
+        assert!(!filter.is_auto_repeat(&key(0x41, true, 1)));
+
+        // Injected characters have no key.
+        assert!(!filter.is_auto_repeat(&key(0, true, 1)));
+        assert!(!filter.is_auto_repeat(&key(0, true, 1)));
+    }
+
+    #[test]
+    fn test_process() {
+        let mut filter = KeyRepeatFilter::new();
+        let mut press = InputRecord::KeyEvent(key(0x41, true, 4));
+        assert!(filter.process(&mut press));
+        assert_eq!(press, InputRecord::KeyEvent(key(0x41, true, 1)));
+        assert!(!filter.process(&mut InputRecord::KeyEvent(key(0x41, true, 1))));
+
+        // The release was missed while the window didn't have focus.
+        let mut focus_lost = InputRecord::FocusEvent(FocusEventRecord { set_focus: false });
+        assert!(filter.process(&mut focus_lost));
+        assert!(filter.process(&mut InputRecord::KeyEvent(key(0x41, true, 1))));
+    }
+}
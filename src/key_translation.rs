@@ -0,0 +1,273 @@
+//! This module translates key event records into the logical keys of [`VtKeyEvent`], so input
+//! read without `ENABLE_VIRTUAL_TERMINAL_INPUT` can be handled like decoded VT input.
+
+use winapi::um::wincon::{
+    LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED, SHIFT_PRESSED,
+};
+use winapi::um::winuser::{
+    VK_BACK, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_F12, VK_HOME, VK_INSERT, VK_LEFT,
+    VK_NEXT, VK_NUMPAD0, VK_NUMPAD9, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SPACE, VK_TAB, VK_UP,
+};
+
+use super::{KeyEventRecord, VtKey, VtKeyEvent, VtModifiers};
+
+impl VtKeyEvent {
+    /// Translate a key down record into a logical key, or `None` for key up records, modifier
+    /// keys and keys without a logical meaning, like dead keys or numpad 5 with Num Lock off.
+    ///
+    /// The console reports the same key differently depending on where it is and on the host,
+    /// the translation smooths this out:
+    ///
+    /// - The arrows, Home, End, Page Up, Page Down, Insert and Delete are the same key on the
+    ///   navigation cluster, which sets `ENHANCED_KEY`, and on the numeric keypad with Num Lock
+    ///   off, which doesn't.
+    /// - With Num Lock on, Shift temporarily turns the keypad into navigation keys and the
+    ///   console reports them without `SHIFT_PRESSED`, so Shift+numpad 8 is [`VtKey::Up`]
+    ///   without Shift.
+    /// - The digits and operators of the keypad are the characters they type, and the keypad
+    ///   Enter is [`VtKey::Enter`].
+    /// - Ctrl+letter is the letter with [`VtModifiers::ctrl`], whether the host reports the
+    ///   control character or no character, and AltGr (Ctrl+Alt) characters are reported without
+    ///   modifiers.
+    ///
+    /// The result matches what a [`VtInputDecoder`](crate::VtInputDecoder) reports for the same
+    /// key: characters don't carry Shift, and Shift+Tab is [`VtKey::BackTab`].
+    pub fn from_key_record(record: &KeyEventRecord) -> Option<VtKeyEvent> {
+        if !record.key_down {
+            return None;
+        }
+        let state = &record.control_key_state;
+        let mut modifiers = VtModifiers {
+            shift: state.has_state(SHIFT_PRESSED),
+            alt: state.has_state(LEFT_ALT_PRESSED | RIGHT_ALT_PRESSED),
+            ctrl: state.has_state(LEFT_CTRL_PRESSED | RIGHT_CTRL_PRESSED),
+        };
+        let code = i32::from(record.virtual_key_code);
+
+        let key = match code {
+            VK_UP => VtKey::Up,
+            VK_DOWN => VtKey::Down,
+            VK_LEFT => VtKey::Left,
+            VK_RIGHT => VtKey::Right,
+            VK_HOME => VtKey::Home,
+            VK_END => VtKey::End,
+            VK_PRIOR => VtKey::PageUp,
+            VK_NEXT => VtKey::PageDown,
+            VK_INSERT => VtKey::Insert,
+            VK_DELETE => VtKey::Delete,
+            VK_RETURN => VtKey::Enter,
+            VK_ESCAPE => VtKey::Escape,
+            VK_BACK => VtKey::Backspace,
+            VK_TAB if modifiers.shift => {
+                modifiers.shift = false;
+                VtKey::BackTab
+            }
+            VK_TAB => VtKey::Tab,
+            VK_F1..=VK_F12 => VtKey::F((code - VK_F1 + 1) as u8),
+            _ => {
+                let c = char_of(record, code, &mut modifiers)?;
+                // Like the VT decoder, the character already reflects Shift.
+                modifiers.shift = false;
+                VtKey::Char(c)
+            }
+        };
+        Some(VtKeyEvent { key, modifiers })
+    }
+}
+
+/// Get the character of a key that isn't a named key, adjusting the modifiers.
+fn char_of(record: &KeyEventRecord, code: i32, modifiers: &mut VtModifiers) -> Option<char> {
+    let c = char::from_u32(u32::from(record.u_char)).filter(|&c| c != '\0');
+    match c {
+        // Ctrl+letter produces the control character.
+        Some(c @ '\x01'..='\x1a') => {
+            modifiers.ctrl = true;
+            Some((b'a' + c as u8 - 1) as char)
+        }
+        Some(c) if c.is_control() => None,
+        Some(c) => {
+            // AltGr is reported as Ctrl+Alt, the character already includes it.
+            if modifiers.ctrl && modifiers.alt {
+                modifiers.ctrl = false;
+                modifiers.alt = false;
+            }
+            Some(c)
+        }
+        // Some hosts report Ctrl and Ctrl+Alt combinations without a character.
+        None => match code {
+            VK_SPACE => Some(' '),
+            0x30..=0x39 | 0x41..=0x5a => Some((code as u8).to_ascii_lowercase() as char),
+            VK_NUMPAD0..=VK_NUMPAD9 => Some((b'0' + (code - VK_NUMPAD0) as u8) as char),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{
+        ENHANCED_KEY, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, NUMLOCK_ON, RIGHT_ALT_PRESSED,
+        SHIFT_PRESSED,
+    };
+
+    use crate::{ControlKeyState, KeyEventRecord, VtKey, VtKeyEvent, VtModifiers};
+
+    const NONE: VtModifiers = VtModifiers {
+        shift: false,
+        alt: false,
+        ctrl: false,
+    };
+    const SHIFT: VtModifiers = VtModifiers {
+        shift: true,
+        ..NONE
+    };
+    const CTRL: VtModifiers = VtModifiers { ctrl: true, ..NONE };
+    const ALT: VtModifiers = VtModifiers { alt: true, ..NONE };
+
+    /// A record a host reports, by virtual key code, character and control key state, with the
+    /// key it should translate to.
+    type Case = (&'static str, u16, u16, u32, Option<(VtKey, VtModifiers)>);
+
+    const CASES: &[Case] = &[
+        ("up", 0x26, 0, ENHANCED_KEY, Some((VtKey::Up, NONE))),
+        (
+            "shift+up",
+            0x26,
+            0,
+            ENHANCED_KEY | SHIFT_PRESSED,
+            Some((VtKey::Up, SHIFT)),
+        ),
+        (
+            "ctrl+home",
+            0x24,
+            0,
+            ENHANCED_KEY | LEFT_CTRL_PRESSED,
+            Some((VtKey::Home, CTRL)),
+        ),
+        (
+            "numpad 8, num lock off",
+            0x26,
+            0,
+            0,
+            Some((VtKey::Up, NONE)),
+        ),
+        (
+            "shift+numpad 8, num lock on",
+            0x26,
+            0,
+            NUMLOCK_ON,
+            Some((VtKey::Up, NONE)),
+        ),
+        (
+            "numpad 8, num lock on",
+            0x68,
+            0x38,
+            NUMLOCK_ON,
+            Some((VtKey::Char('8'), NONE)),
+        ),
+        ("numpad 5, num lock off", 0x0c, 0, 0, None),
+        (
+            "numpad delete, num lock off",
+            0x2e,
+            0,
+            0,
+            Some((VtKey::Delete, NONE)),
+        ),
+        (
+            "numpad enter",
+            0x0d,
+            0x0d,
+            ENHANCED_KEY,
+            Some((VtKey::Enter, NONE)),
+        ),
+        (
+            "numpad divide",
+            0x6f,
+            0x2f,
+            ENHANCED_KEY,
+            Some((VtKey::Char('/'), NONE)),
+        ),
+        (
+            "shift+a",
+            0x41,
+            0x41,
+            SHIFT_PRESSED,
+            Some((VtKey::Char('A'), NONE)),
+        ),
+        (
+            "ctrl+a",
+            0x41,
+            0x01,
+            LEFT_CTRL_PRESSED,
+            Some((VtKey::Char('a'), CTRL)),
+        ),
+        (
+            "ctrl+alt+a without a character",
+            0x41,
+            0,
+            LEFT_CTRL_PRESSED | LEFT_ALT_PRESSED,
+            Some((
+                VtKey::Char('a'),
+                VtModifiers {
+                    ctrl: true,
+                    alt: true,
+                    shift: false,
+                },
+            )),
+        ),
+        (
+            "ctrl+space",
+            0x20,
+            0,
+            LEFT_CTRL_PRESSED,
+            Some((VtKey::Char(' '), CTRL)),
+        ),
+        (
+            "alt+x",
+            0x58,
+            0x78,
+            LEFT_ALT_PRESSED,
+            Some((VtKey::Char('x'), ALT)),
+        ),
+        (
+            "altgr+q",
+            0x51,
+            0x40,
+            LEFT_CTRL_PRESSED | RIGHT_ALT_PRESSED,
+            Some((VtKey::Char('@'), NONE)),
+        ),
+        (
+            "shift+tab",
+            0x09,
+            0x09,
+            SHIFT_PRESSED,
+            Some((VtKey::BackTab, NONE)),
+        ),
+        ("f5", 0x74, 0, 0, Some((VtKey::F(5), NONE))),
+        ("shift", 0x10, 0, SHIFT_PRESSED, None),
+    ];
+
+    #[test]
+    fn test_from_key_record() {
+        for (name, code, u_char, state, expected) in CASES {
+            let record = KeyEventRecord::builder()
+                .virtual_key_code(*code)
+                .u_char(*u_char)
+                .control_key_state(ControlKeyState::new(*state))
+                .build();
+            let expected = expected
+                .clone()
+                .map(|(key, modifiers)| VtKeyEvent { key, modifiers });
+            assert_eq!(VtKeyEvent::from_key_record(&record), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_key_up_is_ignored() {
+        let record = KeyEventRecord::builder()
+            .virtual_key_code(0x26)
+            .key_down(false)
+            .build();
+        assert_eq!(VtKeyEvent::from_key_record(&record), None);
+    }
+}
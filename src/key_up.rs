@@ -0,0 +1,152 @@
+//! This module decides how key up records are reported by [`Events`](crate::Events).
+
+use std::collections::VecDeque;
+
+use super::{Event, InputRecord, KeyEventRecord};
+
+/// How [`Events`](crate::Events) reports key up records.
+///
+/// Line editors only care about key presses, while games need to know when a key is released.
+/// The console doesn't report a release when the window loses focus while a key is held, so a
+/// game that relies on the records alone sees the key stuck.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyUpPolicy {
+    /// Key up records are dropped.
+    Ignore,
+    /// Key up records are reported as the console reports them.
+    #[default]
+    Emit,
+    /// Every key down is matched by exactly one key up. Releases are synthesized for the keys
+    /// that are still held when the console loses focus, and key up records for keys that are
+    /// not held, for example because they were pressed before the stream was created, are
+    /// dropped.
+    SynthesizeRelease,
+}
+
+/// Applies a [`KeyUpPolicy`] to the records read from the console.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyUpTracker {
+    policy: KeyUpPolicy,
+    held: Vec<KeyEventRecord>,
+}
+
+impl KeyUpTracker {
+    pub(crate) fn new(policy: KeyUpPolicy) -> KeyUpTracker {
+        KeyUpTracker {
+            policy,
+            held: Vec::new(),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> KeyUpPolicy {
+        self.policy
+    }
+
+    /// Decide whether `record` is reported, adding the releases to report before it to
+    /// `synthesized`.
+    pub(crate) fn process(
+        &mut self,
+        record: &InputRecord,
+        synthesized: &mut VecDeque<Event>,
+    ) -> bool {
+        match (self.policy, record) {
+            (KeyUpPolicy::SynthesizeRelease, InputRecord::KeyEvent(key)) if key.key_down => {
+                if !self.is_held(key) {
+                    self.held.push(key.clone());
+                }
+                true
+            }
+            (KeyUpPolicy::SynthesizeRelease, InputRecord::KeyEvent(key)) => {
+                match self.position(key) {
+                    Some(i) => {
+                        self.held.remove(i);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            (KeyUpPolicy::SynthesizeRelease, InputRecord::FocusEvent(focus))
+                if !focus.focus_gained() =>
+            {
+                synthesized.extend(self.held.drain(..).map(|key| {
+                    Event::Input(InputRecord::KeyEvent(KeyEventRecord {
+                        key_down: false,
+                        repeat_count: 1,
+                        ..key
+                    }))
+                }));
+                true
+            }
+            (KeyUpPolicy::Ignore, InputRecord::KeyEvent(key)) => key.key_down,
+            _ => true,
+        }
+    }
+
+    fn is_held(&self, key: &KeyEventRecord) -> bool {
+        self.position(key).is_some()
+    }
+
+    fn position(&self, key: &KeyEventRecord) -> Option<usize> {
+        self.held
+            .iter()
+            .position(|held| held.virtual_key_code == key.virtual_key_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{KeyUpPolicy, KeyUpTracker};
+    use crate::{Event, FocusEventRecord, InputRecord, KeyEventRecord};
+
+    fn key(virtual_key_code: u16, key_down: bool) -> InputRecord {
+        InputRecord::KeyEvent(
+            KeyEventRecord::builder()
+                .virtual_key_code(virtual_key_code)
+                .key_down(key_down)
+                .build(),
+        )
+    }
+
+    fn focus_lost() -> InputRecord {
+        InputRecord::FocusEvent(FocusEventRecord { set_focus: false })
+    }
+
+    #[test]
+    fn test_ignore_and_emit() {
+        let mut synthesized = VecDeque::new();
+        let mut ignore = KeyUpTracker::new(KeyUpPolicy::Ignore);
+        assert!(ignore.process(&key(0x41, true), &mut synthesized));
+        assert!(!ignore.process(&key(0x41, false), &mut synthesized));
+
+        let mut emit = KeyUpTracker::default();
+        assert!(emit.process(&key(0x41, false), &mut synthesized));
+        assert!(emit.process(&focus_lost(), &mut synthesized));
+        assert!(synthesized.is_empty());
+    }
+
+    #[test]
+    fn test_synthesize_release() {
+        let mut synthesized = VecDeque::new();
+        let mut tracker = KeyUpTracker::new(KeyUpPolicy::SynthesizeRelease);
+        // Released before it was seen pressed.
+        assert!(!tracker.process(&key(0x41, false), &mut synthesized));
+
+        assert!(tracker.process(&key(0x41, true), &mut synthesized));
+        assert!(tracker.process(&key(0x41, true), &mut synthesized));
+        assert!(tracker.process(&key(0x42, true), &mut synthesized));
+        assert!(tracker.process(&key(0x42, false), &mut synthesized));
+
+        assert!(tracker.process(&focus_lost(), &mut synthesized));
+        let release = match synthesized.pop_front() {
+            Some(Event::Input(InputRecord::KeyEvent(release))) => release,
+            event => panic!("expected a key release, got {:?}", event),
+        };
+        assert_eq!((release.virtual_key_code, release.key_down), (0x41, false));
+        assert!(synthesized.is_empty());
+
+        // The release was synthesized, the late one from the console is dropped.
+        assert!(!tracker.process(&key(0x41, false), &mut synthesized));
+    }
+}
@@ -9,27 +9,147 @@ use winapi::um::wincontypes::COORD;
 use winapi::um::winnt::HANDLE;
 
 pub use self::{
+    announce::AnnouncePriority,
+    attribute_guard::AttributeGuard,
+    buffer_snapshot::BufferSnapshot,
+    buffer_stack::BufferStack,
+    cached_screen_buffer::CachedScreenBuffer,
+    capability::{
+        supports_conpty, supports_focus_events, supports_vt, supports_vt_input, windows_version,
+        ApiSupport, Capabilities, Support, WindowsVersion,
+    },
     cfi::FontInfo,
-    console::Console,
+    clear_type::ClearType,
+    code_page_guard::CodePageGuard,
+    config::Overrides,
+    console::{Console, ConsoleBuilder},
+    console_font::ConsoleFont,
+    console_host::{ConsoleHost, FileVersion, HostKind},
+    console_lock::{console_lock, ConsoleLockGuard},
     console_mode::ConsoleMode,
+    console_mode_flags::{ConsoleModeFlags, ModeDiff, ModeKind},
+    console_mode_preset::ConsoleModePreset,
+    console_window::{ConsoleWindow, MonitorInfo, WindowPlacement, WindowShow},
+    console_writer::{ConsoleWriter, DEFAULT_BUFFER_CAPACITY},
+    conversion::{ConversionError, ConversionPolicy},
+    cooked_read::{CookedLine, WakeupKeys},
     csbi::ScreenBufferInfo,
-    handle::{Handle, HandleType},
+    csbi_ex::{InfoExSource, ScreenBufferInfoEx, DEFAULT_COLOR_TABLE, DEFAULT_POPUP_ATTRIBUTES},
+    ctrl_c_capture::CtrlCCaptureGuard,
+    ctrl_handler::{CtrlEvent, CtrlHandler, CtrlHandlerGuard},
+    diagnostics::{diagnostics, Diagnostics},
+    error::{ApiError, ErrorContext, Result},
+    event::{Event, EventSender, Events},
+    exit::{on_exit, run_exit_cleanups},
+    handle::{Handle, HandleKind, HandleType, NoStdHandle, Ownership},
+    hotkeys::GlobalHotkeys,
+    init::{init, shutdown, InitOptions},
+    input_filter::{EventFilter, FilterAction},
+    io_stats::{IoStats, WriteSample},
+    job_guard::JobGuard,
+    key_repeat::KeyRepeatFilter,
+    key_up::KeyUpPolicy,
+    read_watchdog::StallReport,
+    resize_debouncer::ResizeDebouncer,
     screen_buffer::ScreenBuffer,
+    scroll_on_drag::ScrollOnDrag,
     semaphore::Semaphore,
+    size_report::{SizeReport, SizeSource},
+    size_watcher::SizeWatcher,
+    startup_info::{StartupInfo, StartupInfoBuilder},
     structs::{
-        ButtonState, ControlKeyState, Coord, EventFlags, InputRecord, KeyEventRecord, MouseEvent,
-        Size, WindowPositions,
+        ButtonState, CharInfo, ControlKeyState, Coord, EventFlags, FocusEventRecord, InputRecord,
+        KeyEventRecord, KeyEventRecordBuilder, LegacyColor, MenuEventRecord, MouseEvent,
+        MouseEventBuilder, ReservedEvent, Size, SurrogatePairer, TextAttributes,
+        WindowBufferSizeRecord, WindowPositions,
     },
+    terminal_session::{TerminalSession, TerminalSessionBuilder},
+    vt_input::{EscDisambiguator, VtInputDecoder, VtKey, VtKeyEvent, VtModifiers},
+    wheel::{system_wheel_scroll_lines, wheel_scroll_chars, WheelScroll},
 };
 
+#[cfg(feature = "tokio")]
+pub use self::async_console::{AsyncConsoleReader, AsyncConsoleWriter};
+#[cfg(feature = "console-source")]
+pub use self::console_source::ConsoleSource;
+#[cfg(feature = "debug-handles")]
+pub use self::debug_handles::{handle_report, HandleReport, LiveHandle};
+#[cfg(feature = "event-channel")]
+pub use self::event_channel::EventChannel;
+#[cfg(feature = "hidden-input")]
+pub use self::hidden_input::SecretString;
+#[cfg(feature = "registry")]
+pub use self::registry::ConsoleDefaults;
+
+pub mod alias;
+mod alt_numpad;
+mod announce;
+#[cfg(feature = "tokio")]
+mod async_console;
+mod attribute_guard;
+mod buffer_snapshot;
+mod buffer_stack;
+mod cached_screen_buffer;
+mod capability;
 mod cfi;
-mod console;
+mod clear_type;
+pub mod clipboard;
+mod code_page_guard;
+pub mod color;
+pub mod config;
+pub mod console;
+mod console_font;
+mod console_host;
+mod console_lock;
 mod console_mode;
+mod console_mode_flags;
+mod console_mode_preset;
+#[cfg(feature = "console-source")]
+mod console_source;
+mod console_window;
+mod console_writer;
+pub mod constants;
+mod conversion;
+mod cooked_read;
 mod csbi;
+mod csbi_ex;
+mod ctrl_c_capture;
+mod ctrl_handler;
+#[cfg(feature = "debug-handles")]
+mod debug_handles;
+mod diagnostics;
+pub mod encoding;
+mod error;
+mod event;
+#[cfg(feature = "event-channel")]
+mod event_channel;
+mod exit;
 mod handle;
+#[cfg(feature = "hidden-input")]
+mod hidden_input;
+mod hotkeys;
+mod init;
+mod input_filter;
+mod io_stats;
+mod job_guard;
+mod key_repeat;
+mod key_translation;
+mod key_up;
+mod read_watchdog;
+#[cfg(feature = "registry")]
+mod registry;
+mod resize_debouncer;
 mod screen_buffer;
+mod scroll_on_drag;
 mod semaphore;
+mod size_report;
+mod size_watcher;
+mod startup_info;
 mod structs;
+mod terminal_session;
+mod trace;
+mod vt_input;
+mod wheel;
 
 /// Get the result of a call to WinAPI as an [`io::Result`].
 #[inline]
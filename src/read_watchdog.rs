@@ -0,0 +1,105 @@
+//! This module contains a watchdog that reports reads of the console input buffer that block
+//! although input is pending, see [`Console::read_with_watchdog`](crate::Console::read_with_watchdog).
+
+use std::fmt;
+use std::io::Result;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Console, ConsoleMode, ConsoleModeFlags, InputRecord};
+
+/// The state of the console input buffer while a read was blocked, passed to the callback of
+/// [`Console::read_with_watchdog`](crate::Console::read_with_watchdog).
+///
+/// Use the `Display` implementation to get a human readable dump for a bug report.
+#[derive(Debug)]
+pub struct StallReport {
+    /// How long the read has been blocked.
+    pub elapsed: Duration,
+    /// The number of records in the input buffer.
+    pub pending_events: Result<u32>,
+    /// The mode of the input buffer.
+    pub input_mode: Result<ConsoleModeFlags>,
+}
+
+impl StallReport {
+    /// Returns whether the read is stalled: it blocks although the input buffer isn't empty, or
+    /// the number of records can't even be queried.
+    fn is_stalled(&self) -> bool {
+        !matches!(self.pending_events, Ok(0))
+    }
+}
+
+impl fmt::Display for StallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "read blocked for: {:?}", self.elapsed)?;
+        match &self.pending_events {
+            Ok(count) => writeln!(f, "pending events: {}", count)?,
+            Err(e) => writeln!(f, "pending events: unavailable ({})", e)?,
+        }
+        match &self.input_mode {
+            Ok(mode) => writeln!(f, "input mode: {:#06x} ({})", mode.bits(), mode)?,
+            Err(e) => writeln!(f, "input mode: unavailable ({})", e)?,
+        }
+        Ok(())
+    }
+}
+
+/// Read one input record from `console`, calling `on_stall` every `timeout` while the read blocks
+/// although records are pending.
+pub(crate) fn read_with_watchdog(
+    console: &Console,
+    timeout: Duration,
+    mut on_stall: impl FnMut(&StallReport) + Send,
+) -> Result<Option<InputRecord>> {
+    let handle = console.handle().clone();
+    let (done, finished) = mpsc::channel::<()>();
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let console = Console::from(handle.clone());
+            let mode = ConsoleMode::from(handle);
+            while let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(timeout) {
+                let report = StallReport {
+                    elapsed: start.elapsed(),
+                    pending_events: console.number_of_console_input_events(),
+                    input_mode: mode.flags(),
+                };
+                if report.is_stalled() {
+                    on_stall(&report);
+                }
+            }
+        });
+
+        let record = console.try_read_single_input_event();
+        // Dropping the sender stops the watchdog.
+        drop(done);
+        record
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::time::Duration;
+
+    use super::StallReport;
+    use crate::ConsoleModeFlags;
+
+    fn report(pending_events: io::Result<u32>) -> StallReport {
+        StallReport {
+            elapsed: Duration::from_secs(5),
+            pending_events,
+            input_mode: Ok(ConsoleModeFlags::input(0x1f7)),
+        }
+    }
+
+    #[test]
+    fn test_is_stalled() {
+        assert!(!report(Ok(0)).is_stalled());
+        assert!(report(Ok(3)).is_stalled());
+        assert!(report(Err(io::Error::from_raw_os_error(6))).is_stalled());
+    }
+}
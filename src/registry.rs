@@ -0,0 +1,285 @@
+//! This module reads and writes the console defaults stored in the registry.
+
+use std::io::{self, Result};
+use std::ptr::null_mut;
+
+use winapi::shared::minwindef::HKEY;
+use winapi::shared::winerror::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+use winapi::um::winnt::{KEY_READ, KEY_WRITE, REG_DWORD, REG_SZ};
+use winapi::um::winreg::{
+    RegCloseKey, RegCreateKeyExW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW,
+    HKEY_CURRENT_USER,
+};
+
+use super::{trace, ApiError, ErrorContext, Size};
+
+/// The key below `HKEY_CURRENT_USER` that holds the defaults.
+const CONSOLE_KEY: &str = "Console";
+
+/// The console defaults of the current user, stored in `HKEY_CURRENT_USER\Console`.
+///
+/// The legacy console host applies these to every new console window that has no settings of
+/// its own, for example from a shortcut. Windows Terminal has its own settings and ignores them.
+///
+/// Every value is optional: [`load`](ConsoleDefaults::load) leaves the values that are not set
+/// in the registry as `None`, and [`save`](ConsoleDefaults::save) only writes the values that
+/// are `Some`, so other settings are kept.
+///
+/// ```no_run
+/// # use crossterm_winapi::ConsoleDefaults;
+/// # fn main() -> std::io::Result<()> {
+/// let mut defaults = ConsoleDefaults::load()?;
+/// defaults.quick_edit = Some(false);
+/// defaults.face_name = Some("Cascadia Mono".to_owned());
+/// defaults.save()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsoleDefaults {
+    /// Whether the mouse selects text, the `QuickEdit` value.
+    pub quick_edit: Option<bool>,
+    /// The size of the screen buffer in cells, the `ScreenBufferSize` value.
+    pub buffer_size: Option<Size>,
+    /// The size of the window in cells, the `WindowSize` value.
+    pub window_size: Option<Size>,
+    /// The 16 console colors as `0x00BBGGRR`, the `ColorTable00` to `ColorTable15` values.
+    pub color_table: Option<[u32; 16]>,
+    /// The name of the typeface, the `FaceName` value.
+    pub face_name: Option<String>,
+    /// The size of a cell in pixels, the `FontSize` value. A width of `0` lets the console pick
+    /// the width that matches the height.
+    pub font_size: Option<Size>,
+    /// The weight of the font, `400` is normal and `700` is bold, the `FontWeight` value.
+    pub font_weight: Option<u32>,
+}
+
+impl ConsoleDefaults {
+    /// Read the defaults of the current user.
+    ///
+    /// If the key doesn't exist, all values are `None`.
+    ///
+    /// This wraps
+    /// [`RegQueryValueExW`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryvalueexw).
+    pub fn load() -> Result<ConsoleDefaults> {
+        let key = match Key::open(CONSOLE_KEY) {
+            Ok(key) => key,
+            Err(e) if ApiError::raw_os_error(&e) == Some(ERROR_FILE_NOT_FOUND as i32) => {
+                return Ok(ConsoleDefaults::default())
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut color_table = [0; 16];
+        let mut has_colors = false;
+        for (i, color) in color_table.iter_mut().enumerate() {
+            if let Some(value) = key.dword(&color_name(i))? {
+                *color = value;
+                has_colors = true;
+            }
+        }
+
+        Ok(ConsoleDefaults {
+            quick_edit: key.dword("QuickEdit")?.map(|value| value != 0),
+            buffer_size: key.dword("ScreenBufferSize")?.map(unpack_size),
+            window_size: key.dword("WindowSize")?.map(unpack_size),
+            color_table: if has_colors { Some(color_table) } else { None },
+            face_name: key.string("FaceName")?,
+            font_size: key.dword("FontSize")?.map(unpack_size),
+            font_weight: key.dword("FontWeight")?,
+        })
+    }
+
+    /// Write the values that are `Some` to the defaults of the current user, creating the key if
+    /// it doesn't exist.
+    ///
+    /// This wraps
+    /// [`RegSetValueExW`](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regsetvalueexw).
+    pub fn save(&self) -> Result<()> {
+        let key = Key::create(CONSOLE_KEY)?;
+        if let Some(quick_edit) = self.quick_edit {
+            key.set_dword("QuickEdit", u32::from(quick_edit))?;
+        }
+        if let Some(size) = self.buffer_size {
+            key.set_dword("ScreenBufferSize", pack_size(size))?;
+        }
+        if let Some(size) = self.window_size {
+            key.set_dword("WindowSize", pack_size(size))?;
+        }
+        if let Some(color_table) = &self.color_table {
+            for (i, &color) in color_table.iter().enumerate() {
+                key.set_dword(&color_name(i), color)?;
+            }
+        }
+        if let Some(face_name) = &self.face_name {
+            key.set_string("FaceName", face_name)?;
+        }
+        if let Some(size) = self.font_size {
+            key.set_dword("FontSize", pack_size(size))?;
+        }
+        if let Some(weight) = self.font_weight {
+            key.set_dword("FontWeight", weight)?;
+        }
+        Ok(())
+    }
+}
+
+fn color_name(index: usize) -> String {
+    format!("ColorTable{:02}", index)
+}
+
+/// Sizes are stored with the height in the high word and the width in the low word.
+fn pack_size(size: Size) -> u32 {
+    (u32::from(size.height as u16) << 16) | u32::from(size.width as u16)
+}
+
+fn unpack_size(value: u32) -> Size {
+    Size::new(value as u16 as i16, (value >> 16) as u16 as i16)
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+fn status_result(status: i32) -> Result<()> {
+    if status == ERROR_SUCCESS as i32 {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(status))
+    }
+}
+
+/// An open registry key below `HKEY_CURRENT_USER`, closed on drop.
+struct Key(HKEY);
+
+impl Key {
+    fn open(path: &str) -> Result<Key> {
+        let path = wide(path);
+        let mut key = null_mut();
+        status_result(trace::call("RegOpenKeyExW", null_mut(), || unsafe {
+            RegOpenKeyExW(HKEY_CURRENT_USER, path.as_ptr(), 0, KEY_READ, &mut key)
+        }))
+        .with_context("RegOpenKeyExW")?;
+        Ok(Key(key))
+    }
+
+    fn create(path: &str) -> Result<Key> {
+        let path = wide(path);
+        let mut key = null_mut();
+        status_result(trace::call("RegCreateKeyExW", null_mut(), || unsafe {
+            RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                path.as_ptr(),
+                0,
+                null_mut(),
+                0,
+                KEY_WRITE,
+                null_mut(),
+                &mut key,
+                null_mut(),
+            )
+        }))
+        .with_context("RegCreateKeyExW")?;
+        Ok(Key(key))
+    }
+
+    /// Read the raw data of the value `name` if it exists and has the type `ty`.
+    fn query(&self, name: &str, ty: u32) -> Result<Option<Vec<u8>>> {
+        let name = wide(name);
+        let mut actual_ty = 0;
+        let mut len = 0;
+        let status = trace::call("RegQueryValueExW", self.0.cast(), || unsafe {
+            RegQueryValueExW(
+                self.0,
+                name.as_ptr(),
+                null_mut(),
+                &mut actual_ty,
+                null_mut(),
+                &mut len,
+            )
+        });
+        if status == ERROR_FILE_NOT_FOUND as i32 || (status == 0 && actual_ty != ty) {
+            return Ok(None);
+        }
+        status_result(status)?;
+
+        let mut data = vec![0u8; len as usize];
+        status_result(trace::call("RegQueryValueExW", self.0.cast(), || unsafe {
+            RegQueryValueExW(
+                self.0,
+                name.as_ptr(),
+                null_mut(),
+                null_mut(),
+                data.as_mut_ptr(),
+                &mut len,
+            )
+        }))
+        .with_context("RegQueryValueExW")?;
+        data.truncate(len as usize);
+        Ok(Some(data))
+    }
+
+    fn dword(&self, name: &str) -> Result<Option<u32>> {
+        Ok(self.query(name, REG_DWORD)?.and_then(|data| {
+            let bytes: [u8; 4] = data.get(..4)?.try_into().ok()?;
+            Some(u32::from_le_bytes(bytes))
+        }))
+    }
+
+    fn string(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.query(name, REG_SZ)?.map(|data| {
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .take_while(|&unit| unit != 0)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }))
+    }
+
+    fn set(&self, name: &str, ty: u32, data: &[u8]) -> Result<()> {
+        let name = wide(name);
+        status_result(trace::call("RegSetValueExW", self.0.cast(), || unsafe {
+            RegSetValueExW(
+                self.0,
+                name.as_ptr(),
+                0,
+                ty,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        }))
+        .with_context("RegSetValueExW")
+    }
+
+    fn set_dword(&self, name: &str, value: u32) -> Result<()> {
+        self.set(name, REG_DWORD, &value.to_le_bytes())
+    }
+
+    fn set_string(&self, name: &str, value: &str) -> Result<()> {
+        let data: Vec<u8> = wide(value)
+            .into_iter()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        self.set(name, REG_SZ, &data)
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        trace::call("RegCloseKey", null_mut(), || unsafe { RegCloseKey(self.0) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{color_name, pack_size, unpack_size};
+    use crate::Size;
+
+    #[test]
+    fn test_pack_size() {
+        assert_eq!(pack_size(Size::new(120, 9001)), 0x2329_0078);
+        assert_eq!(unpack_size(0x2329_0078), Size::new(120, 9001));
+        assert_eq!(color_name(7), "ColorTable07");
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use super::{InputRecord, WindowBufferSizeRecord};
+
+/// Coalesces bursts of resize events into one.
+///
+/// Conhost emits a [`InputRecord::WindowBufferSizeEvent`] for every intermediate size while the
+/// user drags the window border. Rendering every one of them wastes work and flickers. This
+/// adapter holds back resize events until no further resize arrived for the quiet period, and
+/// then only reports the latest size. All other records pass through unchanged.
+///
+/// ```no_run
+/// # use std::time::{Duration, Instant};
+/// # use crossterm_winapi::{Event, Events, Handle, ResizeDebouncer};
+/// # fn main() -> std::io::Result<()> {
+/// let mut events = Events::new(Handle::current_in_handle()?)?;
+/// let mut debouncer = ResizeDebouncer::new(Duration::from_millis(50));
+/// loop {
+///     if let Some(resize) = debouncer.poll(Instant::now()) {
+///         println!("{:?}", resize);
+///     }
+///     match events.poll(debouncer.timeout(Instant::now()))? {
+///         Some(Event::Input(record)) => {
+///             if let Some(record) = debouncer.push(record, Instant::now()) {
+///                 println!("{:?}", record);
+///             }
+///         }
+///         _ => {}
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResizeDebouncer {
+    quiet_period: Duration,
+    pending: Option<(WindowBufferSizeRecord, Instant)>,
+}
+
+impl ResizeDebouncer {
+    /// Create a debouncer that reports a resize after `quiet_period` without further resizes.
+    pub fn new(quiet_period: Duration) -> ResizeDebouncer {
+        ResizeDebouncer {
+            quiet_period,
+            pending: None,
+        }
+    }
+
+    /// Get the quiet period.
+    pub fn quiet_period(&self) -> Duration {
+        self.quiet_period
+    }
+
+    /// Feed a record that was read at `now`.
+    ///
+    /// Resize events are held back and `None` is returned, other records are returned as is.
+    pub fn push(&mut self, record: InputRecord, now: Instant) -> Option<InputRecord> {
+        match record {
+            InputRecord::WindowBufferSizeEvent(size) => {
+                self.pending = Some((size, now));
+                None
+            }
+            record => Some(record),
+        }
+    }
+
+    /// Take the held back resize event if the quiet period has passed at `now`.
+    pub fn poll(&mut self, now: Instant) -> Option<InputRecord> {
+        match self.pending {
+            Some((size, at)) if now.saturating_duration_since(at) >= self.quiet_period => {
+                self.pending = None;
+                Some(InputRecord::WindowBufferSizeEvent(size))
+            }
+            _ => None,
+        }
+    }
+
+    /// Take the held back resize event regardless of the quiet period.
+    pub fn flush(&mut self) -> Option<InputRecord> {
+        self.pending
+            .take()
+            .map(|(size, _)| InputRecord::WindowBufferSizeEvent(size))
+    }
+
+    /// Get the time left at `now` until the held back resize event is due, or `None` if no
+    /// resize event is held back.
+    ///
+    /// This is meant to be used as the timeout when waiting for the next record.
+    pub fn timeout(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .map(|(_, at)| (at + self.quiet_period).saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::ResizeDebouncer;
+    use crate::{Coord, InputRecord, KeyEventRecord, WindowBufferSizeRecord};
+
+    fn resize(x: i16, y: i16) -> InputRecord {
+        InputRecord::WindowBufferSizeEvent(WindowBufferSizeRecord {
+            size: Coord::new(x, y),
+            buffer_size: Coord::new(x, 9001),
+        })
+    }
+
+    #[test]
+    fn test_coalesce_resizes() {
+        let quiet = Duration::from_millis(50);
+        let mut debouncer = ResizeDebouncer::new(quiet);
+        let start = Instant::now();
+
+        assert_eq!(debouncer.push(resize(80, 24), start), None);
+        assert_eq!(
+            debouncer.push(resize(90, 30), start + Duration::from_millis(20)),
+            None
+        );
+        assert_eq!(debouncer.poll(start + Duration::from_millis(60)), None);
+        assert_eq!(
+            debouncer.timeout(start + Duration::from_millis(60)),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(
+            debouncer.poll(start + Duration::from_millis(70)),
+            Some(resize(90, 30))
+        );
+        assert_eq!(debouncer.timeout(start), None);
+    }
+
+    #[test]
+    fn test_pass_through() {
+        let mut debouncer = ResizeDebouncer::new(Duration::from_millis(50));
+        let key = InputRecord::KeyEvent(KeyEventRecord::builder().char('a').build());
+        assert_eq!(debouncer.push(key.clone(), Instant::now()), Some(key));
+    }
+}
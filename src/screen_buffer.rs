@@ -1,7 +1,14 @@
 //! This contains the logic for working with the console buffer.
 
-use std::io::Result;
-use std::mem::size_of;
+use std::char;
+use std::io::{self, Result};
+use std::mem::{size_of, zeroed};
+use std::os::windows::io::{
+    AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle, RawHandle,
+};
+use std::ptr;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use winapi::{
     shared::minwindef::TRUE,
@@ -9,15 +16,34 @@ use winapi::{
     um::{
         minwinbase::SECURITY_ATTRIBUTES,
         wincon::{
-            CreateConsoleScreenBuffer, GetConsoleScreenBufferInfo, GetCurrentConsoleFont,
-            SetConsoleActiveScreenBuffer, SetConsoleScreenBufferSize, CONSOLE_TEXTMODE_BUFFER,
-            COORD,
+            CreateConsoleScreenBuffer, FillConsoleOutputAttribute, FillConsoleOutputCharacterW,
+            GetConsoleDisplayMode, GetConsoleScreenBufferInfo, GetConsoleScreenBufferInfoEx,
+            GetCurrentConsoleFont, ReadConsoleOutputAttribute, ReadConsoleOutputCharacterW,
+            ReadConsoleOutputW, ScrollConsoleScreenBufferW, SetConsoleActiveScreenBuffer,
+            SetConsoleCursorPosition, SetConsoleScreenBufferSize, SetConsoleTextAttribute,
+            SetConsoleWindowInfo, WriteConsoleOutputCharacterW, CHAR_INFO, CONSOLE_FULLSCREEN,
+            CONSOLE_TEXTMODE_BUFFER, COORD, SMALL_RECT,
         },
-        winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE},
+        winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE, HANDLE},
     },
 };
 
-use super::{handle_result, result, FontInfo, Handle, HandleType, ScreenBufferInfo};
+use super::{
+    console_lock, handle_result, result, size_report::DEFAULT_DPI, trace, AttributeGuard,
+    BufferSnapshot, ClearType, ConsoleWindow, Coord, ErrorContext, FontInfo, Handle, HandleKind,
+    HandleType, Ownership, ScreenBufferInfo, ScreenBufferInfoEx, Size, SizeReport, SizeWatcher,
+    TextAttributes, WindowPositions,
+};
+
+/// The text attributes the process started with, see [`ScreenBuffer::default_attributes`].
+static DEFAULT_ATTRIBUTES: Mutex<Option<u16>> = Mutex::new(None);
+
+/// Remember the current attributes of `handle` as the defaults, unless they are known already.
+///
+/// This is called before this crate changes the attributes for the first time.
+pub(crate) fn capture_default_attributes(handle: &Handle) {
+    let _ = ScreenBuffer::new(handle.clone()).default_attributes();
+}
 
 /// A wrapper around a screen buffer.
 #[derive(Clone, Debug)]
@@ -27,10 +53,48 @@ pub struct ScreenBuffer {
 
 impl ScreenBuffer {
     /// Create a wrapper around a screen buffer from its handle.
+    ///
+    /// The raw handle is closed according to the [`Ownership`] of `handle`, once the last clone of
+    /// it is dropped.
     pub fn new(handle: Handle) -> Self {
         Self { handle }
     }
 
+    /// Create a wrapper around the screen buffer of the handle of the given type, for example
+    /// [`HandleType::ErrorHandle`] for the standard error.
+    pub fn from_handle_type(handle_type: HandleType) -> Result<ScreenBuffer> {
+        Ok(Self::new(Handle::new(handle_type)?))
+    }
+
+    /// Create a wrapper around the screen buffer of the standard error.
+    ///
+    /// Interactive tools like pickers draw on the standard error while their standard output is
+    /// piped to another program. All methods work the same on this screen buffer, and a
+    /// [`Console`](crate::Console) for it is created with [`HandleType::ErrorHandle`] or from
+    /// [`handle`](ScreenBuffer::handle).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the standard error is not a console.
+    pub fn from_stderr() -> Result<ScreenBuffer> {
+        let handle = Handle::error_handle()?;
+        if handle.kind() != HandleKind::Console {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the standard error is not a console",
+            ));
+        }
+        Ok(Self::new(handle))
+    }
+
+    /// Create a wrapper around a raw screen buffer handle, closing it on drop only if it is
+    /// [`Ownership::Owned`].
+    ///
+    /// # Safety
+    ///
+    /// See [`Handle::from_raw_with_ownership`].
+    pub unsafe fn from_handle_with_ownership(handle: HANDLE, ownership: Ownership) -> Self {
+        Self::new(Handle::from_raw_with_ownership(handle, ownership))
+    }
+
     /// Get the current console screen buffer
     pub fn current() -> Result<ScreenBuffer> {
         Ok(ScreenBuffer {
@@ -49,16 +113,20 @@ impl ScreenBuffer {
             bInheritHandle: TRUE,
         };
 
-        let new_screen_buffer = handle_result(unsafe {
-            CreateConsoleScreenBuffer(
-                GENERIC_READ |           // read/write access
-                    GENERIC_WRITE,
-                FILE_SHARE_READ | FILE_SHARE_WRITE, // shared
-                &security_attr,                     // default security attributes
-                CONSOLE_TEXTMODE_BUFFER,            // must be TEXTMODE
-                NULL,
-            )
-        })?;
+        let new_screen_buffer = handle_result(trace::call(
+            "CreateConsoleScreenBuffer",
+            ptr::null_mut(),
+            || unsafe {
+                CreateConsoleScreenBuffer(
+                    GENERIC_READ |           // read/write access
+                        GENERIC_WRITE,
+                    FILE_SHARE_READ | FILE_SHARE_WRITE, // shared
+                    &security_attr,                     // default security attributes
+                    CONSOLE_TEXTMODE_BUFFER,            // must be TEXTMODE
+                    NULL,
+                )
+            },
+        ))?;
         Ok(ScreenBuffer {
             handle: unsafe { Handle::from_raw(new_screen_buffer) },
         })
@@ -69,7 +137,13 @@ impl ScreenBuffer {
     /// This wraps
     /// [`SetConsoleActiveScreenBuffer`](https://docs.microsoft.com/en-us/windows/console/setconsoleactivescreenbuffer).
     pub fn show(&self) -> Result<()> {
-        result(unsafe { SetConsoleActiveScreenBuffer(*self.handle) })
+        let _lock = console_lock()?;
+        result(trace::call(
+            "SetConsoleActiveScreenBuffer",
+            *self.handle,
+            || unsafe { SetConsoleActiveScreenBuffer(*self.handle) },
+        ))
+        .with_handle_context("SetConsoleActiveScreenBuffer", &self.handle)
     }
 
     /// Get the screen buffer information like terminal size, cursor position, buffer size.
@@ -78,17 +152,84 @@ impl ScreenBuffer {
     /// [`GetConsoleScreenBufferInfo`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfo).
     pub fn info(&self) -> Result<ScreenBufferInfo> {
         let mut csbi = ScreenBufferInfo::new();
-        result(unsafe { GetConsoleScreenBufferInfo(*self.handle, &mut csbi.0) })?;
+        result(trace::call(
+            "GetConsoleScreenBufferInfo",
+            *self.handle,
+            || unsafe { GetConsoleScreenBufferInfo(*self.handle, &mut csbi.0) },
+        ))
+        .with_handle_context("GetConsoleScreenBufferInfo", &self.handle)?;
         Ok(csbi)
     }
 
+    /// Get the extended screen buffer information, like the color table.
+    ///
+    /// If `GetConsoleScreenBufferInfoEx` fails, as it does on some versions of Wine and ReactOS,
+    /// this falls back to [`info`](ScreenBuffer::info) and defaults for the extended fields.
+    /// [`ScreenBufferInfoEx::source`] reports which path was used.
+    ///
+    /// This wraps
+    /// [`GetConsoleScreenBufferInfoEx`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfoex).
+    pub fn info_ex(&self) -> Result<ScreenBufferInfoEx> {
+        let mut raw = ScreenBufferInfoEx::empty();
+        let native = result(trace::call(
+            "GetConsoleScreenBufferInfoEx",
+            *self.handle,
+            || unsafe { GetConsoleScreenBufferInfoEx(*self.handle, &mut raw) },
+        ))
+        .with_handle_context("GetConsoleScreenBufferInfoEx", &self.handle);
+        match native {
+            Ok(()) => Ok(ScreenBufferInfoEx::native(raw)),
+            Err(e) => {
+                trace::warn(
+                    "GetConsoleScreenBufferInfoEx",
+                    *self.handle,
+                    &format!("falling back to GetConsoleScreenBufferInfo: {}", e),
+                );
+                Ok(ScreenBufferInfoEx::fallback(&self.info()?))
+            }
+        }
+    }
+
+    /// Get the size of the visible window in cells and in physical pixels.
+    ///
+    /// The pixel size is cross-checked between the client area of the console window, the DPI of
+    /// the window, the display mode and the font size, so it is correct in fullscreen mode and
+    /// for processes that are not DPI aware on scaled monitors. [`SizeReport::source`] tells
+    /// which was used.
+    ///
+    /// This wraps
+    /// [`GetConsoleDisplayMode`](https://docs.microsoft.com/en-us/windows/console/getconsoledisplaymode)
+    /// and
+    /// [`GetClientRect`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getclientrect).
+    pub fn size_report(&self) -> Result<SizeReport> {
+        let info = self.info()?;
+        let font = self.font_info()?.size();
+
+        let mut display_mode = 0;
+        let fullscreen = trace::call("GetConsoleDisplayMode", ptr::null_mut(), || unsafe {
+            GetConsoleDisplayMode(&mut display_mode)
+        }) != 0
+            && display_mode & CONSOLE_FULLSCREEN != 0;
+
+        let window = ConsoleWindow::current().ok();
+        let client = window.and_then(|window| window.client_size().ok());
+        let dpi = window.map_or(DEFAULT_DPI, |window| window.dpi());
+
+        Ok(SizeReport::measure(&info, font, client, dpi, fullscreen))
+    }
+
     /// Get the current font information like size and font index.
     ///
     /// This wraps
     /// [`GetConsoleFontSize`](https://learn.microsoft.com/en-us/windows/console/getconsolefontsize).
     pub fn font_info(&self) -> Result<FontInfo> {
         let mut fi = FontInfo::new();
-        result(unsafe { GetCurrentConsoleFont(*self.handle, 0, &mut fi.0) })?;
+        result(trace::call(
+            "GetCurrentConsoleFont",
+            *self.handle,
+            || unsafe { GetCurrentConsoleFont(*self.handle, 0, &mut fi.0) },
+        ))
+        .with_handle_context("GetCurrentConsoleFont", &self.handle)?;
         Ok(fi)
     }
 
@@ -97,7 +238,400 @@ impl ScreenBuffer {
     /// This wraps
     /// [`SetConsoleScreenBufferSize`](https://docs.microsoft.com/en-us/windows/console/setconsolescreenbuffersize).
     pub fn set_size(&self, x: i16, y: i16) -> Result<()> {
-        result(unsafe { SetConsoleScreenBufferSize(*self.handle, COORD { X: x, Y: y }) })
+        let _lock = console_lock()?;
+        result(trace::call(
+            "SetConsoleScreenBufferSize",
+            *self.handle,
+            || unsafe { SetConsoleScreenBufferSize(*self.handle, COORD { X: x, Y: y }) },
+        ))
+        .with_handle_context("SetConsoleScreenBufferSize", &self.handle)
+    }
+
+    /// Read the characters and attributes of the cells in `region`.
+    ///
+    /// The region is clipped to the screen buffer. Large regions are read in several calls, as
+    /// older console hosts fail to read more than 64 KiB at once.
+    ///
+    /// This wraps
+    /// [`ReadConsoleOutputW`](https://docs.microsoft.com/en-us/windows/console/readconsoleoutput).
+    pub fn snapshot(&self, region: WindowPositions) -> Result<BufferSnapshot> {
+        let buffer_size = self.info()?.buffer_size();
+        let region = WindowPositions {
+            left: region.left.max(0),
+            top: region.top.max(0),
+            right: region.right.min(buffer_size.width - 1),
+            bottom: region.bottom.min(buffer_size.height - 1),
+        };
+        let width = (region.right - region.left + 1).max(0);
+        let height = (region.bottom - region.top + 1).max(0);
+
+        let mut chars = Vec::with_capacity(width as usize * height as usize);
+        let mut attributes = Vec::with_capacity(chars.capacity());
+        if width == 0 || height == 0 {
+            return Ok(BufferSnapshot::new(region, chars, attributes));
+        }
+
+        const MAX_CELLS_PER_READ: i16 = (64 * 1024 / size_of::<CHAR_INFO>()) as i16;
+        let rows_per_read = (MAX_CELLS_PER_READ / width).max(1);
+        let mut buf: Vec<CHAR_INFO> = Vec::new();
+
+        let mut top = region.top;
+        while top <= region.bottom {
+            let rows = rows_per_read.min(region.bottom - top + 1);
+            buf.clear();
+            buf.resize(width as usize * rows as usize, unsafe { zeroed() });
+            let mut read_region = SMALL_RECT {
+                Left: region.left,
+                Top: top,
+                Right: region.right,
+                Bottom: top + rows - 1,
+            };
+
+            result(trace::call("ReadConsoleOutputW", *self.handle, || unsafe {
+                ReadConsoleOutputW(
+                    *self.handle,
+                    buf.as_mut_ptr(),
+                    COORD { X: width, Y: rows },
+                    COORD { X: 0, Y: 0 },
+                    &mut read_region,
+                )
+            }))
+            .with_handle_context("ReadConsoleOutputW", &self.handle)?;
+
+            for cell in &buf {
+                chars.push(unsafe { *cell.Char.UnicodeChar() });
+                attributes.push(cell.Attributes);
+            }
+            top += rows;
+        }
+
+        Ok(BufferSnapshot::new(region, chars, attributes))
+    }
+
+    /// Get the attributes of the cell at `coord`.
+    ///
+    /// This wraps
+    /// [`ReadConsoleOutputAttribute`](https://docs.microsoft.com/en-us/windows/console/readconsoleoutputattribute).
+    pub fn attribute_at(&self, coord: Coord) -> Result<TextAttributes> {
+        let mut attribute = 0;
+        let mut read = 0;
+        result(trace::call(
+            "ReadConsoleOutputAttribute",
+            *self.handle,
+            || unsafe {
+                ReadConsoleOutputAttribute(
+                    *self.handle,
+                    &mut attribute,
+                    1,
+                    COORD::from(coord),
+                    &mut read,
+                )
+            },
+        ))
+        .with_handle_context("ReadConsoleOutputAttribute", &self.handle)?;
+        if read == 0 {
+            return Err(outside_buffer(coord));
+        }
+        Ok(TextAttributes::new(attribute))
+    }
+
+    /// Get the character in the cell at `coord`.
+    ///
+    /// A character outside the basic multilingual plane takes two cells, it is returned when
+    /// `coord` is the first of them. The second cell, or any other unpaired surrogate, is
+    /// returned as `U+FFFD`.
+    ///
+    /// This wraps
+    /// [`ReadConsoleOutputCharacterW`](https://docs.microsoft.com/en-us/windows/console/readconsoleoutputcharacter).
+    pub fn char_at(&self, coord: Coord) -> Result<char> {
+        // Read the next cell in the same call, in case this one holds a high surrogate.
+        let mut units = [0u16; 2];
+        let mut read = 0;
+        result(trace::call(
+            "ReadConsoleOutputCharacterW",
+            *self.handle,
+            || unsafe {
+                ReadConsoleOutputCharacterW(
+                    *self.handle,
+                    units.as_mut_ptr(),
+                    units.len() as u32,
+                    COORD::from(coord),
+                    &mut read,
+                )
+            },
+        ))
+        .with_handle_context("ReadConsoleOutputCharacterW", &self.handle)?;
+        if read == 0 {
+            return Err(outside_buffer(coord));
+        }
+
+        let c = char::decode_utf16(units[..read as usize].iter().copied())
+            .next()
+            .and_then(|c| c.ok())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        Ok(c)
+    }
+
+    /// Write the characters of `text` starting at `pos`, returning the position of the cell
+    /// after them.
+    ///
+    /// The text wraps to the next line at the right edge of the buffer, and wide characters and
+    /// characters outside the basic multilingual plane take two cells. A wide character that
+    /// doesn't fit in the last cell of a line is moved to the next line. The attributes and the
+    /// cursor are not changed, and control characters are written as they are. Text that doesn't
+    /// fit in the buffer is cut off, the position is then the start of the line below the buffer.
+    ///
+    /// This wraps
+    /// [`WriteConsoleOutputCharacterW`](https://docs.microsoft.com/en-us/windows/console/writeconsoleoutputcharacter).
+    pub fn write_chars_at_advance(&self, text: &str, pos: Coord) -> Result<Coord> {
+        let buffer_size = self.info()?.buffer_size();
+        if pos.x < 0 || pos.y < 0 || pos.x >= buffer_size.width || pos.y >= buffer_size.height {
+            return Err(outside_buffer(pos));
+        }
+        let units: Vec<u16> = text.encode_utf16().collect();
+        let mut written = 0;
+        result(trace::call(
+            "WriteConsoleOutputCharacterW",
+            *self.handle,
+            || unsafe {
+                WriteConsoleOutputCharacterW(
+                    *self.handle,
+                    units.as_ptr(),
+                    units.len() as u32,
+                    COORD::from(pos),
+                    &mut written,
+                )
+            },
+        ))
+        .with_handle_context("WriteConsoleOutputCharacterW", &self.handle)?;
+        Ok(advance(pos, text, buffer_size))
+    }
+
+    /// Discard the scrollback above the window except for its last `keep_lines` lines.
+    ///
+    /// The buffer contents are scrolled up, so the window and the cursor keep showing the same
+    /// text, and the lines freed at the bottom of the buffer are blanked with the current
+    /// attributes. The size of the buffer doesn't change.
+    ///
+    /// This wraps
+    /// [`ScrollConsoleScreenBufferW`](https://docs.microsoft.com/en-us/windows/console/scrollconsolescreenbuffer).
+    pub fn trim_scrollback(&self, keep_lines: u16) -> Result<()> {
+        let info = self.info()?;
+        let window = info.terminal_window();
+        let shift = scrollback_shift(window.top, keep_lines);
+        if shift == 0 {
+            return Ok(());
+        }
+        let buffer_size = info.buffer_size();
+
+        let scroll = SMALL_RECT {
+            Left: 0,
+            Top: shift,
+            Right: buffer_size.width - 1,
+            Bottom: buffer_size.height - 1,
+        };
+        let mut fill: CHAR_INFO = unsafe { zeroed() };
+        unsafe { *fill.Char.UnicodeChar_mut() = u16::from(b' ') };
+        fill.Attributes = info.attributes();
+        result(trace::call(
+            "ScrollConsoleScreenBufferW",
+            *self.handle,
+            || unsafe {
+                ScrollConsoleScreenBufferW(
+                    *self.handle,
+                    &scroll,
+                    ptr::null(),
+                    COORD { X: 0, Y: 0 },
+                    &fill,
+                )
+            },
+        ))
+        .with_handle_context("ScrollConsoleScreenBufferW", &self.handle)?;
+
+        let window = SMALL_RECT {
+            Left: window.left,
+            Top: window.top - shift,
+            Right: window.right,
+            Bottom: window.bottom - shift,
+        };
+        result(trace::call(
+            "SetConsoleWindowInfo",
+            *self.handle,
+            || unsafe { SetConsoleWindowInfo(*self.handle, TRUE, &window) },
+        ))
+        .with_handle_context("SetConsoleWindowInfo", &self.handle)?;
+
+        let cursor = info.cursor_pos();
+        let cursor = COORD {
+            X: cursor.x,
+            Y: (cursor.y - shift).max(0),
+        };
+        result(trace::call(
+            "SetConsoleCursorPosition",
+            *self.handle,
+            || unsafe { SetConsoleCursorPosition(*self.handle, cursor) },
+        ))
+        .with_handle_context("SetConsoleCursorPosition", &self.handle)
+    }
+
+    /// Discard all of the scrollback above the window, like `ESC [ 3 J` does on consoles with
+    /// virtual terminal processing.
+    ///
+    /// See [`trim_scrollback`](ScreenBuffer::trim_scrollback) for how the buffer is changed.
+    pub fn clear_scrollback(&self) -> Result<()> {
+        self.trim_scrollback(0)
+    }
+
+    /// Move the window over the buffer so its top left cell is at `origin`, keeping its size.
+    ///
+    /// The origin is clamped so the window stays inside the buffer, which makes this suitable
+    /// for scrolling through the scrollback one page at a time.
+    ///
+    /// This wraps
+    /// [`SetConsoleWindowInfo`](https://docs.microsoft.com/en-us/windows/console/setconsolewindowinfo).
+    pub fn set_viewport_origin(&self, origin: Coord) -> Result<()> {
+        let info = self.info()?;
+        let window = SMALL_RECT::from(viewport_at(
+            info.terminal_window(),
+            origin,
+            info.buffer_size(),
+        ));
+        result(trace::call(
+            "SetConsoleWindowInfo",
+            *self.handle,
+            || unsafe { SetConsoleWindowInfo(*self.handle, TRUE, &window) },
+        ))
+        .with_handle_context("SetConsoleWindowInfo", &self.handle)
+    }
+
+    /// Watch the size of this screen buffer by querying it every `interval`.
+    ///
+    /// Use this to get resize notifications in a process whose standard input is not the console,
+    /// where no [`InputRecord::WindowBufferSizeEvent`](crate::InputRecord::WindowBufferSizeEvent)
+    /// arrives.
+    pub fn watch_size(&self, interval: Duration) -> Result<SizeWatcher> {
+        SizeWatcher::new(self.clone(), interval)
+    }
+
+    /// Copy the cells in `src` so that its top left cell ends up at `dst`.
+    ///
+    /// The regions may overlap, which makes this suitable to emulate inserting and deleting
+    /// lines. Only the destination cells are changed, the cells of `src` that are not overwritten
+    /// are left as they are. Cells moved outside the buffer are discarded.
+    ///
+    /// This wraps
+    /// [`ScrollConsoleScreenBufferW`](https://docs.microsoft.com/en-us/windows/console/scrollconsolescreenbuffer),
+    /// using the destination as clipping rectangle so nothing is filled.
+    pub fn copy_region(&self, src: WindowPositions, dst: Coord) -> Result<()> {
+        let scroll = SMALL_RECT::from(src);
+        let clip = SMALL_RECT::from(destination(src, dst));
+        let mut fill: CHAR_INFO = unsafe { zeroed() };
+        unsafe { *fill.Char.UnicodeChar_mut() = u16::from(b' ') };
+        result(trace::call(
+            "ScrollConsoleScreenBufferW",
+            *self.handle,
+            || unsafe {
+                ScrollConsoleScreenBufferW(*self.handle, &scroll, &clip, COORD::from(dst), &fill)
+            },
+        ))
+        .with_handle_context("ScrollConsoleScreenBufferW", &self.handle)
+    }
+
+    /// Clear a part of the buffer, see [`ClearType`].
+    ///
+    /// Cleared cells are set to a space with the current attributes. The cursor is moved when
+    /// clearing [`All`](ClearType::All) or the [`CurrentLine`](ClearType::CurrentLine).
+    ///
+    /// This wraps
+    /// [`FillConsoleOutputCharacterW`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputcharacter)
+    /// and
+    /// [`FillConsoleOutputAttribute`](https://docs.microsoft.com/en-us/windows/console/fillconsoleoutputattribute).
+    pub fn clear(&self, clear_type: ClearType) -> Result<()> {
+        let info = self.info()?;
+        let range = clear_type.range(info.cursor_pos(), info.buffer_size());
+        let start = COORD::from(range.start);
+
+        let mut written = 0;
+        result(trace::call(
+            "FillConsoleOutputCharacterW",
+            *self.handle,
+            || unsafe {
+                FillConsoleOutputCharacterW(
+                    *self.handle,
+                    u16::from(b' '),
+                    range.cells,
+                    start,
+                    &mut written,
+                )
+            },
+        ))
+        .with_handle_context("FillConsoleOutputCharacterW", &self.handle)?;
+        result(trace::call(
+            "FillConsoleOutputAttribute",
+            *self.handle,
+            || unsafe {
+                FillConsoleOutputAttribute(
+                    *self.handle,
+                    info.attributes(),
+                    range.cells,
+                    start,
+                    &mut written,
+                )
+            },
+        ))
+        .with_handle_context("FillConsoleOutputAttribute", &self.handle)?;
+
+        match range.cursor {
+            Some(cursor) => result(trace::call(
+                "SetConsoleCursorPosition",
+                *self.handle,
+                || unsafe { SetConsoleCursorPosition(*self.handle, COORD::from(cursor)) },
+            ))
+            .with_handle_context("SetConsoleCursorPosition", &self.handle),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the text attributes the process started with, to restore the colors of the user on
+    /// exit.
+    ///
+    /// The attributes are captured once for the whole process: before this crate changes the
+    /// attributes for the first time, on the first call to [`init`](crate::init), or else on the
+    /// first call to this function. Call this at startup if the attributes may be changed
+    /// without this crate.
+    pub fn default_attributes(&self) -> Result<u16> {
+        let mut defaults = DEFAULT_ATTRIBUTES.lock().unwrap_or_else(|e| e.into_inner());
+        match *defaults {
+            Some(attributes) => Ok(attributes),
+            None => {
+                let attributes = self.info()?.attributes();
+                *defaults = Some(attributes);
+                Ok(attributes)
+            }
+        }
+    }
+
+    /// Restore the [`default_attributes`](ScreenBuffer::default_attributes) of the process.
+    ///
+    /// This wraps
+    /// [`SetConsoleTextAttribute`](https://docs.microsoft.com/en-us/windows/console/setconsoletextattribute).
+    pub fn reset_to_default_attributes(&self) -> Result<()> {
+        let attributes = self.default_attributes()?;
+        result(trace::call(
+            "SetConsoleTextAttribute",
+            *self.handle,
+            || unsafe { SetConsoleTextAttribute(*self.handle, attributes) },
+        ))
+        .with_handle_context("SetConsoleTextAttribute", &self.handle)
+    }
+
+    /// Run `f` with the given text attributes applied to this screen buffer.
+    ///
+    /// The previous attributes are restored afterwards, even when `f` panics.
+    /// See [`AttributeGuard`] for more info.
+    pub fn with_attributes<T>(&self, attributes: u16, f: impl FnOnce() -> T) -> Result<T> {
+        let _guard = AttributeGuard::new(self, attributes)?;
+        Ok(f())
     }
 
     /// Get the underlying raw `HANDLE` used by this type to execute with.
@@ -106,15 +640,198 @@ impl ScreenBuffer {
     }
 }
 
+fn outside_buffer(coord: Coord) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} is outside of the screen buffer", coord),
+    )
+}
+
+/// The position after `text` is written at `pos` in a buffer of `buffer_size`.
+fn advance(pos: Coord, text: &str, buffer_size: Size) -> Coord {
+    let end = Coord::new(0, buffer_size.height);
+    let mut pos = pos;
+    for c in text.chars() {
+        let width = cell_width(c);
+        if pos.x + width > buffer_size.width {
+            pos = Coord::new(0, pos.y + 1);
+        }
+        if pos.y >= buffer_size.height {
+            return end;
+        }
+        pos.x += width;
+        if pos.x >= buffer_size.width {
+            pos = Coord::new(0, pos.y + 1);
+        }
+    }
+    if pos.y >= buffer_size.height {
+        end
+    } else {
+        pos
+    }
+}
+
+/// The number of cells the console uses for `c`.
+pub(crate) fn cell_width(c: char) -> i16 {
+    const WIDE: [(u32, u32); 12] = [
+        (0x1100, 0x115F),
+        (0x2E80, 0x303E),
+        (0x3041, 0x33FF),
+        (0x3400, 0x4DBF),
+        (0x4E00, 0x9FFF),
+        (0xA000, 0xA4CF),
+        (0xAC00, 0xD7A3),
+        (0xF900, 0xFAFF),
+        (0xFE30, 0xFE4F),
+        (0xFF00, 0xFF60),
+        (0xFFE0, 0xFFE6),
+        // Outside the basic multilingual plane a character takes two cells for its surrogates.
+        (0x10000, 0x10FFFF),
+    ];
+    let c = u32::from(c);
+    if WIDE
+        .iter()
+        .any(|&(first, last)| (first..=last).contains(&c))
+    {
+        2
+    } else {
+        1
+    }
+}
+
+/// The region `src` covers once it is copied to `dst`.
+fn destination(src: WindowPositions, dst: Coord) -> WindowPositions {
+    WindowPositions {
+        left: dst.x,
+        top: dst.y,
+        right: dst.x.saturating_add(src.right.saturating_sub(src.left)),
+        bottom: dst.y.saturating_add(src.bottom.saturating_sub(src.top)),
+    }
+}
+
+/// The position of `window` moved to `origin` inside a buffer of `buffer_size`.
+pub(crate) fn viewport_at(
+    window: WindowPositions,
+    origin: Coord,
+    buffer_size: Size,
+) -> WindowPositions {
+    let width = window.right - window.left;
+    let height = window.bottom - window.top;
+    let left = origin.x.clamp(0, (buffer_size.width - 1 - width).max(0));
+    let top = origin.y.clamp(0, (buffer_size.height - 1 - height).max(0));
+    WindowPositions {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// The number of lines to scroll the buffer up by to keep `keep_lines` lines above the window.
+fn scrollback_shift(window_top: i16, keep_lines: u16) -> i16 {
+    let keep_lines = i16::try_from(keep_lines).unwrap_or(i16::MAX);
+    window_top.saturating_sub(keep_lines).max(0)
+}
+
 impl From<Handle> for ScreenBuffer {
+    /// Wrap `handle`, see [`ScreenBuffer::new`].
     fn from(handle: Handle) -> Self {
         ScreenBuffer { handle }
     }
 }
 
+impl AsRawHandle for ScreenBuffer {
+    /// Get the raw handle, which stays owned by this `ScreenBuffer`.
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle.as_raw_handle()
+    }
+}
+
+impl AsHandle for ScreenBuffer {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+impl TryFrom<ScreenBuffer> for OwnedHandle {
+    type Error = io::Error;
+
+    /// Convert the handle of `screen_buffer` into an [`OwnedHandle`] like converting a
+    /// [`Handle`], which duplicates it if it is shared.
+    fn try_from(screen_buffer: ScreenBuffer) -> Result<OwnedHandle> {
+        OwnedHandle::try_from(screen_buffer.handle)
+    }
+}
+
+impl FromRawHandle for ScreenBuffer {
+    /// Take ownership of `handle`, it is closed when the last clone of the `ScreenBuffer` is
+    /// dropped.
+    unsafe fn from_raw_handle(handle: RawHandle) -> Self {
+        ScreenBuffer::from(Handle::from_raw_handle(handle))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::ScreenBuffer;
+    use super::{advance, destination, scrollback_shift, viewport_at, ScreenBuffer};
+    use crate::{Coord, Size, WindowPositions};
+
+    #[test]
+    fn test_destination() {
+        let src = WindowPositions {
+            left: 0,
+            top: 5,
+            right: 79,
+            bottom: 23,
+        };
+        assert_eq!(
+            destination(src, Coord::new(0, 6)),
+            WindowPositions {
+                left: 0,
+                top: 6,
+                right: 79,
+                bottom: 24,
+            }
+        );
+    }
+
+    #[test]
+    fn test_advance() {
+        let size = Size::new(10, 3);
+        assert_eq!(advance(Coord::new(2, 0), "abc", size), Coord::new(5, 0));
+        assert_eq!(advance(Coord::new(8, 0), "abc", size), Coord::new(1, 1));
+        assert_eq!(advance(Coord::new(7, 1), "abc", size), Coord::new(0, 2));
+        // The wide character doesn't fit in the last cell.
+        assert_eq!(advance(Coord::new(8, 0), "a漢", size), Coord::new(2, 1));
+        assert_eq!(advance(Coord::new(0, 0), "😀", size), Coord::new(2, 0));
+        assert_eq!(advance(Coord::new(8, 2), "abcd", size), Coord::new(0, 3));
+    }
+
+    #[test]
+    fn test_viewport_at() {
+        let window = WindowPositions {
+            left: 0,
+            top: 10,
+            right: 79,
+            bottom: 34,
+        };
+        let buffer_size = Size::new(120, 300);
+        let moved = viewport_at(window, Coord::new(5, 100), buffer_size);
+        assert_eq!(
+            (moved.left, moved.top, moved.right, moved.bottom),
+            (5, 100, 84, 124)
+        );
+        let clamped = viewport_at(window, Coord::new(-3, 1000), buffer_size);
+        assert_eq!((clamped.left, clamped.top, clamped.bottom), (0, 275, 299));
+    }
+
+    #[test]
+    fn test_scrollback_shift() {
+        assert_eq!(scrollback_shift(100, 0), 100);
+        assert_eq!(scrollback_shift(100, 30), 70);
+        assert_eq!(scrollback_shift(20, 30), 0);
+        assert_eq!(scrollback_shift(0, u16::MAX), 0);
+    }
 
     #[test]
     fn test_screen_buffer_info() {
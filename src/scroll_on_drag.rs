@@ -0,0 +1,177 @@
+use std::io::Result;
+use std::time::{Duration, Instant};
+
+use super::{screen_buffer::viewport_at, Coord, MouseEvent, ScreenBuffer, Size, WindowPositions};
+
+/// Scrolls the window of a screen buffer while the mouse is dragged outside of it.
+///
+/// Selecting text with the mouse needs the window to scroll when the drag reaches its edge. The
+/// console only reports the drag once, clamped to the edge of the window, see
+/// [`MouseEvent::is_clamped`]. This keeps scrolling the window one cell per interval towards the
+/// edge until the mouse moves back inside or the button is released, and reports the cell under
+/// the mouse after every step.
+///
+/// ```no_run
+/// # use std::time::{Duration, Instant};
+/// # use crossterm_winapi::{Event, Events, Handle, InputRecord, ScreenBuffer, ScrollOnDrag};
+/// # fn main() -> std::io::Result<()> {
+/// let mut events = Events::new(Handle::current_in_handle()?)?;
+/// let mut scroll = ScrollOnDrag::new(ScreenBuffer::current()?, Duration::from_millis(50));
+/// loop {
+///     if let Some(mouse) = scroll.poll(Instant::now())? {
+///         println!("extend the selection to {:?}", mouse.mouse_position);
+///     }
+///     if let Some(Event::Input(InputRecord::MouseEvent(mouse))) =
+///         events.poll(scroll.timeout(Instant::now()))?
+///     {
+///         scroll.push(&mouse, Instant::now())?;
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ScrollOnDrag {
+    screen_buffer: ScreenBuffer,
+    interval: Duration,
+    drag: Option<(MouseEvent, Instant)>,
+}
+
+impl ScrollOnDrag {
+    /// Create a scroller for the window of `screen_buffer` that scrolls by one cell every
+    /// `interval`.
+    pub fn new(screen_buffer: ScreenBuffer, interval: Duration) -> ScrollOnDrag {
+        ScrollOnDrag {
+            screen_buffer,
+            interval,
+            drag: None,
+        }
+    }
+
+    /// Feed a mouse event that was read at `now`.
+    ///
+    /// A drag clamped to the window starts scrolling after one interval, any other event stops
+    /// it.
+    pub fn push(&mut self, mouse: &MouseEvent, now: Instant) -> Result<()> {
+        if mouse.is_clamped(&self.screen_buffer.info()?) {
+            let since = self.drag.map_or(now, |(_, since)| since);
+            self.drag = Some((*mouse, since));
+        } else {
+            self.drag = None;
+        }
+        Ok(())
+    }
+
+    /// Scroll the window by one step if an interval has passed at `now` while dragging.
+    ///
+    /// Returns the drag with the position of the cell under the mouse after scrolling, or `None`
+    /// if it is not time to scroll or the window is already at the edge of the buffer.
+    ///
+    /// This wraps
+    /// [`SetConsoleWindowInfo`](https://docs.microsoft.com/en-us/windows/console/setconsolewindowinfo).
+    pub fn poll(&mut self, now: Instant) -> Result<Option<MouseEvent>> {
+        let (mouse, since) = match self.drag {
+            Some((mouse, since)) if now.saturating_duration_since(since) >= self.interval => {
+                (mouse, since)
+            }
+            _ => return Ok(None),
+        };
+        // Keep the schedule of the ticks even if polled late.
+        self.drag = Some((mouse, since + self.interval));
+
+        let info = self.screen_buffer.info()?;
+        let (window, position) =
+            match scroll_step(&mouse, info.terminal_window(), info.buffer_size()) {
+                Some(step) => step,
+                None => return Ok(None),
+            };
+        self.screen_buffer
+            .set_viewport_origin(Coord::new(window.left, window.top))?;
+
+        let mouse = MouseEvent {
+            mouse_position: position,
+            ..mouse
+        };
+        self.drag = Some((mouse, since + self.interval));
+        Ok(Some(mouse))
+    }
+
+    /// Get the time until the next step at `now`, or `None` while not dragging.
+    ///
+    /// This can be passed to [`Events::poll`](crate::Events::poll) as the timeout.
+    pub fn timeout(&self, now: Instant) -> Option<Duration> {
+        self.drag
+            .map(|(_, since)| (since + self.interval).saturating_duration_since(now))
+    }
+
+    /// Returns whether the mouse is dragged outside the window.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+}
+
+/// The window after scrolling it one cell towards the edge `mouse` is clamped to, and the new
+/// position of the mouse, or `None` if the window can't move.
+fn scroll_step(
+    mouse: &MouseEvent,
+    window: WindowPositions,
+    buffer_size: Size,
+) -> Option<(WindowPositions, Coord)> {
+    let position = mouse.mouse_position;
+    let direction = |at: i16, low: i16, high: i16| {
+        if at <= low {
+            -1
+        } else if at >= high {
+            1
+        } else {
+            0
+        }
+    };
+    let origin = Coord::new(
+        window.left + direction(position.x, window.left, window.right),
+        window.top + direction(position.y, window.top, window.bottom),
+    );
+    let moved = viewport_at(window, origin, buffer_size);
+    if moved == window {
+        return None;
+    }
+    let position = Coord::new(
+        position.x + moved.left - window.left,
+        position.y + moved.top - window.top,
+    );
+    Some((moved, position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scroll_step;
+    use crate::{ButtonState, Coord, EventFlags, MouseEvent, Size, WindowPositions};
+
+    #[test]
+    fn test_scroll_step() {
+        let window = WindowPositions {
+            left: 0,
+            top: 10,
+            right: 79,
+            bottom: 34,
+        };
+        let buffer_size = Size::new(80, 100);
+        let drag = |x, y| {
+            MouseEvent::builder()
+                .position(Coord::new(x, y))
+                .button_state(ButtonState::from(1))
+                .event_flags(EventFlags::MouseMoved)
+                .build()
+        };
+
+        let (moved, position) = scroll_step(&drag(5, 34), window, buffer_size).unwrap();
+        assert_eq!((moved.top, moved.bottom), (11, 35));
+        assert_eq!(position, Coord::new(5, 35));
+
+        let (moved, position) = scroll_step(&drag(5, 10), window, buffer_size).unwrap();
+        assert_eq!(moved.top, 9);
+        assert_eq!(position, Coord::new(5, 9));
+
+        // The buffer is as wide as the window, so it can't scroll sideways.
+        assert_eq!(scroll_step(&drag(79, 20), window, buffer_size), None);
+    }
+}
@@ -2,7 +2,7 @@ use std::{io, ptr};
 
 use winapi::um::synchapi::{CreateSemaphoreW, ReleaseSemaphore};
 
-use crate::{nonnull_handle_result, result, Handle};
+use crate::{nonnull_handle_result, result, trace, ErrorContext, Handle};
 
 /// A [Windows semaphore](https://docs.microsoft.com/en-us/windows/win32/sync/semaphore-objects).
 #[derive(Clone, Debug)]
@@ -14,9 +14,11 @@ impl Semaphore {
     /// This wraps
     /// [`CreateSemaphoreW`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-createsemaphorew).
     pub fn new() -> io::Result<Self> {
-        let handle = nonnull_handle_result(unsafe {
-            CreateSemaphoreW(ptr::null_mut(), 0, 1, ptr::null_mut())
-        })?;
+        let handle = nonnull_handle_result(trace::call(
+            "CreateSemaphoreW",
+            ptr::null_mut(),
+            || unsafe { CreateSemaphoreW(ptr::null_mut(), 0, 1, ptr::null_mut()) },
+        ))?;
 
         let handle = unsafe { Handle::from_raw(handle) };
         Ok(Self(handle))
@@ -27,7 +29,10 @@ impl Semaphore {
     /// This wraps
     /// [`ReleaseSemaphore`](https://docs.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-releasesemaphore).
     pub fn release(&self) -> io::Result<()> {
-        result(unsafe { ReleaseSemaphore(*self.0, 1, ptr::null_mut()) })
+        result(trace::call("ReleaseSemaphore", *self.0, || unsafe {
+            ReleaseSemaphore(*self.0, 1, ptr::null_mut())
+        }))
+        .with_context("ReleaseSemaphore")
     }
 
     /// Access the underlying handle to the semaphore.
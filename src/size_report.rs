@@ -0,0 +1,143 @@
+use std::fmt;
+
+use super::{ScreenBufferInfo, Size};
+
+/// The dots per inch of a window at 100% scaling.
+pub(crate) const DEFAULT_DPI: u32 = 96;
+
+/// Where the pixel size of a [`SizeReport`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeSource {
+    /// The client area of the console window, which matches the cells.
+    Window,
+    /// The client area of the console window, scaled from the logical pixels a DPI unaware
+    /// process sees to physical pixels.
+    DpiScaled,
+    /// The cells times the font size, because the console is in fullscreen display mode and the
+    /// window doesn't reflect the screen.
+    Fullscreen,
+    /// The cells times the font size, because the console has no window that matches the cells,
+    /// as in Windows Terminal.
+    Font,
+}
+
+impl fmt::Display for SizeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SizeSource::Window => "window",
+            SizeSource::DpiScaled => "dpi scaled window",
+            SizeSource::Fullscreen => "fullscreen",
+            SizeSource::Font => "font",
+        })
+    }
+}
+
+/// The size of the visible window of a screen buffer in cells and in physical pixels.
+///
+/// This is created with [`ScreenBuffer::size_report`](crate::ScreenBuffer::size_report).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeReport {
+    /// The number of visible columns and rows.
+    ///
+    /// Unlike [`ScreenBufferInfo::terminal_size`], this is the number of cells, not the
+    /// difference between the first and the last.
+    pub cells: Size,
+    /// The size of the cells in physical pixels.
+    pub pixels: Size,
+    /// How [`pixels`](SizeReport::pixels) was determined.
+    pub source: SizeSource,
+}
+
+impl SizeReport {
+    /// Cross-check the visible window of `info` with the client area of the console window, in
+    /// the pixels the process sees, the font size and the DPI of the window.
+    pub(crate) fn measure(
+        info: &ScreenBufferInfo,
+        font: Size,
+        client: Option<Size>,
+        dpi: u32,
+        fullscreen: bool,
+    ) -> SizeReport {
+        let window = info.terminal_window();
+        let cells = Size::new(
+            window.right - window.left + 1,
+            window.bottom - window.top + 1,
+        );
+        let expected = Size::new(
+            saturate(i32::from(cells.width) * i32::from(font.width)),
+            saturate(i32::from(cells.height) * i32::from(font.height)),
+        );
+        let report = |pixels, source| SizeReport {
+            cells,
+            pixels,
+            source,
+        };
+
+        if fullscreen {
+            return report(expected, SizeSource::Fullscreen);
+        }
+        let client = match client {
+            Some(client) => client,
+            None => return report(expected, SizeSource::Font),
+        };
+        if fits(client, expected, font) {
+            return report(client, SizeSource::Window);
+        }
+        let scaled = Size::new(
+            saturate(i32::from(client.width) * dpi as i32 / DEFAULT_DPI as i32),
+            saturate(i32::from(client.height) * dpi as i32 / DEFAULT_DPI as i32),
+        );
+        if dpi != DEFAULT_DPI && fits(scaled, expected, font) {
+            return report(scaled, SizeSource::DpiScaled);
+        }
+        report(expected, SizeSource::Font)
+    }
+}
+
+/// Returns whether a client area of `client` pixels holds the `expected` pixels of the cells,
+/// with less than one cell to spare for the padding of the window.
+fn fits(client: Size, expected: Size, font: Size) -> bool {
+    let spare_width = i32::from(client.width) - i32::from(expected.width);
+    let spare_height = i32::from(client.height) - i32::from(expected.height);
+    (0..=i32::from(font.width)).contains(&spare_width)
+        && (0..=i32::from(font.height)).contains(&spare_height)
+}
+
+fn saturate(pixels: i32) -> i16 {
+    pixels.clamp(0, i32::from(i16::MAX)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SizeReport, SizeSource};
+    use crate::{ScreenBufferInfo, Size};
+
+    fn info() -> ScreenBufferInfo {
+        let mut info = ScreenBufferInfo::new();
+        info.0.srWindow.Right = 119;
+        info.0.srWindow.Top = 10;
+        info.0.srWindow.Bottom = 39;
+        info
+    }
+
+    #[test]
+    fn test_measure() {
+        let font = Size::new(8, 16);
+        let report = SizeReport::measure(&info(), font, Some(Size::new(964, 482)), 144, false);
+        assert_eq!(report.cells, Size::new(120, 30));
+        assert_eq!(report.pixels, Size::new(964, 482));
+        assert_eq!(report.source, SizeSource::Window);
+
+        // A DPI unaware process sees 2/3 of the pixels at 150% scaling.
+        let report = SizeReport::measure(&info(), font, Some(Size::new(640, 320)), 144, false);
+        assert_eq!(report.pixels, Size::new(960, 480));
+        assert_eq!(report.source, SizeSource::DpiScaled);
+
+        let report = SizeReport::measure(&info(), font, Some(Size::new(300, 200)), 96, false);
+        assert_eq!(report.pixels, Size::new(960, 480));
+        assert_eq!(report.source, SizeSource::Font);
+
+        let report = SizeReport::measure(&info(), font, Some(Size::new(964, 482)), 96, true);
+        assert_eq!(report.source, SizeSource::Fullscreen);
+    }
+}
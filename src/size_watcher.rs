@@ -0,0 +1,130 @@
+use std::io::Result;
+use std::thread;
+use std::time::Duration;
+
+use super::{Coord, ScreenBuffer, ScreenBufferInfo, WindowBufferSizeRecord};
+
+/// Reports changes of the size of a screen buffer by polling it, see
+/// [`ScreenBuffer::watch_size`].
+///
+/// The console only reports resizes as [`InputRecord::WindowBufferSizeEvent`]s in the input
+/// buffer, which never arrive in a process whose standard input is not the console, like a
+/// renderer that only opened `CONOUT$`. This queries the size of the screen buffer instead and
+/// reports it when the window or the buffer changed size.
+///
+/// As an iterator it blocks, sleeping for the interval between queries until the size changes.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use crossterm_winapi::ScreenBuffer;
+/// # fn main() -> std::io::Result<()> {
+/// for resize in ScreenBuffer::current()?.watch_size(Duration::from_millis(100))? {
+///     println!("resized to {:?}", resize?.size);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`InputRecord::WindowBufferSizeEvent`]: crate::InputRecord::WindowBufferSizeEvent
+#[derive(Debug)]
+pub struct SizeWatcher {
+    screen_buffer: ScreenBuffer,
+    interval: Duration,
+    last: WindowBufferSizeRecord,
+}
+
+impl SizeWatcher {
+    pub(crate) fn new(screen_buffer: ScreenBuffer, interval: Duration) -> Result<SizeWatcher> {
+        let last = size_record(&screen_buffer.info()?);
+        Ok(SizeWatcher {
+            screen_buffer,
+            interval,
+            last,
+        })
+    }
+
+    /// Get the interval between queries when used as an iterator.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Get the size that was seen last.
+    pub fn size(&self) -> WindowBufferSizeRecord {
+        self.last
+    }
+
+    /// Query the size once, returning it if it changed since the last query.
+    ///
+    /// This wraps
+    /// [`GetConsoleScreenBufferInfo`](https://docs.microsoft.com/en-us/windows/console/getconsolescreenbufferinfo).
+    pub fn poll(&mut self) -> Result<Option<WindowBufferSizeRecord>> {
+        let record = size_record(&self.screen_buffer.info()?);
+        Ok(update(&mut self.last, record))
+    }
+}
+
+impl Iterator for SizeWatcher {
+    type Item = Result<WindowBufferSizeRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            thread::sleep(self.interval);
+            match self.poll() {
+                Ok(Some(record)) => return Some(Ok(record)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Get the record the console would report for the size in `info`.
+pub(crate) fn size_record(info: &ScreenBufferInfo) -> WindowBufferSizeRecord {
+    let window = info.terminal_size();
+    let buffer = info.buffer_size();
+    WindowBufferSizeRecord {
+        size: Coord::new(window.width, window.height),
+        buffer_size: Coord::new(buffer.width, buffer.height),
+    }
+}
+
+/// Replace `last` with `record`, returning it if it is different.
+fn update(
+    last: &mut WindowBufferSizeRecord,
+    record: WindowBufferSizeRecord,
+) -> Option<WindowBufferSizeRecord> {
+    if *last == record {
+        None
+    } else {
+        *last = record;
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::update;
+    use crate::{Coord, WindowBufferSizeRecord};
+
+    fn record(x: i16, y: i16, lines: i16) -> WindowBufferSizeRecord {
+        WindowBufferSizeRecord {
+            size: Coord::new(x, y),
+            buffer_size: Coord::new(x, lines),
+        }
+    }
+
+    #[test]
+    fn test_update() {
+        let mut last = record(80, 24, 9001);
+        assert_eq!(update(&mut last, record(80, 24, 9001)), None);
+        assert_eq!(
+            update(&mut last, record(100, 30, 9001)),
+            Some(record(100, 30, 9001))
+        );
+        assert_eq!(
+            update(&mut last, record(100, 30, 30)),
+            Some(record(100, 30, 30))
+        );
+        assert_eq!(last, record(100, 30, 30));
+    }
+}
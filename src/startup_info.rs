@@ -0,0 +1,273 @@
+//! This module contains the glue to pass console handles, or a pseudo console, to a child process
+//! started with `CreateProcessW`.
+
+use std::fmt;
+use std::io::Result;
+use std::mem::{self, size_of};
+use std::ptr;
+
+use winapi::shared::basetsd::{DWORD_PTR, SIZE_T};
+use winapi::shared::minwindef::TRUE;
+use winapi::um::{
+    handleapi::DuplicateHandle,
+    processthreadsapi::{
+        DeleteProcThreadAttributeList, GetCurrentProcess, InitializeProcThreadAttributeList,
+        UpdateProcThreadAttribute, STARTUPINFOW,
+    },
+    winbase::{EXTENDED_STARTUPINFO_PRESENT, STARTF_USESTDHANDLES, STARTUPINFOEXW},
+    wincontypes::HPCON,
+    winnt::{DUPLICATE_SAME_ACCESS, HANDLE},
+};
+
+use super::{result, trace, ErrorContext, Handle};
+
+/// Restricts the handles a child inherits to a list, missing from `winapi`.
+const PROC_THREAD_ATTRIBUTE_HANDLE_LIST: DWORD_PTR = 0x0002_0002;
+/// Attaches a pseudo console to a child, missing from `winapi`.
+const PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE: DWORD_PTR = 0x0002_0016;
+
+/// A builder for the [`StartupInfo`] of a child process.
+///
+/// ```no_run
+/// # use crossterm_winapi::{Handle, StartupInfoBuilder};
+/// # fn main() -> std::io::Result<()> {
+/// let output = Handle::current_out_handle()?;
+/// let mut startup_info = StartupInfoBuilder::new()
+///     .stdout(&output)
+///     .stderr(&output)
+///     .build()?;
+/// // Pass `startup_info.as_mut_ptr()`, `startup_info.creation_flags()` and
+/// // `startup_info.inherit_handles()` to `CreateProcessW`.
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StartupInfoBuilder {
+    stdin: Option<Handle>,
+    stdout: Option<Handle>,
+    stderr: Option<Handle>,
+    pseudo_console: Option<HPCON>,
+}
+
+impl StartupInfoBuilder {
+    /// Create a builder that passes no handles.
+    pub fn new() -> StartupInfoBuilder {
+        StartupInfoBuilder::default()
+    }
+
+    /// Use `handle` as the standard input of the child.
+    pub fn stdin(mut self, handle: &Handle) -> Self {
+        self.stdin = Some(handle.clone());
+        self
+    }
+
+    /// Use `handle` as the standard output of the child.
+    pub fn stdout(mut self, handle: &Handle) -> Self {
+        self.stdout = Some(handle.clone());
+        self
+    }
+
+    /// Use `handle` as the standard error of the child.
+    pub fn stderr(mut self, handle: &Handle) -> Self {
+        self.stderr = Some(handle.clone());
+        self
+    }
+
+    /// Attach the pseudo console `hpcon` to the child.
+    ///
+    /// The child then gets the pseudo console as its console. Don't set any standard handles as
+    /// well, they would replace the handles of the pseudo console.
+    ///
+    /// # Safety
+    ///
+    /// `hpcon` must be a pseudo console created with `CreatePseudoConsole` that stays open until
+    /// the child is created.
+    pub unsafe fn pseudo_console(mut self, hpcon: HPCON) -> Self {
+        self.pseudo_console = Some(hpcon);
+        self
+    }
+
+    /// Create the startup information.
+    ///
+    /// The standard handles are duplicated as inheritable handles, so the handles of this
+    /// process stay private, and the child is restricted to inherit only these duplicates.
+    ///
+    /// This wraps
+    /// [`DuplicateHandle`](https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle)
+    /// and
+    /// [`UpdateProcThreadAttribute`](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-updateprocthreadattribute).
+    pub fn build(self) -> Result<StartupInfo> {
+        let mut info: STARTUPINFOEXW = unsafe { mem::zeroed() };
+        info.StartupInfo.cb = size_of::<STARTUPINFOEXW>() as u32;
+
+        let mut inherited = Vec::new();
+        let std_handles = [&self.stdin, &self.stdout, &self.stderr];
+        if std_handles.iter().any(|handle| handle.is_some()) {
+            info.StartupInfo.dwFlags |= STARTF_USESTDHANDLES;
+            let targets: [&mut HANDLE; 3] = [
+                &mut info.StartupInfo.hStdInput,
+                &mut info.StartupInfo.hStdOutput,
+                &mut info.StartupInfo.hStdError,
+            ];
+            for (handle, target) in std_handles.iter().zip(targets) {
+                if let Some(handle) = handle {
+                    // The same handle is usually passed as the output and the error, it only
+                    // has to be duplicated once.
+                    let position = inherited.iter().position(|(source, _)| source == handle);
+                    let duplicate = match position {
+                        Some(i) => &inherited[i].1,
+                        None => {
+                            inherited.push((handle.clone(), duplicate_inheritable(handle)?));
+                            &inherited[inherited.len() - 1].1
+                        }
+                    };
+                    *target = **duplicate;
+                }
+            }
+        }
+
+        let mut startup_info = StartupInfo {
+            info,
+            attributes: Vec::new(),
+            handle_list: inherited.iter().map(|(_, handle)| **handle).collect(),
+            inherited: inherited.into_iter().map(|(_, handle)| handle).collect(),
+        };
+        let count = u32::from(!startup_info.handle_list.is_empty())
+            + u32::from(self.pseudo_console.is_some());
+        if count == 0 {
+            return Ok(startup_info);
+        }
+
+        let mut size: SIZE_T = 0;
+        // The first call only reports the size and fails with ERROR_INSUFFICIENT_BUFFER.
+        trace::call(
+            "InitializeProcThreadAttributeList",
+            ptr::null_mut(),
+            || unsafe { InitializeProcThreadAttributeList(ptr::null_mut(), count, 0, &mut size) },
+        );
+        // A `usize` buffer keeps the list pointer aligned.
+        startup_info.attributes =
+            vec![0usize; (size + size_of::<usize>() - 1) / size_of::<usize>()];
+        let list = startup_info.attributes.as_mut_ptr().cast();
+        result(trace::call(
+            "InitializeProcThreadAttributeList",
+            ptr::null_mut(),
+            || unsafe { InitializeProcThreadAttributeList(list, count, 0, &mut size) },
+        ))
+        .with_context("InitializeProcThreadAttributeList")?;
+        startup_info.info.lpAttributeList = list;
+
+        if !startup_info.handle_list.is_empty() {
+            let handles = startup_info.handle_list.as_mut_ptr();
+            let size = startup_info.handle_list.len() * size_of::<HANDLE>();
+            result(trace::call(
+                "UpdateProcThreadAttribute",
+                ptr::null_mut(),
+                || unsafe {
+                    UpdateProcThreadAttribute(
+                        list,
+                        0,
+                        PROC_THREAD_ATTRIBUTE_HANDLE_LIST,
+                        handles.cast(),
+                        size,
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                    )
+                },
+            ))
+            .with_context("UpdateProcThreadAttribute")?;
+        }
+        if let Some(hpcon) = self.pseudo_console {
+            result(trace::call(
+                "UpdateProcThreadAttribute",
+                ptr::null_mut(),
+                || unsafe {
+                    UpdateProcThreadAttribute(
+                        list,
+                        0,
+                        PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE,
+                        hpcon,
+                        size_of::<HPCON>(),
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                    )
+                },
+            ))
+            .with_context("UpdateProcThreadAttribute")?;
+        }
+        Ok(startup_info)
+    }
+}
+
+/// Duplicate `handle` as an inheritable handle that is closed when the duplicate is dropped.
+fn duplicate_inheritable(handle: &Handle) -> Result<Handle> {
+    let mut duplicate = ptr::null_mut();
+    result(trace::call("DuplicateHandle", **handle, || unsafe {
+        DuplicateHandle(
+            GetCurrentProcess(),
+            **handle,
+            GetCurrentProcess(),
+            &mut duplicate,
+            0,
+            TRUE,
+            DUPLICATE_SAME_ACCESS,
+        )
+    }))
+    .with_handle_context("DuplicateHandle", handle)?;
+    Ok(unsafe { Handle::from_raw(duplicate) })
+}
+
+/// The startup information of a child process, created with a [`StartupInfoBuilder`].
+///
+/// This owns the inheritable duplicates of the standard handles and the attribute list, so it
+/// must be kept alive until `CreateProcessW` returned. Dropping it afterwards closes the
+/// duplicates in this process, the child keeps its own copies.
+pub struct StartupInfo {
+    info: STARTUPINFOEXW,
+    attributes: Vec<usize>,
+    handle_list: Vec<HANDLE>,
+    inherited: Vec<Handle>,
+}
+
+impl StartupInfo {
+    /// Get a pointer to pass as `lpStartupInfo` to `CreateProcessW`.
+    pub fn as_mut_ptr(&mut self) -> *mut STARTUPINFOW {
+        (&mut self.info as *mut STARTUPINFOEXW).cast()
+    }
+
+    /// Get the flags to add to `dwCreationFlags` of `CreateProcessW`.
+    pub fn creation_flags(&self) -> u32 {
+        if self.info.lpAttributeList.is_null() {
+            0
+        } else {
+            EXTENDED_STARTUPINFO_PRESENT
+        }
+    }
+
+    /// Get the value to pass as `bInheritHandles` to `CreateProcessW`.
+    pub fn inherit_handles(&self) -> bool {
+        !self.inherited.is_empty()
+    }
+}
+
+impl Drop for StartupInfo {
+    fn drop(&mut self) {
+        if !self.info.lpAttributeList.is_null() {
+            trace::call(
+                "DeleteProcThreadAttributeList",
+                ptr::null_mut(),
+                || unsafe { DeleteProcThreadAttributeList(self.info.lpAttributeList) },
+            );
+        }
+    }
+}
+
+impl fmt::Debug for StartupInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StartupInfo")
+            .field("flags", &self.info.StartupInfo.dwFlags)
+            .field("inherited", &self.inherited)
+            .field("attributes", &!self.attributes.is_empty())
+            .finish()
+    }
+}
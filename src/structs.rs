@@ -1,11 +1,20 @@
+pub use self::char_info::CharInfo;
 pub use self::coord::Coord;
 pub use self::input::{
-    ButtonState, ControlKeyState, EventFlags, InputRecord, KeyEventRecord, MouseEvent,
+    ButtonState, ControlKeyState, EventFlags, FocusEventRecord, InputRecord, KeyEventRecord,
+    MenuEventRecord, MouseEvent, ReservedEvent, WindowBufferSizeRecord,
 };
+pub use self::input_builder::{KeyEventRecordBuilder, MouseEventBuilder};
 pub use self::size::Size;
+pub use self::surrogate::SurrogatePairer;
+pub use self::text_attributes::{LegacyColor, TextAttributes};
 pub use self::window_coords::WindowPositions;
 
+mod char_info;
 mod coord;
 mod input;
+mod input_builder;
 mod size;
+mod surrogate;
+mod text_attributes;
 mod window_coords;
@@ -0,0 +1,99 @@
+//! This module provides a type that represents a cell of a screen buffer.
+//! In WinAPI this is `CHAR_INFO`, which stores the character in a union.
+
+use std::char;
+
+use winapi::um::wincon::CHAR_INFO;
+
+use super::TextAttributes;
+
+/// The character and attributes of a [cell of a screen
+/// buffer](https://docs.microsoft.com/en-us/windows/console/char-info-str).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CharInfo {
+    /// The UTF-16 code unit in the cell.
+    ///
+    /// A character outside the basic multilingual plane takes two cells, each holding half of
+    /// the surrogate pair.
+    pub ch: u16,
+    /// The attributes of the cell.
+    pub attrs: TextAttributes,
+}
+
+impl CharInfo {
+    /// Create a cell with a character and its attributes.
+    ///
+    /// A character outside the basic multilingual plane doesn't fit in a single cell, it is
+    /// replaced with `U+FFFD`.
+    pub fn new(ch: char, attrs: TextAttributes) -> CharInfo {
+        let ch = u16::try_from(u32::from(ch)).unwrap_or(char::REPLACEMENT_CHARACTER as u16);
+        CharInfo { ch, attrs }
+    }
+
+    /// Get the character in the cell, or `U+FFFD` if it holds half of a surrogate pair.
+    pub fn to_char(&self) -> char {
+        char::from_u32(u32::from(self.ch)).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    /// Returns whether the cell is the first of the two cells of a wide character.
+    ///
+    /// This is the `COMMON_LVB_LEADING_BYTE` attribute, which console hosts only set for DBCS
+    /// code pages.
+    pub fn is_wide_leading(&self) -> bool {
+        self.attrs.is_leading_byte()
+    }
+
+    /// Returns whether the cell is the second of the two cells of a wide character.
+    ///
+    /// This is the `COMMON_LVB_TRAILING_BYTE` attribute, which console hosts only set for DBCS
+    /// code pages.
+    pub fn is_wide_trailing(&self) -> bool {
+        self.attrs.is_trailing_byte()
+    }
+}
+
+impl From<CHAR_INFO> for CharInfo {
+    fn from(info: CHAR_INFO) -> Self {
+        CharInfo {
+            ch: unsafe { *info.Char.UnicodeChar() },
+            attrs: TextAttributes::new(info.Attributes),
+        }
+    }
+}
+
+impl From<CharInfo> for CHAR_INFO {
+    fn from(info: CharInfo) -> Self {
+        let mut raw = CHAR_INFO::default();
+        unsafe { *raw.Char.UnicodeChar_mut() = info.ch };
+        raw.Attributes = info.attrs.bits();
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{CHAR_INFO, COMMON_LVB_LEADING_BYTE};
+
+    use super::CharInfo;
+    use crate::TextAttributes;
+
+    #[test]
+    fn test_raw_round_trip() {
+        let info = CharInfo::new('x', TextAttributes::new(0x001e));
+        let raw = CHAR_INFO::from(info);
+        assert_eq!(raw.Attributes, 0x001e);
+        assert_eq!(CharInfo::from(raw), info);
+        assert_eq!(info.to_char(), 'x');
+    }
+
+    #[test]
+    fn test_wide_and_astral() {
+        let info = CharInfo::new('漢', TextAttributes::new(COMMON_LVB_LEADING_BYTE | 0x7));
+        assert!(info.is_wide_leading());
+        assert!(!info.is_wide_trailing());
+        assert_eq!(
+            CharInfo::new('😀', TextAttributes::default()).to_char(),
+            char::REPLACEMENT_CHARACTER
+        );
+    }
+}
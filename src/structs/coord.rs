@@ -2,10 +2,15 @@
 //! For example, in WinAPI we have `COORD` which looks and feels inconvenient.
 //! This module provides also some trait implementations who will make parsing and working with `COORD` easier.
 
+use std::fmt;
+
 use winapi::um::wincon::COORD;
 
+use crate::{ConversionError, ConversionPolicy};
+
 /// This is type represents the position of something on a certain 'x' and 'y'.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Coord {
     /// the position on the x axis
     pub x: i16,
@@ -18,6 +23,36 @@ impl Coord {
     pub fn new(x: i16, y: i16) -> Coord {
         Coord { x, y }
     }
+
+    /// Create a new coordinate from unsigned positions, converting them with the given policy.
+    pub fn from_unsigned(
+        x: u32,
+        y: u32,
+        policy: ConversionPolicy,
+    ) -> Result<Coord, ConversionError> {
+        Ok(Coord::new(policy.to_i16(x)?, policy.to_i16(y)?))
+    }
+
+    /// Convert this coordinate to unsigned positions with the given policy.
+    ///
+    /// Unlike `Into<(u16, u16)>`, this doesn't silently wrap negative positions.
+    pub fn to_unsigned(self, policy: ConversionPolicy) -> Result<(u16, u16), ConversionError> {
+        Ok((policy.to_u16(self.x)?, policy.to_u16(self.y)?))
+    }
+
+    /// Convert this coordinate to `(x, y)` indices, for example to index a buffer of cells.
+    ///
+    /// This fails if one of the positions is negative.
+    pub fn to_usize(self) -> Result<(usize, usize), ConversionError> {
+        let (x, y) = self.to_unsigned(ConversionPolicy::Strict)?;
+        Ok((x.into(), y.into()))
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
 }
 
 impl From<COORD> for Coord {
@@ -35,8 +70,16 @@ impl From<Coord> for COORD {
     }
 }
 
-impl Into<(u16, u16)> for Coord {
-    fn into(self) -> (u16, u16) {
-        (self.x as u16, self.y as u16)
+impl From<Coord> for (u16, u16) {
+    fn from(coord: Coord) -> Self {
+        (coord.x as u16, coord.y as u16)
+    }
+}
+
+impl TryFrom<(u16, u16)> for Coord {
+    type Error = ConversionError;
+
+    fn try_from((x, y): (u16, u16)) -> Result<Self, Self::Error> {
+        Coord::from_unsigned(x.into(), y.into(), ConversionPolicy::Strict)
     }
 }
@@ -9,6 +9,9 @@
 //! - `InputEventType`
 //! - `INPUT_RECORD`
 
+use std::io;
+use std::ops::BitOr;
+
 use winapi::shared::minwindef::DWORD;
 use winapi::um::wincon::{
     FOCUS_EVENT, FOCUS_EVENT_RECORD, FROM_LEFT_1ST_BUTTON_PRESSED, FROM_LEFT_2ND_BUTTON_PRESSED,
@@ -17,11 +20,12 @@ use winapi::um::wincon::{
     RIGHTMOST_BUTTON_PRESSED, WINDOW_BUFFER_SIZE_EVENT, WINDOW_BUFFER_SIZE_RECORD,
 };
 
-use super::Coord;
-use crate::ScreenBuffer;
+use super::{Coord, WindowPositions};
+use crate::{ScreenBuffer, ScreenBufferInfo};
 
 /// A [keyboard input event](https://docs.microsoft.com/en-us/windows/console/key-event-record-str).
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct KeyEventRecord {
     /// If the key is pressed, this member is true. Otherwise, this member is
     /// false (the key is released).
@@ -37,7 +41,11 @@ pub struct KeyEventRecord {
     /// The virtual scan code of the given key that represents the
     /// device-dependent value generated by the keyboard hardware.
     pub virtual_scan_code: u16,
-    /// The translated Unicode character (as a WCHAR, or utf-16 value)
+    /// The translated Unicode character (as a WCHAR, or utf-16 value).
+    ///
+    /// This is the raw value, which can be half of a surrogate pair. Prefer
+    /// [`unicode_scalar`](KeyEventRecord::unicode_scalar) or
+    /// [`unicode_lossy`](KeyEventRecord::unicode_lossy) over casting it.
     pub u_char: u16,
     /// The state of the control keys.
     pub control_key_state: ControlKeyState,
@@ -63,6 +71,7 @@ impl KeyEventRecord {
 
 /// A [mouse input event](https://docs.microsoft.com/en-us/windows/console/mouse-event-record-str).
 #[derive(PartialEq, Debug, Copy, Clone, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MouseEvent {
     /// The position of the mouse when the event occurred in cell coordinates.
     pub mouse_position: Coord,
@@ -86,6 +95,28 @@ impl From<MOUSE_EVENT_RECORD> for MouseEvent {
     }
 }
 
+impl MouseEvent {
+    /// Returns whether the mouse was likely dragged outside the window of the screen buffer.
+    ///
+    /// While a button is held, the console keeps reporting the mouse when it leaves the window,
+    /// with the position clamped to the nearest cell of the window. This returns `true` for a
+    /// move with a button held on the edge of the [window](ScreenBufferInfo::terminal_window),
+    /// which can't be told apart from a drag that really stops on the edge.
+    pub fn is_clamped(&self, info: &ScreenBufferInfo) -> bool {
+        self.is_clamped_to(info.terminal_window())
+    }
+
+    pub(crate) fn is_clamped_to(&self, window: WindowPositions) -> bool {
+        let position = self.mouse_position;
+        self.event_flags == EventFlags::MouseMoved
+            && !self.button_state.release_button()
+            && (position.x <= window.left
+                || position.x >= window.right
+                || position.y <= window.top
+                || position.y >= window.bottom)
+    }
+}
+
 /// The status of the mouse buttons.
 /// The least significant bit corresponds to the leftmost mouse button.
 /// The next least significant bit corresponds to the rightmost mouse button.
@@ -117,6 +148,7 @@ impl From<MOUSE_EVENT_RECORD> for MouseEvent {
 ///
 /// [Ms Docs](https://docs.microsoft.com/en-us/windows/console/mouse-event-record-str#members)
 #[derive(PartialEq, Debug, Copy, Clone, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ButtonState {
     state: i32,
 }
@@ -130,6 +162,19 @@ impl From<DWORD> for ButtonState {
 }
 
 impl ButtonState {
+    /// Create a button state from its raw bitmask, see [`constants`](crate::constants) for the
+    /// bits.
+    pub const fn new(state: u32) -> ButtonState {
+        ButtonState {
+            state: state as i32,
+        }
+    }
+
+    /// Returns whether all buttons of `buttons` are pressed.
+    pub fn contains(&self, buttons: ButtonState) -> bool {
+        self.state & buttons.state == buttons.state
+    }
+
     /// Get whether no buttons are being pressed.
     pub fn release_button(&self) -> bool {
         self.state == 0
@@ -196,13 +241,48 @@ impl ButtonState {
 /// | The caps lock light is on | `0x0080` |
 /// | The key is [enhanced](https://docs.microsoft.com/en-us/windows/console/key-event-record-str#remarks) | `0x0100` |
 #[derive(PartialEq, Debug, Copy, Clone, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ControlKeyState(u32);
 
 impl ControlKeyState {
+    /// Create a control key state from its raw bitmask, see [`constants`](crate::constants) for
+    /// the bits.
+    pub const fn new(state: u32) -> ControlKeyState {
+        ControlKeyState(state)
+    }
+
+    /// Returns whether all keys and lights of `state` are set.
+    pub fn contains(&self, state: ControlKeyState) -> bool {
+        self.0 & state.0 == state.0
+    }
+
     /// Whether the control key has a state.
     pub fn has_state(&self, state: u32) -> bool {
         (state & self.0) != 0
     }
+
+    /// Returns the raw bitmask.
+    pub fn state(&self) -> u32 {
+        self.0
+    }
+}
+
+impl BitOr for ControlKeyState {
+    type Output = ControlKeyState;
+
+    fn bitor(self, rhs: ControlKeyState) -> ControlKeyState {
+        ControlKeyState(self.0 | rhs.0)
+    }
+}
+
+impl BitOr for ButtonState {
+    type Output = ButtonState;
+
+    fn bitor(self, rhs: ButtonState) -> ButtonState {
+        ButtonState {
+            state: self.state | rhs.state,
+        }
+    }
 }
 
 /// The type of mouse event.
@@ -211,6 +291,7 @@ impl ControlKeyState {
 ///
 /// [Ms Docs](https://docs.microsoft.com/en-us/windows/console/mouse-event-record-str#members)
 #[derive(PartialEq, Debug, Copy, Clone, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum EventFlags {
     PressOrRelease = 0x0000,
     /// The second click (button press) of a double-click occurred. The first click is returned as a regular button-press event.
@@ -244,27 +325,48 @@ impl From<DWORD> for EventFlags {
 /// The [size of console screen
 /// buffer](https://docs.microsoft.com/en-us/windows/console/window-buffer-size-record-str).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WindowBufferSizeRecord {
+    /// The size of the window of the screen buffer, which is the size of the terminal.
+    ///
+    /// When reading input this is queried from the active screen buffer, as the size in the
+    /// record is the size of the buffer, including the scrollback.
     pub size: Coord,
+    /// The size of the screen buffer as reported by the OS.
+    pub buffer_size: Coord,
 }
 
 impl From<WINDOW_BUFFER_SIZE_RECORD> for WindowBufferSizeRecord {
+    /// Convert the record without querying the window size, so
+    /// [`size`](WindowBufferSizeRecord::size) is the buffer size too.
     #[inline]
     fn from(record: WINDOW_BUFFER_SIZE_RECORD) -> Self {
         WindowBufferSizeRecord {
             size: record.dwSize.into(),
+            buffer_size: record.dwSize.into(),
         }
     }
 }
 
-/// A [focus event](https://docs.microsoft.com/en-us/windows/console/focus-event-record-str). This
-/// is used only internally by Windows and should be ignored.
+/// A [focus event](https://docs.microsoft.com/en-us/windows/console/focus-event-record-str).
+///
+/// The legacy console host uses these internally, and they should be ignored there. Windows
+/// Terminal reports real focus changes with them, use
+/// [`supports_focus_events`](crate::supports_focus_events) to tell the two apart.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct FocusEventRecord {
-    /// Reserved; do not use.
+    /// Whether the console gained focus, prefer [`FocusEventRecord::focus_gained`].
     pub set_focus: bool,
 }
 
+impl FocusEventRecord {
+    /// Returns whether the console gained (`true`) or lost (`false`) focus.
+    pub fn focus_gained(&self) -> bool {
+        self.set_focus
+    }
+}
+
 impl From<FOCUS_EVENT_RECORD> for FocusEventRecord {
     #[inline]
     fn from(record: FOCUS_EVENT_RECORD) -> Self {
@@ -277,6 +379,7 @@ impl From<FOCUS_EVENT_RECORD> for FocusEventRecord {
 /// A [menu event](https://docs.microsoft.com/en-us/windows/console/menu-event-record-str). This is
 /// used only internally by Windows and should be ignored.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MenuEventRecord {
     /// Reserved; do not use.
     pub command_id: u32,
@@ -297,6 +400,7 @@ impl From<MENU_EVENT_RECORD> for MenuEventRecord {
 /// or `PeekConsoleInput` function, or written to the input buffer by using the
 /// `WriteConsoleInput` function.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum InputRecord {
     /// A keyboard event occurred.
     KeyEvent(KeyEventRecord),
@@ -304,16 +408,43 @@ pub enum InputRecord {
     MouseEvent(MouseEvent),
     /// A console screen buffer was resized.
     WindowBufferSizeEvent(WindowBufferSizeRecord),
-    /// A focus event occured. This is used only internally by Windows and should be ignored.
+    /// A focus event occured. Only meaningful if
+    /// [`supports_focus_events`](crate::supports_focus_events) reports support.
     FocusEvent(FocusEventRecord),
     /// A menu event occurred. This is used only internally by Windows and should be ignored.
     MenuEvent(MenuEventRecord),
 }
 
-impl From<INPUT_RECORD> for InputRecord {
-    #[inline]
-    fn from(record: INPUT_RECORD) -> Self {
-        match record.EventType {
+/// A record the console host uses internally, which applications should not act on.
+///
+/// See [`InputRecord::reserved`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservedEvent {
+    /// A menu event.
+    Menu(MenuEventRecord),
+    /// A focus event, on hosts that don't report real focus changes.
+    Focus(FocusEventRecord),
+}
+
+impl InputRecord {
+    /// Get the record as a [`ReservedEvent`] if it is a menu or focus record.
+    ///
+    /// Focus records are real focus changes on some hosts, see
+    /// [`supports_focus_events`](crate::supports_focus_events).
+    pub fn reserved(&self) -> Option<ReservedEvent> {
+        match self {
+            InputRecord::MenuEvent(menu) => Some(ReservedEvent::Menu(*menu)),
+            InputRecord::FocusEvent(focus) => Some(ReservedEvent::Focus(*focus)),
+            _ => None,
+        }
+    }
+
+    /// Convert an `INPUT_RECORD` to an `InputRecord` without panicking.
+    ///
+    /// This fails if the record has an unknown event type, or if the screen buffer can't be
+    /// queried to correct the size of a resize event.
+    pub fn try_from_winapi(record: INPUT_RECORD) -> io::Result<Self> {
+        Ok(match record.EventType {
             KEY_EVENT => InputRecord::KeyEvent(KeyEventRecord::from_winapi(unsafe {
                 record.Event.KeyEvent()
             })),
@@ -321,7 +452,7 @@ impl From<INPUT_RECORD> for InputRecord {
             WINDOW_BUFFER_SIZE_EVENT => InputRecord::WindowBufferSizeEvent({
                 let mut buffer =
                     unsafe { WindowBufferSizeRecord::from(*record.Event.WindowBufferSizeEvent()) };
-                let window = ScreenBuffer::current().unwrap().info().unwrap();
+                let window = ScreenBuffer::current()?.info()?;
                 let screen_size = window.terminal_size();
 
                 buffer.size.y = screen_size.height;
@@ -331,7 +462,144 @@ impl From<INPUT_RECORD> for InputRecord {
             }),
             FOCUS_EVENT => InputRecord::FocusEvent(unsafe { *record.Event.FocusEvent() }.into()),
             MENU_EVENT => InputRecord::MenuEvent(unsafe { *record.Event.MenuEvent() }.into()),
-            code => panic!("Unexpected INPUT_RECORD EventType: {}", code),
+            code => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unexpected INPUT_RECORD EventType: {}", code),
+                ))
+            }
+        })
+    }
+}
+
+impl From<KeyEventRecord> for KEY_EVENT_RECORD {
+    fn from(record: KeyEventRecord) -> Self {
+        let mut raw = KEY_EVENT_RECORD {
+            bKeyDown: record.key_down.into(),
+            wRepeatCount: record.repeat_count,
+            wVirtualKeyCode: record.virtual_key_code,
+            wVirtualScanCode: record.virtual_scan_code,
+            uChar: Default::default(),
+            dwControlKeyState: record.control_key_state.0,
+        };
+        unsafe { *raw.uChar.UnicodeChar_mut() = record.u_char };
+        raw
+    }
+}
+
+impl From<MouseEvent> for MOUSE_EVENT_RECORD {
+    fn from(event: MouseEvent) -> Self {
+        MOUSE_EVENT_RECORD {
+            dwMousePosition: event.mouse_position.into(),
+            dwButtonState: event.button_state.state as DWORD,
+            dwControlKeyState: event.control_key_state.0,
+            dwEventFlags: event.event_flags as DWORD,
+        }
+    }
+}
+
+impl From<InputRecord> for INPUT_RECORD {
+    /// Convert an `InputRecord` to an `INPUT_RECORD`, for example to write it to the input buffer
+    /// with [`Console::write_input`](crate::Console::write_input).
+    fn from(record: InputRecord) -> Self {
+        let mut raw = INPUT_RECORD::default();
+        unsafe {
+            match record {
+                InputRecord::KeyEvent(event) => {
+                    raw.EventType = KEY_EVENT;
+                    *raw.Event.KeyEvent_mut() = event.into();
+                }
+                InputRecord::MouseEvent(event) => {
+                    raw.EventType = MOUSE_EVENT;
+                    *raw.Event.MouseEvent_mut() = event.into();
+                }
+                InputRecord::WindowBufferSizeEvent(event) => {
+                    raw.EventType = WINDOW_BUFFER_SIZE_EVENT;
+                    *raw.Event.WindowBufferSizeEvent_mut() = WINDOW_BUFFER_SIZE_RECORD {
+                        dwSize: event.buffer_size.into(),
+                    };
+                }
+                InputRecord::FocusEvent(event) => {
+                    raw.EventType = FOCUS_EVENT;
+                    *raw.Event.FocusEvent_mut() = FOCUS_EVENT_RECORD {
+                        bSetFocus: event.set_focus.into(),
+                    };
+                }
+                InputRecord::MenuEvent(event) => {
+                    raw.EventType = MENU_EVENT;
+                    *raw.Event.MenuEvent_mut() = MENU_EVENT_RECORD {
+                        dwCommandId: event.command_id,
+                    };
+                }
+            }
+        }
+        raw
+    }
+}
+
+impl From<INPUT_RECORD> for InputRecord {
+    /// Convert an `INPUT_RECORD` to an `InputRecord`.
+    ///
+    /// # Panics
+    ///
+    /// Panics where [`InputRecord::try_from_winapi`] would fail, which the crate itself uses for
+    /// the records it reads. Prefer it for records from an untrusted host.
+    #[inline]
+    fn from(record: INPUT_RECORD) -> Self {
+        InputRecord::try_from_winapi(record).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ButtonState, Coord, EventFlags, MouseEvent, WindowPositions};
+
+    #[test]
+    fn test_is_clamped() {
+        let window = WindowPositions {
+            left: 0,
+            top: 100,
+            right: 79,
+            bottom: 124,
+        };
+        let drag = |x, y| {
+            MouseEvent::builder()
+                .position(Coord::new(x, y))
+                .button_state(ButtonState::from(1))
+                .event_flags(EventFlags::MouseMoved)
+        };
+        assert!(drag(79, 110).build().is_clamped_to(window));
+        assert!(drag(10, 100).build().is_clamped_to(window));
+        assert!(!drag(10, 110).build().is_clamped_to(window));
+        assert!(!drag(79, 110)
+            .button_state(ButtonState::from(0))
+            .build()
+            .is_clamped_to(window));
+        assert!(!drag(79, 110)
+            .event_flags(EventFlags::PressOrRelease)
+            .build()
+            .is_clamped_to(window));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_round_trip() {
+        use arbitrary::Unstructured;
+        use winapi::um::wincon::INPUT_RECORD;
+
+        use crate::InputRecord;
+
+        for seed in 0..=u8::MAX {
+            let bytes: Vec<u8> = (0..64u8)
+                .map(|i| seed.wrapping_mul(31).wrapping_add(i.wrapping_mul(97)))
+                .collect();
+            let record: InputRecord = Unstructured::new(&bytes).arbitrary().unwrap();
+            // Reading a resize record queries the console, see the fuzz target.
+            if let InputRecord::WindowBufferSizeEvent(_) = record {
+                continue;
+            }
+            let raw = INPUT_RECORD::from(record.clone());
+            assert_eq!(InputRecord::try_from_winapi(raw).unwrap(), record);
         }
     }
 }
@@ -0,0 +1,184 @@
+//! This module provides builders to synthesize input records, for example to inject them into the
+//! input buffer with [`Console::write_input`](crate::Console::write_input).
+
+use winapi::um::wincon::{LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, SHIFT_PRESSED};
+
+use super::{ButtonState, ControlKeyState, Coord, EventFlags, KeyEventRecord, MouseEvent};
+
+impl KeyEventRecord {
+    /// Create a builder for a key event.
+    ///
+    /// ```no_run
+    /// # use crossterm_winapi::KeyEventRecord;
+    /// let ctrl_a = KeyEventRecord::builder().char('a').ctrl().key_down(true).build();
+    /// ```
+    pub fn builder() -> KeyEventRecordBuilder {
+        KeyEventRecordBuilder {
+            record: KeyEventRecord {
+                key_down: true,
+                repeat_count: 1,
+                virtual_key_code: 0,
+                virtual_scan_code: 0,
+                u_char: 0,
+                control_key_state: ControlKeyState::new(0),
+            },
+        }
+    }
+}
+
+/// A builder for a [`KeyEventRecord`].
+///
+/// By default the record is a key down event with a repeat count of `1` and no other state.
+#[derive(Clone, Debug)]
+pub struct KeyEventRecordBuilder {
+    record: KeyEventRecord,
+}
+
+impl KeyEventRecordBuilder {
+    /// Set the character the key translates to.
+    ///
+    /// A record holds a single UTF-16 code unit. For a character outside the basic multilingual
+    /// plane this sets the high surrogate, use [`u_char`](Self::u_char) on a second record to
+    /// send the low surrogate.
+    pub fn char(mut self, c: char) -> Self {
+        let mut units = [0; 2];
+        self.record.u_char = c.encode_utf16(&mut units)[0];
+        self
+    }
+
+    /// Set the raw UTF-16 code unit the key translates to.
+    pub fn u_char(mut self, u_char: u16) -> Self {
+        self.record.u_char = u_char;
+        self
+    }
+
+    /// Set whether the key is pressed (`true`) or released (`false`).
+    pub fn key_down(mut self, key_down: bool) -> Self {
+        self.record.key_down = key_down;
+        self
+    }
+
+    /// Set the repeat count.
+    pub fn repeat_count(mut self, repeat_count: u16) -> Self {
+        self.record.repeat_count = repeat_count;
+        self
+    }
+
+    /// Set the virtual-key code.
+    pub fn virtual_key_code(mut self, virtual_key_code: u16) -> Self {
+        self.record.virtual_key_code = virtual_key_code;
+        self
+    }
+
+    /// Set the virtual scan code.
+    pub fn virtual_scan_code(mut self, virtual_scan_code: u16) -> Self {
+        self.record.virtual_scan_code = virtual_scan_code;
+        self
+    }
+
+    /// Set the state of the control keys, replacing any state set before.
+    pub fn control_key_state(mut self, state: ControlKeyState) -> Self {
+        self.record.control_key_state = state;
+        self
+    }
+
+    /// Mark the left control key as pressed.
+    pub fn ctrl(self) -> Self {
+        self.add_control_key_state(LEFT_CTRL_PRESSED)
+    }
+
+    /// Mark the left alt key as pressed.
+    pub fn alt(self) -> Self {
+        self.add_control_key_state(LEFT_ALT_PRESSED)
+    }
+
+    /// Mark the shift key as pressed.
+    pub fn shift(self) -> Self {
+        self.add_control_key_state(SHIFT_PRESSED)
+    }
+
+    fn add_control_key_state(mut self, state: u32) -> Self {
+        let state = self.record.control_key_state.state() | state;
+        self.record.control_key_state = ControlKeyState::new(state);
+        self
+    }
+
+    /// Create the `KeyEventRecord`.
+    pub fn build(self) -> KeyEventRecord {
+        self.record
+    }
+}
+
+impl MouseEvent {
+    /// Create a builder for a mouse event.
+    ///
+    /// By default the event is a release of all buttons at `(0, 0)`.
+    pub fn builder() -> MouseEventBuilder {
+        MouseEventBuilder {
+            event: MouseEvent {
+                mouse_position: Coord::new(0, 0),
+                button_state: ButtonState::from(0),
+                control_key_state: ControlKeyState::new(0),
+                event_flags: EventFlags::PressOrRelease,
+            },
+        }
+    }
+}
+
+/// A builder for a [`MouseEvent`].
+#[derive(Clone, Debug)]
+pub struct MouseEventBuilder {
+    event: MouseEvent,
+}
+
+impl MouseEventBuilder {
+    /// Set the position of the mouse in cell coordinates.
+    pub fn position(mut self, position: Coord) -> Self {
+        self.event.mouse_position = position;
+        self
+    }
+
+    /// Set the state of the mouse buttons.
+    pub fn button_state(mut self, state: ButtonState) -> Self {
+        self.event.button_state = state;
+        self
+    }
+
+    /// Set the state of the control keys.
+    pub fn control_key_state(mut self, state: ControlKeyState) -> Self {
+        self.event.control_key_state = state;
+        self
+    }
+
+    /// Set the type of mouse event.
+    pub fn event_flags(mut self, flags: EventFlags) -> Self {
+        self.event.event_flags = flags;
+        self
+    }
+
+    /// Create the `MouseEvent`.
+    pub fn build(self) -> MouseEvent {
+        self.event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{INPUT_RECORD, LEFT_CTRL_PRESSED};
+
+    use crate::{InputRecord, KeyEventRecord};
+
+    #[test]
+    fn test_key_event_round_trip() {
+        let record = KeyEventRecord::builder().char('a').ctrl().build();
+        assert!(record.key_down);
+        assert_eq!(record.u_char, 'a' as u16);
+        assert!(record.control_key_state.has_state(LEFT_CTRL_PRESSED));
+
+        let raw = INPUT_RECORD::from(InputRecord::KeyEvent(record.clone()));
+        assert_eq!(
+            InputRecord::try_from_winapi(raw).unwrap(),
+            InputRecord::KeyEvent(record)
+        );
+    }
+}
@@ -2,8 +2,12 @@
 //! For example, in WinAPI we have `COORD` to represent screen/buffer size but this is a little inconvenient.
 //! This module provides some trait implementations who will make parsing and working with `COORD` easier.
 
+use std::fmt;
+
 use winapi::um::wincon::COORD;
 
+use crate::{ConversionError, ConversionPolicy};
+
 /// This is type represents the size of something in width and height.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Size {
@@ -16,6 +20,50 @@ impl Size {
     pub fn new(width: i16, height: i16) -> Size {
         Size { width, height }
     }
+
+    /// Create a new size instance, failing if the width or height is negative.
+    pub fn try_new(width: i16, height: i16) -> Result<Size, ConversionError> {
+        for value in [width, height] {
+            if value < 0 {
+                return Err(ConversionError::new(
+                    value.into(),
+                    "Size",
+                    "dimensions can't be negative",
+                ));
+            }
+        }
+        Ok(Size { width, height })
+    }
+
+    /// Create a new size from unsigned dimensions, converting them with the given policy.
+    pub fn from_unsigned(
+        width: u32,
+        height: u32,
+        policy: ConversionPolicy,
+    ) -> Result<Size, ConversionError> {
+        Ok(Size::new(policy.to_i16(width)?, policy.to_i16(height)?))
+    }
+
+    /// Convert this size to unsigned dimensions with the given policy.
+    ///
+    /// Unlike `Into<(u16, u16)>`, this doesn't silently wrap negative dimensions.
+    pub fn to_unsigned(self, policy: ConversionPolicy) -> Result<(u16, u16), ConversionError> {
+        Ok((policy.to_u16(self.width)?, policy.to_u16(self.height)?))
+    }
+
+    /// Convert this size to `(width, height)` as `usize`, for example to allocate a buffer of cells.
+    ///
+    /// This fails if one of the dimensions is negative.
+    pub fn to_usize(self) -> Result<(usize, usize), ConversionError> {
+        let (width, height) = self.to_unsigned(ConversionPolicy::Strict)?;
+        Ok((width.into(), height.into()))
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
 }
 
 impl From<COORD> for Size {
@@ -24,8 +72,33 @@ impl From<COORD> for Size {
     }
 }
 
-impl Into<(u16, u16)> for Size {
-    fn into(self) -> (u16, u16) {
-        (self.width as u16, self.height as u16)
+impl From<Size> for (u16, u16) {
+    fn from(size: Size) -> Self {
+        (size.width as u16, size.height as u16)
+    }
+}
+
+impl TryFrom<(u16, u16)> for Size {
+    type Error = ConversionError;
+
+    fn try_from((width, height): (u16, u16)) -> Result<Self, Self::Error> {
+        Size::from_unsigned(width.into(), height.into(), ConversionPolicy::Strict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Size;
+
+    #[test]
+    fn test_try_new_rejects_negatives() {
+        assert_eq!(Size::try_new(80, 25), Ok(Size::new(80, 25)));
+        assert!(Size::try_new(-1, 25).is_err());
+        assert!(Size::try_new(80, -1).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Size::new(80, 25).to_string(), "80x25");
     }
 }
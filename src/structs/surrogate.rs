@@ -0,0 +1,97 @@
+//! This module provides a helper to combine the surrogate pairs the console reports as two
+//! separate key events into one `char`.
+
+use std::char;
+
+use super::KeyEventRecord;
+
+/// Combines the two halves of a surrogate pair from consecutive key events.
+///
+/// The console reports a character outside the basic multilingual plane, like most emoji, as
+/// two key events that each carry one UTF-16 surrogate in
+/// [`u_char`](KeyEventRecord::u_char).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SurrogatePairer {
+    high: Option<u16>,
+}
+
+impl SurrogatePairer {
+    /// Create a pairer without a pending high surrogate.
+    pub fn new() -> SurrogatePairer {
+        SurrogatePairer::default()
+    }
+
+    /// Feed the next UTF-16 code unit and get the character it completes.
+    ///
+    /// A high surrogate is held until the next unit. An unpaired surrogate is dropped.
+    pub fn push(&mut self, unit: u16) -> Option<char> {
+        match (self.high.take(), unit) {
+            (_, 0xD800..=0xDBFF) => {
+                self.high = Some(unit);
+                None
+            }
+            (Some(high), 0xDC00..=0xDFFF) => char::decode_utf16([high, unit]).next()?.ok(),
+            (None, 0xDC00..=0xDFFF) => None,
+            (_, unit) => char::from_u32(u32::from(unit)),
+        }
+    }
+
+    /// Feed a key event, only key down events with a character are considered.
+    pub fn push_key(&mut self, record: &KeyEventRecord) -> Option<char> {
+        if !record.key_down || record.u_char == 0 {
+            return None;
+        }
+        self.push(record.u_char)
+    }
+
+    /// Returns whether a high surrogate is waiting for its low surrogate.
+    pub fn is_pending(&self) -> bool {
+        self.high.is_some()
+    }
+}
+
+impl KeyEventRecord {
+    /// Get the character of the key event, or `None` if there is none.
+    ///
+    /// This is strict: `None` is returned for `0` and for a surrogate, which is half of a
+    /// character, use a [`SurrogatePairer`] to combine these with the next key event.
+    pub fn unicode_scalar(&self) -> Option<char> {
+        match self.u_char {
+            0 => None,
+            unit => char::from_u32(u32::from(unit)),
+        }
+    }
+
+    /// Get the character of the key event, with a surrogate replaced by `U+FFFD`.
+    ///
+    /// Returns `'\0'` if the key produced no character.
+    pub fn unicode_lossy(&self) -> char {
+        char::from_u32(u32::from(self.u_char)).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SurrogatePairer;
+    use crate::KeyEventRecord;
+
+    #[test]
+    fn test_pair_surrogates() {
+        let mut pairer = SurrogatePairer::new();
+        assert_eq!(pairer.push(0xD83D), None);
+        assert!(pairer.is_pending());
+        assert_eq!(pairer.push(0xDE00), Some('😀'));
+        assert_eq!(pairer.push(0xDE00), None);
+        assert_eq!(pairer.push('a' as u16), Some('a'));
+    }
+
+    #[test]
+    fn test_unicode_scalar() {
+        let high = KeyEventRecord::builder().u_char(0xD83D).build();
+        assert_eq!(high.unicode_scalar(), None);
+        assert_eq!(high.unicode_lossy(), '\u{FFFD}');
+
+        let a = KeyEventRecord::builder().char('a').build();
+        assert_eq!(a.unicode_scalar(), Some('a'));
+    }
+}
@@ -0,0 +1,320 @@
+//! This module provides a type that represents the attributes of a cell in a screen buffer.
+//! In WinAPI the attributes are a plain `WORD` bitmask, this type gives names to its parts.
+
+use std::fmt;
+
+use winapi::um::wincon::{
+    BACKGROUND_INTENSITY, COMMON_LVB_GRID_HORIZONTAL, COMMON_LVB_GRID_LVERTICAL,
+    COMMON_LVB_GRID_RVERTICAL, COMMON_LVB_LEADING_BYTE, COMMON_LVB_REVERSE_VIDEO,
+    COMMON_LVB_TRAILING_BYTE, COMMON_LVB_UNDERSCORE, FOREGROUND_INTENSITY,
+};
+
+/// One of the 16 colors of the legacy console, in the order of the palette.
+///
+/// The value of a color is its index in the palette and the value of the color in the
+/// attributes: bit `0` is blue, bit `1` green, bit `2` red and bit `3` the intensity. The names
+/// are those of crossterm's `Color`, which are the colors of the default palette.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[repr(u8)]
+pub enum LegacyColor {
+    /// Index `0`, no color bits.
+    #[default]
+    Black = 0x0,
+    /// Index `1`, blue.
+    DarkBlue = 0x1,
+    /// Index `2`, green.
+    DarkGreen = 0x2,
+    /// Index `3`, blue and green.
+    DarkCyan = 0x3,
+    /// Index `4`, red.
+    DarkRed = 0x4,
+    /// Index `5`, blue and red.
+    DarkMagenta = 0x5,
+    /// Index `6`, green and red.
+    DarkYellow = 0x6,
+    /// Index `7`, blue, green and red.
+    Grey = 0x7,
+    /// Index `8`, the intensity only.
+    DarkGrey = 0x8,
+    /// Index `9`, intense blue.
+    Blue = 0x9,
+    /// Index `10`, intense green.
+    Green = 0xa,
+    /// Index `11`, intense blue and green.
+    Cyan = 0xb,
+    /// Index `12`, intense red.
+    Red = 0xc,
+    /// Index `13`, intense blue and red.
+    Magenta = 0xd,
+    /// Index `14`, intense green and red.
+    Yellow = 0xe,
+    /// Index `15`, all bits.
+    White = 0xf,
+}
+
+impl LegacyColor {
+    /// All colors, in the order of the palette.
+    pub const ALL: [LegacyColor; 16] = [
+        LegacyColor::Black,
+        LegacyColor::DarkBlue,
+        LegacyColor::DarkGreen,
+        LegacyColor::DarkCyan,
+        LegacyColor::DarkRed,
+        LegacyColor::DarkMagenta,
+        LegacyColor::DarkYellow,
+        LegacyColor::Grey,
+        LegacyColor::DarkGrey,
+        LegacyColor::Blue,
+        LegacyColor::Green,
+        LegacyColor::Cyan,
+        LegacyColor::Red,
+        LegacyColor::Magenta,
+        LegacyColor::Yellow,
+        LegacyColor::White,
+    ];
+
+    /// Get the color with the given palette index, only the low 4 bits are used.
+    pub const fn from_index(index: u8) -> LegacyColor {
+        LegacyColor::ALL[(index & 0xf) as usize]
+    }
+
+    /// Get the palette index of the color, from `0` to `15`.
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns whether the intensity bit of the color is set.
+    pub const fn is_intense(self) -> bool {
+        self as u8 & 0x8 != 0
+    }
+}
+
+/// The [character
+/// attributes](https://docs.microsoft.com/en-us/windows/console/console-screen-buffers#character-attributes)
+/// of a cell.
+///
+/// Besides the colors, the attributes contain the `COMMON_LVB_*` flags. How they are rendered
+/// depends on the console host:
+///
+/// - The console host of Windows 10 and later and Windows Terminal draw the underscore, the
+///   grid lines and reverse video in every code page.
+/// - The console host of earlier versions of Windows only draws them when a DBCS code page,
+///   like 932 for Japanese, is active.
+/// - The leading and trailing byte flags are set by the console host on the cells of wide
+///   characters in DBCS code pages, they don't change the rendering.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TextAttributes(u16);
+
+impl TextAttributes {
+    /// Create the attributes from the raw bitmask.
+    pub fn new(bits: u16) -> TextAttributes {
+        TextAttributes(bits)
+    }
+
+    /// Get the raw bitmask.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Create attributes with the given colors and no `COMMON_LVB_*` flags.
+    pub fn from_colors(foreground: LegacyColor, background: LegacyColor) -> TextAttributes {
+        TextAttributes(u16::from(foreground.index()) | u16::from(background.index()) << 4)
+    }
+
+    /// Get the foreground and background colors.
+    pub fn colors(&self) -> (LegacyColor, LegacyColor) {
+        (
+            LegacyColor::from_index(self.foreground()),
+            LegacyColor::from_index(self.background()),
+        )
+    }
+
+    /// Get these attributes with the foreground color replaced, keeping the other bits.
+    pub fn with_foreground(self, color: LegacyColor) -> TextAttributes {
+        TextAttributes(self.0 & !0x000f | u16::from(color.index()))
+    }
+
+    /// Get these attributes with the background color replaced, keeping the other bits.
+    pub fn with_background(self, color: LegacyColor) -> TextAttributes {
+        TextAttributes(self.0 & !0x00f0 | u16::from(color.index()) << 4)
+    }
+
+    /// Get the foreground color, a value from `0` to `15` where bit `3` is the intensity.
+    pub fn foreground(&self) -> u8 {
+        (self.0 & 0x000f) as u8
+    }
+
+    /// Get the background color, a value from `0` to `15` where bit `3` is the intensity.
+    pub fn background(&self) -> u8 {
+        ((self.0 & 0x00f0) >> 4) as u8
+    }
+
+    /// Returns whether the foreground color is intense.
+    pub fn is_foreground_intense(&self) -> bool {
+        self.0 & FOREGROUND_INTENSITY != 0
+    }
+
+    /// Returns whether the background color is intense.
+    pub fn is_background_intense(&self) -> bool {
+        self.0 & BACKGROUND_INTENSITY != 0
+    }
+
+    /// Returns whether the cell is underlined, `COMMON_LVB_UNDERSCORE`.
+    pub fn is_underlined(&self) -> bool {
+        self.0 & COMMON_LVB_UNDERSCORE != 0
+    }
+
+    /// Returns whether the foreground and background colors are swapped,
+    /// `COMMON_LVB_REVERSE_VIDEO`.
+    pub fn is_reverse_video(&self) -> bool {
+        self.0 & COMMON_LVB_REVERSE_VIDEO != 0
+    }
+
+    /// Returns whether a line is drawn at the top of the cell, `COMMON_LVB_GRID_HORIZONTAL`.
+    pub fn has_grid_horizontal(&self) -> bool {
+        self.0 & COMMON_LVB_GRID_HORIZONTAL != 0
+    }
+
+    /// Returns whether a line is drawn at the left of the cell, `COMMON_LVB_GRID_LVERTICAL`.
+    pub fn has_grid_left_vertical(&self) -> bool {
+        self.0 & COMMON_LVB_GRID_LVERTICAL != 0
+    }
+
+    /// Returns whether a line is drawn at the right of the cell, `COMMON_LVB_GRID_RVERTICAL`.
+    pub fn has_grid_right_vertical(&self) -> bool {
+        self.0 & COMMON_LVB_GRID_RVERTICAL != 0
+    }
+
+    /// Returns whether the cell is the first of a wide character, `COMMON_LVB_LEADING_BYTE`.
+    pub fn is_leading_byte(&self) -> bool {
+        self.0 & COMMON_LVB_LEADING_BYTE != 0
+    }
+
+    /// Returns whether the cell is the second of a wide character, `COMMON_LVB_TRAILING_BYTE`.
+    pub fn is_trailing_byte(&self) -> bool {
+        self.0 & COMMON_LVB_TRAILING_BYTE != 0
+    }
+
+    /// Get these attributes with the underscore set or cleared.
+    pub fn with_underline(self, underline: bool) -> TextAttributes {
+        self.with_flag(COMMON_LVB_UNDERSCORE, underline)
+    }
+
+    /// Get these attributes with reverse video set or cleared.
+    pub fn with_reverse_video(self, reverse: bool) -> TextAttributes {
+        self.with_flag(COMMON_LVB_REVERSE_VIDEO, reverse)
+    }
+
+    /// Get these attributes with the given grid lines set, clearing the others.
+    pub fn with_grid(self, horizontal: bool, left: bool, right: bool) -> TextAttributes {
+        self.with_flag(COMMON_LVB_GRID_HORIZONTAL, horizontal)
+            .with_flag(COMMON_LVB_GRID_LVERTICAL, left)
+            .with_flag(COMMON_LVB_GRID_RVERTICAL, right)
+    }
+
+    fn with_flag(self, flag: u16, set: bool) -> TextAttributes {
+        if set {
+            TextAttributes(self.0 | flag)
+        } else {
+            TextAttributes(self.0 & !flag)
+        }
+    }
+}
+
+impl From<u16> for TextAttributes {
+    fn from(bits: u16) -> Self {
+        TextAttributes(bits)
+    }
+}
+
+impl From<TextAttributes> for u16 {
+    fn from(attributes: TextAttributes) -> Self {
+        attributes.0
+    }
+}
+
+impl fmt::Display for TextAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{
+        BACKGROUND_BLUE, BACKGROUND_GREEN, BACKGROUND_INTENSITY, BACKGROUND_RED, FOREGROUND_BLUE,
+        FOREGROUND_GREEN, FOREGROUND_INTENSITY, FOREGROUND_RED,
+    };
+
+    use super::{LegacyColor, TextAttributes};
+
+    #[test]
+    fn test_colors() {
+        let attributes = TextAttributes::new(0x001e);
+        assert_eq!(attributes.foreground(), 0xe);
+        assert_eq!(attributes.background(), 0x1);
+        assert!(attributes.is_foreground_intense());
+        assert!(!attributes.is_background_intense());
+        assert_eq!(attributes.to_string(), "0x001e");
+    }
+
+    #[test]
+    fn test_lvb_flags() {
+        let attributes = TextAttributes::new(0x0007)
+            .with_underline(true)
+            .with_grid(true, false, true);
+        assert!(attributes.is_underlined());
+        assert!(attributes.has_grid_horizontal());
+        assert!(!attributes.has_grid_left_vertical());
+        assert!(attributes.has_grid_right_vertical());
+        assert!(!attributes.is_reverse_video());
+        assert_eq!(attributes.foreground(), 0x7);
+
+        let attributes = attributes
+            .with_underline(false)
+            .with_grid(false, false, false);
+        assert_eq!(attributes, TextAttributes::new(0x0007));
+    }
+
+    #[test]
+    fn test_legacy_colors() {
+        let white = FOREGROUND_RED | FOREGROUND_GREEN | FOREGROUND_BLUE | FOREGROUND_INTENSITY;
+        let attributes = TextAttributes::from_colors(LegacyColor::White, LegacyColor::DarkBlue);
+        assert_eq!(attributes.bits(), white | BACKGROUND_BLUE);
+        assert_eq!(
+            TextAttributes::from_colors(LegacyColor::DarkYellow, LegacyColor::Magenta).bits(),
+            FOREGROUND_RED
+                | FOREGROUND_GREEN
+                | BACKGROUND_RED
+                | BACKGROUND_BLUE
+                | BACKGROUND_INTENSITY
+        );
+        assert_eq!(
+            TextAttributes::new(FOREGROUND_GREEN | BACKGROUND_RED | BACKGROUND_GREEN).colors(),
+            (LegacyColor::DarkGreen, LegacyColor::DarkYellow)
+        );
+
+        for (index, &color) in LegacyColor::ALL.iter().enumerate() {
+            assert_eq!(usize::from(color.index()), index);
+            assert_eq!(LegacyColor::from_index(color.index()), color);
+            assert_eq!(color.is_intense(), index >= 8);
+            for &background in &LegacyColor::ALL {
+                let attributes = TextAttributes::from_colors(color, background);
+                assert_eq!(attributes.colors(), (color, background));
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_colors() {
+        let attributes = TextAttributes::new(0x0007)
+            .with_underline(true)
+            .with_foreground(LegacyColor::Red)
+            .with_background(LegacyColor::DarkCyan);
+        assert_eq!(
+            attributes.colors(),
+            (LegacyColor::Red, LegacyColor::DarkCyan)
+        );
+        assert!(attributes.is_underlined());
+    }
+}
@@ -2,8 +2,13 @@
 //! For example, in WinAPI we have `SMALL_RECT` to represent a window size but this is a little inconvenient.
 //! This module provides some trait implementations who will make parsing and working with `SMALL_RECT` easier.
 
+use std::fmt;
+
+use winapi::shared::windef::RECT;
 use winapi::um::wincon::{CONSOLE_SCREEN_BUFFER_INFO, SMALL_RECT};
 
+use crate::{ConversionError, Size};
+
 /// This is a wrapper for the locations of a rectangle.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct WindowPositions {
@@ -17,6 +22,109 @@ pub struct WindowPositions {
     pub top: i16,
 }
 
+impl WindowPositions {
+    /// Create a rectangle from its inclusive edges, failing if `right` is left of `left` or
+    /// `bottom` is above `top`.
+    pub fn try_new(
+        left: i16,
+        top: i16,
+        right: i16,
+        bottom: i16,
+    ) -> Result<WindowPositions, ConversionError> {
+        let positions = WindowPositions {
+            left,
+            right,
+            bottom,
+            top,
+        };
+        positions.validate()?;
+        Ok(positions)
+    }
+
+    /// Check that `right` is not left of `left` and `bottom` is not above `top`.
+    ///
+    /// The edges are inclusive, so a rectangle with equal edges covers one cell.
+    pub fn validate(&self) -> Result<(), ConversionError> {
+        if self.right < self.left {
+            return Err(ConversionError::new(
+                self.right.into(),
+                "WindowPositions",
+                "right is left of left",
+            ));
+        }
+        if self.bottom < self.top {
+            return Err(ConversionError::new(
+                self.bottom.into(),
+                "WindowPositions",
+                "bottom is above top",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Convert the cells to the pixel rectangle they cover, given the size of a cell in pixels.
+    ///
+    /// Unlike the edges of a `WindowPositions`, the right and bottom edges of a `RECT` are
+    /// exclusive.
+    pub fn to_pixel_rect(&self, cell_size: Size) -> RECT {
+        let (width, height) = (i32::from(cell_size.width), i32::from(cell_size.height));
+        RECT {
+            left: i32::from(self.left) * width,
+            top: i32::from(self.top) * height,
+            right: (i32::from(self.right) + 1) * width,
+            bottom: (i32::from(self.bottom) + 1) * height,
+        }
+    }
+
+    /// Get the cells that overlap the pixel rectangle `rect`, given the size of a cell in pixels.
+    ///
+    /// This fails if the cell size isn't positive, if `rect` is empty or if a position doesn't
+    /// fit in an `i16`.
+    pub fn from_pixel_rect(
+        rect: RECT,
+        cell_size: Size,
+    ) -> Result<WindowPositions, ConversionError> {
+        if cell_size.width <= 0 || cell_size.height <= 0 {
+            return Err(ConversionError::new(
+                i64::from(cell_size.width.min(cell_size.height)),
+                "WindowPositions",
+                "the cell size must be positive",
+            ));
+        }
+        if rect.right <= rect.left || rect.bottom <= rect.top {
+            return Err(ConversionError::new(
+                i64::from(rect.right - rect.left).min(i64::from(rect.bottom - rect.top)),
+                "WindowPositions",
+                "the pixel rectangle is empty",
+            ));
+        }
+
+        let (width, height) = (i32::from(cell_size.width), i32::from(cell_size.height));
+        let cell = |pixel: i32, size: i32| {
+            let cell = pixel.div_euclid(size);
+            i16::try_from(cell).map_err(|_| {
+                ConversionError::new(cell.into(), "i16", "the position doesn't fit in an i16")
+            })
+        };
+        WindowPositions::try_new(
+            cell(rect.left, width)?,
+            cell(rect.top, height)?,
+            cell(rect.right - 1, width)?,
+            cell(rect.bottom - 1, height)?,
+        )
+    }
+}
+
+impl fmt::Display for WindowPositions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {})-({}, {})",
+            self.left, self.top, self.right, self.bottom
+        )
+    }
+}
+
 impl From<CONSOLE_SCREEN_BUFFER_INFO> for WindowPositions {
     fn from(csbi: CONSOLE_SCREEN_BUFFER_INFO) -> Self {
         csbi.srWindow.into()
@@ -44,3 +152,57 @@ impl From<SMALL_RECT> for WindowPositions {
         }
     }
 }
+
+impl TryFrom<(RECT, Size)> for WindowPositions {
+    type Error = ConversionError;
+
+    /// Convert a pixel rectangle and a cell size, see [`WindowPositions::from_pixel_rect`].
+    fn try_from((rect, cell_size): (RECT, Size)) -> Result<Self, Self::Error> {
+        WindowPositions::from_pixel_rect(rect, cell_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::shared::windef::RECT;
+
+    use super::WindowPositions;
+    use crate::Size;
+
+    #[test]
+    fn test_validate() {
+        assert!(WindowPositions::try_new(0, 0, 0, 0).is_ok());
+        assert!(WindowPositions::try_new(5, 0, 4, 0).is_err());
+        assert!(WindowPositions::try_new(0, 5, 0, 4).is_err());
+    }
+
+    #[test]
+    fn test_pixel_rect_round_trip() {
+        let cell_size = Size::new(8, 16);
+        let positions = WindowPositions::try_new(2, 1, 4, 3).unwrap();
+        let rect = positions.to_pixel_rect(cell_size);
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (16, 16, 40, 64)
+        );
+        assert_eq!(
+            WindowPositions::from_pixel_rect(rect, cell_size),
+            Ok(positions)
+        );
+    }
+
+    #[test]
+    fn test_from_partial_pixel_rect() {
+        let rect = RECT {
+            left: 3,
+            top: 0,
+            right: 9,
+            bottom: 1,
+        };
+        assert_eq!(
+            WindowPositions::try_from((rect, Size::new(8, 16))),
+            WindowPositions::try_new(0, 0, 1, 0)
+        );
+        assert!(WindowPositions::from_pixel_rect(rect, Size::new(0, 16)).is_err());
+    }
+}
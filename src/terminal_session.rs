@@ -0,0 +1,336 @@
+use std::io::Result;
+
+use winapi::um::wincon::{
+    ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+    ENABLE_PROCESSED_INPUT, ENABLE_PROCESSED_OUTPUT, ENABLE_QUICK_EDIT_MODE,
+    ENABLE_VIRTUAL_TERMINAL_PROCESSING, ENABLE_WINDOW_INPUT,
+};
+
+use super::{
+    size_watcher::size_record, Console, ConsoleMode, Event, Events, Handle, InputRecord,
+    KeyUpPolicy, Overrides, ScreenBuffer,
+};
+
+/// A builder for a [`TerminalSession`].
+///
+/// By default all features are enabled, except for the
+/// [initial resize event](TerminalSessionBuilder::emit_initial_resize).
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalSessionBuilder {
+    options: Options,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    vt: bool,
+    raw: bool,
+    mouse: bool,
+    alternate_screen: bool,
+    key_up_policy: KeyUpPolicy,
+    suppress_key_repeats: bool,
+    initial_resize: bool,
+}
+
+impl Options {
+    /// Turn off the features forbidden by `overrides`.
+    fn restrict(mut self, overrides: Overrides) -> Options {
+        if overrides.force_legacy {
+            self.vt = false;
+        }
+        if overrides.disable_mouse {
+            self.mouse = false;
+        }
+        self
+    }
+}
+
+impl TerminalSessionBuilder {
+    /// Set whether virtual terminal sequences are processed by the screen buffer.
+    pub fn vt(mut self, enable: bool) -> Self {
+        self.options.vt = enable;
+        self
+    }
+
+    /// Set whether input is read without line editing, echo and Ctrl+C processing.
+    pub fn raw(mut self, enable: bool) -> Self {
+        self.options.raw = enable;
+        self
+    }
+
+    /// Set whether mouse events are reported, this disables quick edit mode.
+    pub fn mouse(mut self, enable: bool) -> Self {
+        self.options.mouse = enable;
+        self
+    }
+
+    /// Set whether the session draws on a new screen buffer, leaving the original contents
+    /// untouched.
+    pub fn alternate_screen(mut self, enable: bool) -> Self {
+        self.options.alternate_screen = enable;
+        self
+    }
+
+    /// Set how the [`Events`] of the session report key up records, see [`KeyUpPolicy`].
+    ///
+    /// The default is [`KeyUpPolicy::Emit`].
+    pub fn key_up_policy(mut self, policy: KeyUpPolicy) -> Self {
+        self.options.key_up_policy = policy;
+        self
+    }
+
+    /// Set whether the [`Events`] of the session drop the auto repeats of held keys, see
+    /// [`Events::suppress_key_repeats`].
+    pub fn suppress_key_repeats(mut self, suppress: bool) -> Self {
+        self.options.suppress_key_repeats = suppress;
+        self
+    }
+
+    /// Set whether the [`Events`] of the session start with a
+    /// [`WindowBufferSizeEvent`](InputRecord::WindowBufferSizeEvent) of the current size, so the
+    /// first layout can be done when handling the resize instead of querying the size up front.
+    ///
+    /// This is disabled by default.
+    pub fn emit_initial_resize(mut self, enable: bool) -> Self {
+        self.options.initial_resize = enable;
+        self
+    }
+
+    /// Create the `TerminalSession` for the console of the current process.
+    ///
+    /// The console is not changed until [`TerminalSession::enter`] is called.
+    pub fn build(self) -> Result<TerminalSession> {
+        Ok(TerminalSession {
+            input: Handle::current_in_handle()?,
+            output: ScreenBuffer::current()?,
+            options: self.options,
+            state: None,
+        })
+    }
+}
+
+/// The console input and output set up the way full screen applications need them.
+///
+/// [`enter`](TerminalSession::enter) switches to the alternate screen, enables virtual terminal
+/// processing, raw mode and mouse input, in that order. [`leave`](TerminalSession::leave) undoes
+/// this in reverse order, and is called when the session is dropped.
+///
+/// ```no_run
+/// # use crossterm_winapi::TerminalSession;
+/// # fn main() -> std::io::Result<()> {
+/// let mut session = TerminalSession::builder().mouse(false).build()?;
+/// session.enter()?;
+/// let record = session.input().read_single_input_event()?;
+/// session.leave()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TerminalSession {
+    input: Handle,
+    output: ScreenBuffer,
+    options: Options,
+    state: Option<EnteredState>,
+}
+
+#[derive(Debug)]
+struct EnteredState {
+    input_mode: u32,
+    output_mode: u32,
+    alternate: Option<ScreenBuffer>,
+    vt_enabled: bool,
+}
+
+impl TerminalSession {
+    /// Create a builder to configure a `TerminalSession`.
+    pub fn builder() -> TerminalSessionBuilder {
+        TerminalSessionBuilder {
+            options: Options {
+                vt: true,
+                raw: true,
+                mouse: true,
+                alternate_screen: true,
+                key_up_policy: KeyUpPolicy::Emit,
+                suppress_key_repeats: false,
+                initial_resize: false,
+            },
+        }
+    }
+
+    /// Set up the console, if the session wasn't entered already.
+    ///
+    /// If one of the steps fails, the steps that succeeded are undone.
+    pub fn enter(&mut self) -> Result<()> {
+        if self.state.is_some() {
+            return Ok(());
+        }
+
+        let input_mode = ConsoleMode::from(self.input.clone());
+        let original_input_mode = input_mode.mode()?;
+        let original_output_mode = ConsoleMode::from(self.output.handle().clone()).mode()?;
+
+        let alternate = if self.options.alternate_screen {
+            let alternate = ScreenBuffer::create()?;
+            alternate.show()?;
+            Some(alternate)
+        } else {
+            None
+        };
+
+        // From here on `leave` restores whatever was changed.
+        self.state = Some(EnteredState {
+            input_mode: original_input_mode,
+            output_mode: original_output_mode,
+            alternate,
+            vt_enabled: false,
+        });
+
+        let result = self.apply_modes(&input_mode, original_input_mode, original_output_mode);
+        if result.is_err() {
+            let _ = self.leave();
+        }
+        result
+    }
+
+    fn apply_modes(&mut self, input_mode: &ConsoleMode, input: u32, output: u32) -> Result<()> {
+        let options = self.options.restrict(Overrides::current());
+        let output_mode = ConsoleMode::from(self.active().handle().clone());
+        let new_output_mode = session_output_mode(output, options);
+        // Consoles that don't support virtual terminal sequences reject the whole mode.
+        let vt_enabled = match output_mode.set_mode(new_output_mode) {
+            Ok(()) => output_mode.mode()? & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0,
+            Err(_) if options.vt => {
+                output_mode.set_mode(new_output_mode & !ENABLE_VIRTUAL_TERMINAL_PROCESSING)?;
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(state) = &mut self.state {
+            state.vt_enabled = vt_enabled;
+        }
+
+        input_mode.set_mode(session_input_mode(input, options))
+    }
+
+    /// Restore the console to the state before [`enter`](TerminalSession::enter), if the session
+    /// was entered.
+    pub fn leave(&mut self) -> Result<()> {
+        let state = match self.state.take() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+
+        let input = ConsoleMode::from(self.input.clone()).set_mode(state.input_mode);
+        let output = match &state.alternate {
+            Some(_) => self.output.show(),
+            None => ConsoleMode::from(self.output.handle().clone()).set_mode(state.output_mode),
+        };
+        input.and(output)
+    }
+
+    /// Returns whether the session is entered.
+    pub fn is_entered(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Returns whether virtual terminal processing was enabled when entering the session.
+    ///
+    /// This is `false` if the session is not entered, or if the console doesn't support virtual
+    /// terminal sequences.
+    pub fn vt_enabled(&self) -> bool {
+        self.state.as_ref().map_or(false, |state| state.vt_enabled)
+    }
+
+    /// Get the console input buffer.
+    pub fn input(&self) -> Console {
+        Console::from(self.input.clone())
+    }
+
+    /// Create a stream of events from the console input buffer, with the [`KeyUpPolicy`] and key
+    /// repeat handling of the session.
+    ///
+    /// If enabled with [`TerminalSessionBuilder::emit_initial_resize`], the first event is the
+    /// size of the screen buffer the session draws on.
+    pub fn events(&self) -> Result<Events> {
+        let mut events = Events::with_console(self.input())?;
+        events.key_up_policy(self.options.key_up_policy);
+        events.suppress_key_repeats(self.options.suppress_key_repeats);
+        if self.options.initial_resize {
+            let record = size_record(&self.active().info()?);
+            events
+                .sender()
+                .send(Event::Input(InputRecord::WindowBufferSizeEvent(record)))?;
+        }
+        Ok(events)
+    }
+
+    /// Get the screen buffer the session draws on, the alternate screen if it was entered.
+    pub fn output(&self) -> &ScreenBuffer {
+        self.active()
+    }
+
+    fn active(&self) -> &ScreenBuffer {
+        match &self.state {
+            Some(EnteredState {
+                alternate: Some(alternate),
+                ..
+            }) => alternate,
+            _ => &self.output,
+        }
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        let _ = self.leave();
+    }
+}
+
+fn session_input_mode(mode: u32, options: Options) -> u32 {
+    let mut mode = mode | ENABLE_WINDOW_INPUT;
+    if options.raw {
+        mode &= !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT);
+    }
+    if options.mouse {
+        mode = (mode | ENABLE_MOUSE_INPUT | ENABLE_EXTENDED_FLAGS) & !ENABLE_QUICK_EDIT_MODE;
+    }
+    mode
+}
+
+fn session_output_mode(mode: u32, options: Options) -> u32 {
+    if options.vt {
+        mode | ENABLE_PROCESSED_OUTPUT | ENABLE_VIRTUAL_TERMINAL_PROCESSING
+    } else {
+        mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::wincon::{
+        ENABLE_ECHO_INPUT, ENABLE_EXTENDED_FLAGS, ENABLE_LINE_INPUT, ENABLE_MOUSE_INPUT,
+        ENABLE_PROCESSED_INPUT, ENABLE_QUICK_EDIT_MODE, ENABLE_WINDOW_INPUT,
+    };
+
+    use super::{session_input_mode, TerminalSession};
+
+    #[test]
+    fn test_session_input_mode() {
+        let cooked = ENABLE_PROCESSED_INPUT
+            | ENABLE_LINE_INPUT
+            | ENABLE_ECHO_INPUT
+            | ENABLE_QUICK_EDIT_MODE
+            | ENABLE_EXTENDED_FLAGS;
+
+        let options = TerminalSession::builder().options;
+        assert_eq!(
+            session_input_mode(cooked, options),
+            ENABLE_WINDOW_INPUT | ENABLE_MOUSE_INPUT | ENABLE_EXTENDED_FLAGS
+        );
+
+        let options = TerminalSession::builder().raw(false).mouse(false).options;
+        assert_eq!(
+            session_input_mode(cooked, options),
+            cooked | ENABLE_WINDOW_INPUT
+        );
+    }
+}
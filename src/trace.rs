@@ -0,0 +1,120 @@
+//! Instrumentation of the WinAPI calls made by this crate.
+//!
+//! With the `tracing` feature enabled, every call wrapped by [`call`] emits a `trace` level event
+//! with the name of the API, the handle it was called with, the return value, the value of
+//! `GetLastError` right after the call and how long the call took. Without the feature [`call`]
+//! simply runs the closure.
+//!
+//! The only calls that aren't wrapped are `GetLastError` and `SetLastError` in [`call`] itself,
+//! which preserve the error of the traced call.
+
+use winapi::um::winnt::HANDLE;
+
+/// Run the WinAPI call `f` named `api` on `handle`, tracing it if the `tracing` feature is enabled.
+///
+/// Calls that don't operate on a handle pass a null handle.
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn call<T>(_api: &'static str, _handle: HANDLE, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// Run the WinAPI call `f` named `api` on `handle`, tracing it if the `tracing` feature is enabled.
+///
+/// Calls that don't operate on a handle pass a null handle.
+#[cfg(feature = "tracing")]
+pub(crate) fn call<T: ReturnValue>(api: &'static str, handle: HANDLE, f: impl FnOnce() -> T) -> T {
+    use std::time::Instant;
+    use winapi::um::errhandlingapi::{GetLastError, SetLastError};
+
+    if !tracing::enabled!(tracing::Level::TRACE) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let ret = f();
+    let elapsed = start.elapsed();
+    let last_error = unsafe { GetLastError() };
+
+    tracing::trace!(
+        api,
+        handle = ?handle,
+        ret = ret.as_i64(),
+        last_error,
+        duration_us = elapsed.as_micros() as u64,
+        "winapi call"
+    );
+
+    // Emitting the event may have called other APIs, the caller still expects to see the error of
+    // its own call.
+    unsafe { SetLastError(last_error) };
+    ret
+}
+
+/// Report that the WinAPI call named `api` on `handle` failed in a way this crate worked around,
+/// as a `warn` level event if the `tracing` feature is enabled.
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn warn(_api: &'static str, _handle: HANDLE, _message: &str) {}
+
+/// Report that the WinAPI call named `api` on `handle` failed in a way this crate worked around,
+/// as a `warn` level event if the `tracing` feature is enabled.
+#[cfg(feature = "tracing")]
+pub(crate) fn warn(api: &'static str, handle: HANDLE, message: &str) {
+    tracing::warn!(api, handle = ?handle, "{}", message);
+}
+
+/// A value returned by a WinAPI call that can be recorded in a trace event.
+#[cfg(feature = "tracing")]
+pub(crate) trait ReturnValue {
+    fn as_i64(&self) -> i64;
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for () {
+    fn as_i64(&self) -> i64 {
+        0
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for i32 {
+    fn as_i64(&self) -> i64 {
+        i64::from(*self)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for u32 {
+    fn as_i64(&self) -> i64 {
+        i64::from(*self)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for HANDLE {
+    fn as_i64(&self) -> i64 {
+        *self as i64
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for winapi::um::wincontypes::COORD {
+    fn as_i64(&self) -> i64 {
+        (i64::from(self.X) << 16) | i64::from(self.Y as u16)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for winapi::shared::windef::HWND {
+    fn as_i64(&self) -> i64 {
+        *self as i64
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ReturnValue for winapi::shared::windef::HMONITOR {
+    fn as_i64(&self) -> i64 {
+        *self as i64
+    }
+}
@@ -0,0 +1,469 @@
+//! This module contains a decoder for the escape sequences the console reports with
+//! `ENABLE_VIRTUAL_TERMINAL_INPUT`.
+
+use std::time::{Duration, Instant};
+
+use super::{KeyEventRecord, SurrogatePairer};
+
+const ESC: char = '\x1b';
+
+/// A key decoded by a [`VtInputDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum VtKey {
+    /// A character. Ctrl+letter is reported as the letter with [`VtModifiers::ctrl`] set.
+    Char(char),
+    /// The Enter key.
+    Enter,
+    /// The Tab key.
+    Tab,
+    /// Shift+Tab.
+    BackTab,
+    /// The Backspace key.
+    Backspace,
+    /// The Escape key.
+    Escape,
+    /// The up arrow.
+    Up,
+    /// The down arrow.
+    Down,
+    /// The left arrow.
+    Left,
+    /// The right arrow.
+    Right,
+    /// The Home key.
+    Home,
+    /// The End key.
+    End,
+    /// The Insert key.
+    Insert,
+    /// The Delete key.
+    Delete,
+    /// The Page Up key.
+    PageUp,
+    /// The Page Down key.
+    PageDown,
+    /// A function key, from `1` to `12`.
+    F(u8),
+    /// An escape sequence this decoder doesn't know, without the leading escape.
+    Unknown(String),
+}
+
+/// The modifiers of a [`VtKeyEvent`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VtModifiers {
+    /// Shift was held.
+    pub shift: bool,
+    /// Alt was held.
+    pub alt: bool,
+    /// Ctrl was held.
+    pub ctrl: bool,
+}
+
+impl VtModifiers {
+    /// Decode the modifier parameter of a CSI sequence, which is `1` plus a bitmask of shift
+    /// (`1`), alt (`2`) and ctrl (`4`).
+    fn from_param(param: u32) -> VtModifiers {
+        let bits = param.saturating_sub(1);
+        VtModifiers {
+            shift: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            ctrl: bits & 4 != 0,
+        }
+    }
+}
+
+/// A key press decoded by a [`VtInputDecoder`], or translated from a key event record with
+/// [`VtKeyEvent::from_key_record`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VtKeyEvent {
+    /// The key.
+    pub key: VtKey,
+    /// The modifiers held with the key.
+    pub modifiers: VtModifiers,
+}
+
+impl VtKeyEvent {
+    fn new(key: VtKey) -> VtKeyEvent {
+        VtKeyEvent {
+            key,
+            modifiers: VtModifiers::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi(String),
+    Ss3,
+}
+
+/// Reassembles the escape sequences reported with `ENABLE_VIRTUAL_TERMINAL_INPUT` into keys.
+///
+/// With VT input, the console reports keys like the arrows as a series of key events that each
+/// carry one character of an escape sequence, for example `ESC [ A` for the up arrow. Feed every
+/// key event to [`push`](VtInputDecoder::push) to get the keys back.
+///
+/// A lone escape can't be told apart from the start of a sequence until the next character
+/// arrives. Call [`flush`](VtInputDecoder::flush) when no more input is available, for example
+/// when [`Console::number_of_console_input_events`](crate::Console::number_of_console_input_events)
+/// returns `0`, to report it as [`VtKey::Escape`].
+#[derive(Debug, Clone)]
+pub struct VtInputDecoder {
+    state: State,
+    surrogates: SurrogatePairer,
+}
+
+impl Default for VtInputDecoder {
+    fn default() -> Self {
+        VtInputDecoder::new()
+    }
+}
+
+impl VtInputDecoder {
+    /// Create a decoder that is not in the middle of a sequence.
+    pub fn new() -> VtInputDecoder {
+        VtInputDecoder {
+            state: State::Ground,
+            surrogates: SurrogatePairer::new(),
+        }
+    }
+
+    /// Feed a key event, returning the keys it completes.
+    ///
+    /// Only key down events with a character are considered, their character is repeated
+    /// [`repeat_count`](KeyEventRecord::repeat_count) times.
+    pub fn push(&mut self, record: &KeyEventRecord) -> Vec<VtKeyEvent> {
+        let mut events = Vec::new();
+        if let Some(c) = self.surrogates.push_key(record) {
+            for _ in 0..record.repeat_count.max(1) {
+                self.push_char(c, &mut events);
+            }
+        }
+        events
+    }
+
+    /// Feed a character, returning the keys it completes.
+    pub fn push_char(&mut self, c: char, events: &mut Vec<VtKeyEvent>) {
+        match std::mem::replace(&mut self.state, State::Ground) {
+            State::Ground => match c {
+                ESC => self.state = State::Escape,
+                c => events.push(VtKeyEvent::new(VtKey::Char(c)).decode_control()),
+            },
+            State::Escape => match c {
+                '[' => self.state = State::Csi(String::new()),
+                'O' => self.state = State::Ss3,
+                ESC => {
+                    events.push(VtKeyEvent::new(VtKey::Escape));
+                    self.state = State::Escape;
+                }
+                c => {
+                    let mut event = VtKeyEvent::new(VtKey::Char(c)).decode_control();
+                    event.modifiers.alt = true;
+                    events.push(event);
+                }
+            },
+            State::Csi(mut params) => match c {
+                '0'..='9' | ';' => {
+                    params.push(c);
+                    self.state = State::Csi(params);
+                }
+                '\x40'..='\x7e' => events.push(decode_csi(&params, c)),
+                c => events.push(VtKeyEvent::new(VtKey::Unknown(format!("[{}{}", params, c)))),
+            },
+            State::Ss3 => events.push(decode_ss3(c)),
+        }
+    }
+
+    /// Report a pending escape as [`VtKey::Escape`], and an incomplete sequence as
+    /// [`VtKey::Unknown`].
+    pub fn flush(&mut self) -> Option<VtKeyEvent> {
+        match std::mem::replace(&mut self.state, State::Ground) {
+            State::Ground => None,
+            State::Escape => Some(VtKeyEvent::new(VtKey::Escape)),
+            State::Csi(params) => Some(VtKeyEvent::new(VtKey::Unknown(format!("[{}", params)))),
+            State::Ss3 => Some(VtKeyEvent::new(VtKey::Unknown("O".to_owned()))),
+        }
+    }
+
+    /// Returns whether the decoder is in the middle of an escape sequence.
+    pub fn is_pending(&self) -> bool {
+        self.state != State::Ground || self.surrogates.is_pending()
+    }
+}
+
+/// Tells a lone Escape key from the start of an escape sequence by waiting for the rest of it.
+///
+/// A [`VtInputDecoder`] can only report a pending escape as [`VtKey::Escape`] once it is told
+/// that no more input follows. The characters of a sequence arrive in quick succession, while a
+/// person pressing Escape and then `[` takes much longer, so this reports a pending escape, or an
+/// incomplete sequence, once no character arrived for the delay. Around 50ms works for local
+/// consoles, remote sessions may need more.
+///
+/// ```no_run
+/// # use std::time::{Duration, Instant};
+/// # use crossterm_winapi::{EscDisambiguator, Event, Events, Handle, InputRecord};
+/// # fn main() -> std::io::Result<()> {
+/// let mut events = Events::new(Handle::current_in_handle()?)?;
+/// let mut keys = EscDisambiguator::new(Duration::from_millis(50));
+/// loop {
+///     if let Some(key) = keys.poll(Instant::now()) {
+///         println!("{:?}", key);
+///     }
+///     if let Some(Event::Input(InputRecord::KeyEvent(record))) =
+///         events.poll(keys.timeout(Instant::now()))?
+///     {
+///         for key in keys.push(&record, Instant::now()) {
+///             println!("{:?}", key);
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EscDisambiguator {
+    decoder: VtInputDecoder,
+    delay: Duration,
+    since: Option<Instant>,
+}
+
+impl EscDisambiguator {
+    /// Create a disambiguator that reports a pending escape after `delay` without input.
+    pub fn new(delay: Duration) -> EscDisambiguator {
+        EscDisambiguator {
+            decoder: VtInputDecoder::new(),
+            delay,
+            since: None,
+        }
+    }
+
+    /// Get the delay.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Feed a key event that was read at `now`, returning the keys it completes.
+    pub fn push(&mut self, record: &KeyEventRecord, now: Instant) -> Vec<VtKeyEvent> {
+        let events = self.decoder.push(record);
+        self.since = if self.decoder.is_pending() {
+            Some(now)
+        } else {
+            None
+        };
+        events
+    }
+
+    /// Report the pending escape or incomplete sequence if the delay has passed at `now`.
+    pub fn poll(&mut self, now: Instant) -> Option<VtKeyEvent> {
+        match self.since {
+            Some(since) if now.saturating_duration_since(since) >= self.delay => self.flush(),
+            _ => None,
+        }
+    }
+
+    /// Report the pending escape or incomplete sequence regardless of the delay.
+    pub fn flush(&mut self) -> Option<VtKeyEvent> {
+        self.since = None;
+        self.decoder.flush()
+    }
+
+    /// Get the time left at `now` until a pending escape is reported, or `None` if nothing is
+    /// pending.
+    ///
+    /// This is meant to be used as the timeout when waiting for the next record.
+    pub fn timeout(&self, now: Instant) -> Option<Duration> {
+        self.since
+            .map(|since| (since + self.delay).saturating_duration_since(now))
+    }
+
+    /// Returns whether an escape or an incomplete sequence is waiting for the delay.
+    pub fn is_pending(&self) -> bool {
+        self.since.is_some()
+    }
+}
+
+impl VtKeyEvent {
+    /// Turn the control characters of a plain character event into keys.
+    fn decode_control(mut self) -> VtKeyEvent {
+        if let VtKey::Char(c) = self.key {
+            self.key = match c {
+                '\r' | '\n' => VtKey::Enter,
+                '\t' => VtKey::Tab,
+                '\x7f' | '\x08' => VtKey::Backspace,
+                '\x00' => {
+                    self.modifiers.ctrl = true;
+                    VtKey::Char(' ')
+                }
+                '\x01'..='\x1a' => {
+                    self.modifiers.ctrl = true;
+                    VtKey::Char((b'a' + c as u8 - 1) as char)
+                }
+                c => VtKey::Char(c),
+            };
+        }
+        self
+    }
+}
+
+fn decode_csi(params: &str, last: char) -> VtKeyEvent {
+    let mut numbers = params.split(';').map(|param| param.parse::<u32>().ok());
+    let first = numbers.next().flatten();
+    let modifiers = numbers.next().flatten().map(VtModifiers::from_param);
+
+    let key = match (last, first) {
+        ('A', _) => VtKey::Up,
+        ('B', _) => VtKey::Down,
+        ('C', _) => VtKey::Right,
+        ('D', _) => VtKey::Left,
+        ('H', _) => VtKey::Home,
+        ('F', _) => VtKey::End,
+        ('Z', _) => {
+            return VtKeyEvent {
+                key: VtKey::BackTab,
+                modifiers: VtModifiers {
+                    shift: true,
+                    ..VtModifiers::default()
+                },
+            }
+        }
+        ('P', _) => VtKey::F(1),
+        ('Q', _) => VtKey::F(2),
+        ('R', _) => VtKey::F(3),
+        ('S', _) => VtKey::F(4),
+        ('~', Some(1 | 7)) => VtKey::Home,
+        ('~', Some(2)) => VtKey::Insert,
+        ('~', Some(3)) => VtKey::Delete,
+        ('~', Some(4 | 8)) => VtKey::End,
+        ('~', Some(5)) => VtKey::PageUp,
+        ('~', Some(6)) => VtKey::PageDown,
+        ('~', Some(n @ 11..=15)) => VtKey::F((n - 10) as u8),
+        ('~', Some(n @ 17..=21)) => VtKey::F((n - 11) as u8),
+        ('~', Some(n @ 23..=24)) => VtKey::F((n - 12) as u8),
+        _ => VtKey::Unknown(format!("[{}{}", params, last)),
+    };
+    VtKeyEvent {
+        key,
+        modifiers: modifiers.unwrap_or_default(),
+    }
+}
+
+fn decode_ss3(c: char) -> VtKeyEvent {
+    VtKeyEvent::new(match c {
+        'A' => VtKey::Up,
+        'B' => VtKey::Down,
+        'C' => VtKey::Right,
+        'D' => VtKey::Left,
+        'H' => VtKey::Home,
+        'F' => VtKey::End,
+        'P' => VtKey::F(1),
+        'Q' => VtKey::F(2),
+        'R' => VtKey::F(3),
+        'S' => VtKey::F(4),
+        c => VtKey::Unknown(format!("O{}", c)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{EscDisambiguator, VtInputDecoder, VtKey, VtKeyEvent, VtModifiers};
+    use crate::KeyEventRecord;
+
+    fn decode(input: &str) -> Vec<VtKeyEvent> {
+        let mut decoder = VtInputDecoder::new();
+        let mut events = Vec::new();
+        for unit in input.encode_utf16() {
+            events.extend(decoder.push(&KeyEventRecord::builder().u_char(unit).build()));
+        }
+        events.extend(decoder.flush());
+        events
+    }
+
+    fn keys(input: &str) -> Vec<VtKey> {
+        decode(input).into_iter().map(|event| event.key).collect()
+    }
+
+    #[test]
+    fn test_plain_characters() {
+        assert_eq!(
+            keys("a😀\r\x7f"),
+            [
+                VtKey::Char('a'),
+                VtKey::Char('😀'),
+                VtKey::Enter,
+                VtKey::Backspace
+            ]
+        );
+        let ctrl_c = &decode("\x03")[0];
+        assert_eq!(ctrl_c.key, VtKey::Char('c'));
+        assert!(ctrl_c.modifiers.ctrl);
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            keys("\x1b[A\x1bOB\x1b[3~\x1b[15~\x1b[Z"),
+            [
+                VtKey::Up,
+                VtKey::Down,
+                VtKey::Delete,
+                VtKey::F(5),
+                VtKey::BackTab
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modifiers() {
+        assert_eq!(
+            decode("\x1b[1;5C")[0],
+            VtKeyEvent {
+                key: VtKey::Right,
+                modifiers: VtModifiers {
+                    ctrl: true,
+                    ..VtModifiers::default()
+                },
+            }
+        );
+        let alt_x = &decode("\x1bx")[0];
+        assert_eq!(alt_x.key, VtKey::Char('x'));
+        assert!(alt_x.modifiers.alt);
+    }
+
+    #[test]
+    fn test_escape_and_unknown() {
+        assert_eq!(keys("\x1b"), [VtKey::Escape]);
+        assert_eq!(keys("\x1b\x1b[D"), [VtKey::Escape, VtKey::Left]);
+        assert_eq!(keys("\x1b[99x"), [VtKey::Unknown("[99x".to_owned())]);
+    }
+
+    #[test]
+    fn test_esc_disambiguator() {
+        let delay = Duration::from_millis(50);
+        let mut keys = EscDisambiguator::new(delay);
+        let start = Instant::now();
+        let esc = KeyEventRecord::builder().char('\x1b').build();
+
+        assert_eq!(keys.push(&esc, start), []);
+        assert_eq!(keys.timeout(start), Some(delay));
+        assert_eq!(keys.poll(start + delay / 2), None);
+        let up = KeyEventRecord::builder().char('[').build();
+        assert_eq!(keys.push(&up, start + delay / 2), []);
+        let a = KeyEventRecord::builder().char('A').build();
+        assert_eq!(
+            keys.push(&a, start + delay / 2),
+            [VtKeyEvent::new(VtKey::Up)]
+        );
+        assert!(!keys.is_pending());
+
+        assert_eq!(keys.push(&esc, start + delay), []);
+        assert_eq!(
+            keys.poll(start + delay * 2),
+            Some(VtKeyEvent::new(VtKey::Escape))
+        );
+        assert_eq!(keys.timeout(start + delay * 2), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! This module turns mouse wheel events into scroll amounts using the system settings.
+
+use std::io::Result;
+use std::ptr;
+
+use winapi::um::winuser::{
+    SystemParametersInfoW, SPI_GETWHEELSCROLLCHARS, SPI_GETWHEELSCROLLLINES, WHEEL_DELTA,
+    WHEEL_PAGESCROLL,
+};
+
+use super::{result, trace, ErrorContext, EventFlags, MouseEvent};
+
+/// The number of lines or characters scrolled per notch when the setting can't be read, which is
+/// also the default of Windows.
+const DEFAULT_SCROLL: u32 = 3;
+
+/// How far to scroll for a mouse wheel event, see [`MouseEvent::wheel_scroll_amount`].
+///
+/// Positive amounts scroll up or to the right, negative ones down or to the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelScroll {
+    /// Scroll this many lines.
+    Lines(i32),
+    /// Scroll this many screens, the user chose to scroll a page per notch.
+    Pages(i32),
+    /// Scroll this many characters horizontally.
+    Chars(i32),
+}
+
+/// Get the number of lines to scroll per notch of the vertical wheel, as set by the user.
+///
+/// `u32::MAX` (`WHEEL_PAGESCROLL`) means a page per notch.
+///
+/// This wraps
+/// [`SystemParametersInfoW`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-systemparametersinfow)
+/// with `SPI_GETWHEELSCROLLLINES`.
+pub fn system_wheel_scroll_lines() -> Result<u32> {
+    system_parameter(SPI_GETWHEELSCROLLLINES)
+}
+
+/// Get the number of characters to scroll per notch of the horizontal wheel, as set by the user.
+///
+/// This wraps
+/// [`SystemParametersInfoW`](https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-systemparametersinfow)
+/// with `SPI_GETWHEELSCROLLCHARS`.
+pub fn wheel_scroll_chars() -> Result<u32> {
+    system_parameter(SPI_GETWHEELSCROLLCHARS)
+}
+
+fn system_parameter(action: u32) -> Result<u32> {
+    let mut value: u32 = 0;
+    result(trace::call(
+        "SystemParametersInfoW",
+        ptr::null_mut(),
+        || unsafe { SystemParametersInfoW(action, 0, (&mut value as *mut u32).cast(), 0) },
+    ))
+    .with_context("SystemParametersInfoW")?;
+    Ok(value)
+}
+
+impl MouseEvent {
+    /// Get the distance the wheel was rotated, in multiples of `120` per notch, or `0` if this
+    /// isn't a wheel event.
+    ///
+    /// Positive values are rotations away from the user or to the right. Devices with a finer
+    /// resolution, like touchpads, report smaller values.
+    pub fn wheel_delta(&self) -> i16 {
+        match self.event_flags {
+            EventFlags::MouseWheeled | EventFlags::MouseHwheeled => {
+                (self.button_state.state() >> 16) as i16
+            }
+            _ => 0,
+        }
+    }
+
+    /// Get how far to scroll for this wheel event according to the wheel settings of the user,
+    /// or `None` if this isn't a wheel event.
+    ///
+    /// Each notch scrolls the lines of [`system_wheel_scroll_lines`] or the characters of
+    /// [`wheel_scroll_chars`], 3 if they can't be read. Partial notches of fine resolution
+    /// devices scroll at least one line or character.
+    pub fn wheel_scroll_amount(&self) -> Option<WheelScroll> {
+        let delta = self.wheel_delta();
+        match self.event_flags {
+            EventFlags::MouseWheeled => {
+                let lines = system_wheel_scroll_lines().unwrap_or(DEFAULT_SCROLL);
+                Some(if lines == WHEEL_PAGESCROLL {
+                    WheelScroll::Pages(scaled(delta, 1))
+                } else {
+                    WheelScroll::Lines(scaled(delta, lines))
+                })
+            }
+            EventFlags::MouseHwheeled => {
+                let chars = wheel_scroll_chars().unwrap_or(DEFAULT_SCROLL);
+                Some(WheelScroll::Chars(scaled(delta, chars)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Scale `per_notch` by the notches in `delta`, scrolling at least `1` in the direction of a
+/// non-zero delta.
+fn scaled(delta: i16, per_notch: u32) -> i32 {
+    let amount = i64::from(delta) * i64::from(per_notch) / i64::from(WHEEL_DELTA);
+    let amount = amount.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32;
+    if amount == 0 && per_notch > 0 {
+        i32::from(delta.signum())
+    } else {
+        amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scaled;
+    use crate::{ButtonState, ControlKeyState, Coord, EventFlags, MouseEvent};
+
+    #[test]
+    fn test_scaled() {
+        assert_eq!(scaled(120, 3), 3);
+        assert_eq!(scaled(-240, 3), -6);
+        assert_eq!(scaled(40, 3), 1);
+        assert_eq!(scaled(-10, 3), -1);
+        assert_eq!(scaled(120, 0), 0);
+        assert_eq!(scaled(0, 3), 0);
+    }
+
+    #[test]
+    fn test_wheel_delta() {
+        let event = |flags, state: i32| MouseEvent {
+            mouse_position: Coord::new(0, 0),
+            button_state: ButtonState::new(state as u32),
+            control_key_state: ControlKeyState::new(0),
+            event_flags: flags,
+        };
+        assert_eq!(
+            event(EventFlags::MouseWheeled, -120 << 16).wheel_delta(),
+            -120
+        );
+        assert_eq!(
+            event(EventFlags::MouseHwheeled, 240 << 16).wheel_delta(),
+            240
+        );
+        assert_eq!(event(EventFlags::MouseMoved, 1).wheel_delta(), 0);
+        assert_eq!(event(EventFlags::MouseMoved, 1).wheel_scroll_amount(), None);
+    }
+}